@@ -0,0 +1,381 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use crate::process::budget::{BudgetExhaustion, RewriteBudget};
+use crate::process::control::{EvaluationControl, RewriteExecutionError};
+use crate::process::search::{DerivationDirection, DerivationStep};
+use crate::process::strategy::{run_traced_step, RewriteProcess};
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
+
+/// Why a [`BestFirstSearch`] stopped.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BestFirstTermination {
+    /// No term was left to expand.
+    Exhausted,
+    /// A term satisfying the goal was selected for expansion.
+    GoalReached,
+    /// See [`BestFirstSearch::with_max_expansions`].
+    MaxExpansionsReached,
+    /// The budget ran out.
+    BudgetExhausted(BudgetExhaustion),
+}
+
+/// The result of [`BestFirstSearch::run`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BestFirstResult<LOS: RewritableLanguageOperatorSymbol> {
+    /// The goal term if it was reached, otherwise the visited term of lowest
+    /// cost (the first one found, among equals).
+    pub best_term: LanguageTerm<LOS>,
+    pub best_cost: usize,
+    /// From the initial term to `best_term`.
+    pub derivation: Vec<DerivationStep<LOS>>,
+    pub termination: BestFirstTermination,
+    /// How many terms the strategy was applied to.
+    pub expanded_terms: usize,
+}
+
+/// How a term was reached.
+#[allow(clippy::type_complexity)]
+struct Node<LOS: RewritableLanguageOperatorSymbol> {
+    cost: usize,
+    /// Length of the derivation from the initial term.
+    steps: usize,
+    /// The previous term and the rule chain between them, `None` for the
+    /// initial term.
+    parent: Option<(
+        LanguageTerm<LOS>,
+        Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+    )>,
+}
+
+/// Explores the terms reachable by steps of a strategy, lowest cost first.
+///
+/// By default the search is greedy best-first: the visited term of lowest
+/// `cost` is expanded next, each term being expanded at most once.  Two
+/// options change the order:
+/// - [`with_beam_width`](Self::with_beam_width) explores layer by layer, as
+///   [`RewriteProcessTracedExecutor`](super::traced::RewriteProcessTracedExecutor)
+///   does, but only keeps the cheapest terms of each layer;
+/// - [`with_goal`](Self::with_goal) searches for a term satisfying a
+///   predicate, A*-style: terms are expanded by increasing number of steps
+///   from the initial term plus `cost`, which then serves as the heuristic.
+///   If it never overestimates the number of steps left, the derivation to
+///   the goal is a shortest one.
+///
+/// ```rust,ignore
+/// let result = BestFirstSearch::new(step, tree_size)
+///     .with_max_expansions(1_000)
+///     .run(&term, &mut factory)?;
+/// // result.best_term, result.derivation
+/// ```
+#[allow(clippy::type_complexity)]
+pub struct BestFirstSearch<LOS: RewritableLanguageOperatorSymbol> {
+    strategy: RewriteProcess<LOS>,
+    cost: Box<dyn Fn(&LanguageTerm<LOS>) -> usize>,
+    goal: Option<Box<dyn Fn(&LanguageTerm<LOS>) -> bool>>,
+    beam_width: Option<usize>,
+    max_expansions: Option<usize>,
+    budget: RewriteBudget,
+    check_signatures: bool,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> BestFirstSearch<LOS> {
+    /// Creates an unbounded greedy best-first search over the steps of
+    /// `strategy`, ordered by `cost`, e.g.
+    /// [`tree_size`](crate::metrics::builtin::tree_size).
+    pub fn new(
+        strategy: RewriteProcess<LOS>,
+        cost: impl Fn(&LanguageTerm<LOS>) -> usize + 'static,
+    ) -> Self {
+        Self {
+            strategy,
+            cost: Box::new(cost),
+            goal: None,
+            beam_width: None,
+            max_expansions: None,
+            budget: RewriteBudget::new(),
+            check_signatures: false,
+        }
+    }
+
+    /// Stops at the first term satisfying `goal`, expanding terms by
+    /// increasing number of steps plus cost.
+    pub fn with_goal(mut self, goal: impl Fn(&LanguageTerm<LOS>) -> bool + 'static) -> Self {
+        self.goal = Some(Box::new(goal));
+        self
+    }
+
+    /// Beam search: each layer only keeps the `beam_width` first new terms in
+    /// the search order.
+    pub fn with_beam_width(mut self, beam_width: usize) -> Self {
+        self.beam_width = Some(beam_width);
+        self
+    }
+
+    /// Stops after the strategy was applied to `max_expansions` terms.
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = Some(max_expansions);
+        self
+    }
+
+    /// Limits the whole search with `budget`.  The frontier size limit
+    /// applies to the terms waiting to be expanded.
+    pub fn with_budget(mut self, budget: RewriteBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// See
+    /// [`RewriteProcessTracedExecutor::with_signature_checking`](super::traced::RewriteProcessTracedExecutor::with_signature_checking).
+    pub fn with_signature_checking(mut self, enabled: bool) -> Self {
+        self.check_signatures = enabled;
+        self
+    }
+
+    /// Searches from `initial`.
+    ///
+    /// Returns an error if the strategy fails, e.g. on an ill-formed rule
+    /// output when signature checking is enabled.
+    pub fn run(
+        &self,
+        initial: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<BestFirstResult<LOS>, RewriteExecutionError<LOS>> {
+        let mut exploration = Exploration {
+            search: self,
+            control: EvaluationControl::new(self.check_signatures)
+                .with_budget(self.budget.clone(), 0),
+            nodes: HashMap::new(),
+            best: initial.clone(),
+            expanded_terms: 0,
+        };
+        exploration.nodes.insert(
+            initial.clone(),
+            Node {
+                cost: (self.cost)(initial),
+                steps: 0,
+                parent: None,
+            },
+        );
+        let termination = match self.beam_width {
+            None => exploration.run_queue(initial, factory)?,
+            Some(beam_width) => exploration.run_beam(initial, beam_width, factory)?,
+        };
+        Ok(exploration.into_result(termination))
+    }
+}
+
+/// The state of one [`BestFirstSearch::run`].
+struct Exploration<'s, LOS: RewritableLanguageOperatorSymbol> {
+    search: &'s BestFirstSearch<LOS>,
+    control: EvaluationControl<LOS>,
+    nodes: HashMap<LanguageTerm<LOS>, Node<LOS>>,
+    /// The goal term once reached, otherwise the cheapest term.
+    best: LanguageTerm<LOS>,
+    expanded_terms: usize,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> Exploration<'_, LOS> {
+    /// The order in which terms are expanded, lowest first.
+    fn priority(&self, term: &LanguageTerm<LOS>) -> usize {
+        let node = &self.nodes[term];
+        match self.search.goal {
+            None => node.cost,
+            Some(_) => node.steps + node.cost,
+        }
+    }
+
+    fn is_goal(&self, term: &LanguageTerm<LOS>) -> bool {
+        self.search.goal.as_ref().is_some_and(|goal| goal(term))
+    }
+
+    fn exceeds_frontier_size(&mut self, frontier_size: usize) -> bool {
+        if self
+            .search
+            .budget
+            .max_frontier_size
+            .is_some_and(|max| frontier_size > max)
+        {
+            self.control.exhaust(BudgetExhaustion::FrontierSize);
+            return true;
+        }
+        false
+    }
+
+    /// Applies the strategy to `term` and records the results reached for
+    /// the first time, or, when searching for a goal, in fewer steps.
+    /// Returns these results, or `None` if the budget ran out.
+    fn expand(
+        &mut self,
+        term: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<Option<Vec<LanguageTerm<LOS>>>, RewriteExecutionError<LOS>> {
+        self.expanded_terms += 1;
+        let results = run_traced_step(
+            &self.search.strategy,
+            term,
+            term,
+            &PositionInLanguageTerm::get_root_position(),
+            &PositionInRewriteProcess::get_root_position(),
+            factory,
+            &mut self.control,
+        );
+        if let Some(error) = self.control.take_error() {
+            return Err(error);
+        }
+        if self.control.get_exhaustion().is_some() {
+            return Ok(None);
+        }
+        let steps = self.nodes[term].steps + 1;
+        let mut reached = Vec::new();
+        for (rule_chain, result) in results {
+            let parent = Some((term.clone(), rule_chain));
+            match self.nodes.get_mut(&result) {
+                None => {
+                    let cost = (self.search.cost)(&result);
+                    if cost < self.nodes[&self.best].cost {
+                        self.best = result.clone();
+                    }
+                    self.nodes.insert(
+                        result.clone(),
+                        Node {
+                            cost,
+                            steps,
+                            parent,
+                        },
+                    );
+                    reached.push(result);
+                }
+                Some(node) if self.search.goal.is_some() && steps < node.steps => {
+                    node.steps = steps;
+                    node.parent = parent;
+                    reached.push(result);
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(Some(reached))
+    }
+
+    fn run_queue(
+        &mut self,
+        initial: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<BestFirstTermination, RewriteExecutionError<LOS>> {
+        // Among equal priorities, the term queued first comes first.
+        let mut queue = BinaryHeap::new();
+        let mut queued = 0_usize;
+        queue.push(Reverse((self.priority(initial), queued, initial.clone())));
+        while let Some(Reverse((priority, _, term))) = queue.pop() {
+            // The term was queued again since, with fewer steps.
+            if priority != self.priority(&term) {
+                continue;
+            }
+            if self.is_goal(&term) {
+                self.best = term;
+                return Ok(BestFirstTermination::GoalReached);
+            }
+            if self
+                .search
+                .max_expansions
+                .is_some_and(|max| self.expanded_terms >= max)
+            {
+                return Ok(BestFirstTermination::MaxExpansionsReached);
+            }
+            let reached = match self.expand(&term, factory)? {
+                Some(reached) => reached,
+                None => break,
+            };
+            for result in reached {
+                queued += 1;
+                queue.push(Reverse((self.priority(&result), queued, result)));
+            }
+            if self.exceeds_frontier_size(queue.len()) {
+                break;
+            }
+        }
+        Ok(match self.control.take_exhaustion() {
+            Some(exhaustion) => BestFirstTermination::BudgetExhausted(exhaustion),
+            None => BestFirstTermination::Exhausted,
+        })
+    }
+
+    fn run_beam(
+        &mut self,
+        initial: &LanguageTerm<LOS>,
+        beam_width: usize,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<BestFirstTermination, RewriteExecutionError<LOS>> {
+        let mut layer = vec![initial.clone()];
+        'layers: while !layer.is_empty() {
+            if let Some(goal) = layer.iter().find(|term| self.is_goal(term)) {
+                self.best = goal.clone();
+                return Ok(BestFirstTermination::GoalReached);
+            }
+            let mut next_layer = Vec::new();
+            for term in &layer {
+                if self
+                    .search
+                    .max_expansions
+                    .is_some_and(|max| self.expanded_terms >= max)
+                {
+                    return Ok(BestFirstTermination::MaxExpansionsReached);
+                }
+                match self.expand(term, factory)? {
+                    Some(reached) => next_layer.extend(reached),
+                    None => break 'layers,
+                }
+                if self.exceeds_frontier_size(next_layer.len()) {
+                    break 'layers;
+                }
+            }
+            // A stable sort: among equal priorities, the term reached first.
+            next_layer.sort_by_key(|term| self.priority(term));
+            next_layer.truncate(beam_width);
+            layer = next_layer;
+        }
+        Ok(match self.control.take_exhaustion() {
+            Some(exhaustion) => BestFirstTermination::BudgetExhausted(exhaustion),
+            None => BestFirstTermination::Exhausted,
+        })
+    }
+
+    fn into_result(self, termination: BestFirstTermination) -> BestFirstResult<LOS> {
+        let mut derivation = Vec::new();
+        let mut current = self.best.clone();
+        while let Some((parent, rule_chain)) = &self.nodes[&current].parent {
+            derivation.push(DerivationStep {
+                source: parent.clone(),
+                rule_chain: rule_chain.clone(),
+                target: current.clone(),
+                direction: DerivationDirection::Forward,
+            });
+            current = parent.clone();
+        }
+        derivation.reverse();
+        BestFirstResult {
+            best_cost: self.nodes[&self.best].cost,
+            best_term: self.best,
+            derivation,
+            termination,
+            expanded_terms: self.expanded_terms,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol};
+
+/// Resource limits for an execution of a
+/// [`RewriteProcess`](super::strategy::RewriteProcess).
+///
+/// Every limit is optional; [`RewriteBudget::new`] is unlimited.  The engine
+/// checks the budget before every rule application and at every iteration of
+/// a `Repeat`, so even a non-terminating strategy returns once the budget is
+/// exhausted.
+#[derive(Clone, Debug, Default)]
+pub struct RewriteBudget {
+    pub(crate) max_rule_applications: Option<usize>,
+    pub(crate) max_frontier_size: Option<usize>,
+    pub(crate) max_term_size: Option<usize>,
+    deadline: Option<Instant>,
+    cancellation_flag: Option<Arc<AtomicBool>>,
+}
+
+impl RewriteBudget {
+    /// Creates a budget without any limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the total number of rule applications (rules that fired).
+    pub fn with_max_rule_applications(mut self, max: usize) -> Self {
+        self.max_rule_applications = Some(max);
+        self
+    }
+
+    /// Limits the number of terms held at once: the frontier of the traced
+    /// executor, or any intermediate result set of the untraced executor.
+    pub fn with_max_frontier_size(mut self, max: usize) -> Self {
+        self.max_frontier_size = Some(max);
+        self
+    }
+
+    /// Limits the size ([`tree_size`](crate::metrics::builtin::tree_size)) of
+    /// the whole term produced by a rule application.
+    pub fn with_max_term_size(mut self, max: usize) -> Self {
+        self.max_term_size = Some(max);
+        self
+    }
+
+    /// Stops the execution once `deadline` has passed.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stops the execution once `timeout` has elapsed from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Stops the execution as soon as `flag` is set, e.g. from another thread.
+    pub fn with_cancellation_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancellation_flag = Some(flag);
+        self
+    }
+
+    /// Checks the limits that do not depend on the execution's progress: the
+    /// cancellation flag and the deadline.
+    pub(crate) fn check_interruption(&self) -> Option<BudgetExhaustion> {
+        if let Some(flag) = &self.cancellation_flag {
+            if flag.load(Ordering::Relaxed) {
+                return Some(BudgetExhaustion::Cancelled);
+            }
+        }
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Some(BudgetExhaustion::DeadlineReached);
+            }
+        }
+        None
+    }
+}
+
+/// Which limit of a [`RewriteBudget`] stopped an execution.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BudgetExhaustion {
+    /// See [`RewriteBudget::with_max_rule_applications`].
+    RuleApplications,
+    /// See [`RewriteBudget::with_max_frontier_size`].
+    FrontierSize,
+    /// See [`RewriteBudget::with_max_term_size`].
+    TermSize,
+    /// See [`RewriteBudget::with_deadline`].
+    DeadlineReached,
+    /// See [`RewriteBudget::with_cancellation_flag`].
+    Cancelled,
+}
+
+impl fmt::Display for BudgetExhaustion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetExhaustion::RuleApplications => {
+                write!(f, "maximum number of rule applications reached")
+            }
+            BudgetExhaustion::FrontierSize => write!(f, "maximum frontier size exceeded"),
+            BudgetExhaustion::TermSize => write!(f, "maximum term size exceeded"),
+            BudgetExhaustion::DeadlineReached => write!(f, "deadline reached"),
+            BudgetExhaustion::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// The result of an execution under a [`RewriteBudget`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RewriteOutcome<LOS: RewritableLanguageOperatorSymbol> {
+    /// The strategy ran to completion within the budget.
+    Finished(Vec<LanguageTerm<LOS>>),
+    /// The budget ran out.  `partial_results` holds the terms reached so far;
+    /// their meaning depends on the executor.
+    BudgetExhausted {
+        exhaustion: BudgetExhaustion,
+        partial_results: Vec<LanguageTerm<LOS>>,
+    },
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> RewriteOutcome<LOS> {
+    /// Whether the strategy ran to completion within the budget.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, RewriteOutcome::Finished(_))
+    }
+
+    /// The results, or the partial results if the budget ran out.
+    pub fn get_terms(&self) -> &[LanguageTerm<LOS>] {
+        match self {
+            RewriteOutcome::Finished(terms) => terms,
+            RewriteOutcome::BudgetExhausted {
+                partial_results, ..
+            } => partial_results,
+        }
+    }
+
+    /// Same as [`get_terms`](Self::get_terms), by value.
+    pub fn into_terms(self) -> Vec<LanguageTerm<LOS>> {
+        match self {
+            RewriteOutcome::Finished(terms) => terms,
+            RewriteOutcome::BudgetExhausted {
+                partial_results, ..
+            } => partial_results,
+        }
+    }
+}
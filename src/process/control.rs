@@ -0,0 +1,429 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::fmt;
+use std::rc::Rc;
+
+use crate::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use crate::process::budget::{BudgetExhaustion, RewriteBudget};
+use crate::process::environment::StrategyEnvironment;
+use crate::rule::RewriteRule;
+use crate::rules::util::ordering::TermOrdering;
+use crate::term::syntax::{
+    validate_term, IllFormedPosition, LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory,
+};
+
+/// A rule produced a term that does not respect the operators' arities.
+///
+/// Reported by the executors when signature checking is enabled.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RuleOutputSignatureViolation<LOS: RewritableLanguageOperatorSymbol> {
+    /// The [`get_desc`](RewriteRule::get_desc) of the offending rule.
+    pub rule_desc: String,
+    /// Where the rule fired, in the term being rewritten.
+    pub position: PositionInLanguageTerm,
+    /// The sub-term the rule was applied to.
+    pub redex: LanguageTerm<LOS>,
+    /// The ill-formed term the rule returned.
+    pub output: LanguageTerm<LOS>,
+    /// Every ill-formed position, relative to `output`.
+    pub ill_formed_positions: Vec<IllFormedPosition<LOS>>,
+}
+
+/// A rule application whose output is not smaller than the sub-term it
+/// rewrote, under the ordering of a termination monitor.
+///
+/// Reported by the executors when a termination monitor is set (see
+/// [`with_termination_monitor`](super::traced::RewriteProcessTracedExecutor::with_termination_monitor)),
+/// and by [`TerminationMonitoredRule`](crate::rules::combinators::monitored::TerminationMonitoredRule).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TerminationViolation<LOS: RewritableLanguageOperatorSymbol> {
+    /// The [`get_desc`](RewriteRule::get_desc) of the offending rule.
+    pub rule_desc: String,
+    /// Where the rule fired, in the term being rewritten.
+    pub position: PositionInLanguageTerm,
+    /// The sub-term the rule was applied to.
+    pub redex: LanguageTerm<LOS>,
+    /// The term the rule returned, which is not smaller than `redex`.
+    pub output: LanguageTerm<LOS>,
+}
+
+/// One rule firing in a [`RewriteCycle`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CycleRuleApplication {
+    /// The [`get_desc`](RewriteRule::get_desc) of the rule.
+    pub rule_desc: String,
+    /// Where the rule fired, in the whole term.
+    pub position: PositionInLanguageTerm,
+}
+
+/// A loop witness: rewriting came back to a term it had already gone through
+/// on the same branch, so it would never terminate.
+///
+/// Reported by the executors when cycle detection is enabled.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RewriteCycle<LOS: RewritableLanguageOperatorSymbol> {
+    /// The whole terms along the cycle; the last one is equal to the first.
+    pub terms: Vec<LanguageTerm<LOS>>,
+    /// `rule_chains[i]` holds the rules that rewrote `terms[i]` into
+    /// `terms[i + 1]`, in the order they fired, or `None` if the untraced
+    /// executor could not replay that step (through a nested `Repeat`, or
+    /// once the budget ran out).
+    pub rule_chains: Vec<Option<Vec<CycleRuleApplication>>>,
+}
+
+/// An error that aborts an execution of a
+/// [`RewriteProcess`](super::strategy::RewriteProcess).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RewriteExecutionError<LOS: RewritableLanguageOperatorSymbol> {
+    /// See [`RuleOutputSignatureViolation`].
+    IllFormedRuleOutput(RuleOutputSignatureViolation<LOS>),
+    /// The [`RewriteBudget`] ran out.  Only returned by the methods that do not
+    /// report partial results through a
+    /// [`RewriteOutcome`](super::budget::RewriteOutcome).
+    BudgetExhausted(BudgetExhaustion),
+    /// See [`RewriteCycle`].
+    CycleDetected(RewriteCycle<LOS>),
+    /// A [`Call`](super::strategy::RewriteProcess::Call) to a name that is not
+    /// defined in the enclosing [`Let`](super::strategy::RewriteProcess::Let),
+    /// or outside of any `Let`.
+    UnboundStrategyName(String),
+    /// See [`TerminationViolation`].
+    NonDecreasingRuleApplication(TerminationViolation<LOS>),
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> fmt::Display for RewriteExecutionError<LOS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RewriteExecutionError::IllFormedRuleOutput(violation) => {
+                write!(
+                    f,
+                    "rule '{}' fired at position {} and produced an ill-formed term",
+                    violation.rule_desc, violation.position
+                )?;
+                for ill_formed in &violation.ill_formed_positions {
+                    write!(f, "; at {}: {}", ill_formed.position, ill_formed.mismatch)?;
+                }
+                Ok(())
+            }
+            RewriteExecutionError::BudgetExhausted(exhaustion) => {
+                write!(f, "rewriting budget exhausted: {}", exhaustion)
+            }
+            RewriteExecutionError::CycleDetected(cycle) => {
+                write!(
+                    f,
+                    "rewriting cycle of length {} detected",
+                    cycle.rule_chains.len()
+                )?;
+                for (i, chain) in cycle.rule_chains.iter().enumerate() {
+                    write!(f, "{} step {}:", if i == 0 { ":" } else { ";" }, i)?;
+                    let Some(chain) = chain else {
+                        write!(f, " not replayed")?;
+                        continue;
+                    };
+                    for application in chain {
+                        write!(
+                            f,
+                            " '{}' at {}",
+                            application.rule_desc, application.position
+                        )?;
+                    }
+                }
+                Ok(())
+            }
+            RewriteExecutionError::UnboundStrategyName(name) => {
+                write!(f, "call to the undefined strategy '{}'", name)
+            }
+            RewriteExecutionError::NonDecreasingRuleApplication(violation) => write!(
+                f,
+                "rule '{}' fired at position {} without decreasing the rewritten sub-term",
+                violation.rule_desc, violation.position
+            ),
+        }
+    }
+}
+
+/// A rule that fired in a traced evaluation, as recorded by
+/// [`EvaluationControl::with_firing_record`].
+pub(crate) struct RuleFiring<LOS: RewritableLanguageOperatorSymbol> {
+    pub(crate) strategy_position: PositionInRewriteProcess,
+    pub(crate) term_position: PositionInLanguageTerm,
+    pub(crate) redex: LanguageTerm<LOS>,
+    pub(crate) output: LanguageTerm<LOS>,
+}
+
+/// Mutable state threaded through the strategy evaluators.
+///
+/// Every rule application goes through [`apply_rule`](Self::apply_rule), which
+/// runs the opt-in checks and enforces the [`RewriteBudget`].  Once an error or
+/// an exhaustion is recorded, no further rule fires and the evaluators unwind;
+/// the executor then reports it instead of (or alongside) the results.
+pub(crate) struct EvaluationControl<LOS: RewritableLanguageOperatorSymbol> {
+    check_signatures: bool,
+    detect_cycles: bool,
+    budget: RewriteBudget,
+    rule_applications: usize,
+    error: Option<RewriteExecutionError<LOS>>,
+    exhaustion: Option<BudgetExhaustion>,
+    /// Every rule output must be smaller than its input under this ordering.
+    termination_monitor: Option<Rc<dyn TermOrdering<LOS>>>,
+    /// The environment of the innermost enclosing `Let`.
+    environment: Option<Rc<StrategyEnvironment<LOS>>>,
+    /// Every rule that fired in the traced evaluators, in evaluation order.
+    firings: Option<Vec<RuleFiring<LOS>>>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> EvaluationControl<LOS> {
+    pub(crate) fn new(check_signatures: bool) -> Self {
+        Self {
+            check_signatures,
+            detect_cycles: false,
+            budget: RewriteBudget::new(),
+            rule_applications: 0,
+            error: None,
+            exhaustion: None,
+            termination_monitor: None,
+            environment: None,
+            firings: None,
+        }
+    }
+
+    /// Enforces `budget`, of which `rule_applications` were already spent.
+    pub(crate) fn with_budget(mut self, budget: RewriteBudget, rule_applications: usize) -> Self {
+        self.budget = budget;
+        self.rule_applications = rule_applications;
+        self
+    }
+
+    pub(crate) fn with_cycle_detection(mut self, enabled: bool) -> Self {
+        self.detect_cycles = enabled;
+        self
+    }
+
+    pub(crate) fn with_termination_monitor(
+        mut self,
+        termination_monitor: Option<Rc<dyn TermOrdering<LOS>>>,
+    ) -> Self {
+        self.termination_monitor = termination_monitor;
+        self
+    }
+
+    /// Records the rules that fire in [`run_traced_step`], with the sub-term
+    /// each one rewrote and its output, for [`take_firings`](Self::take_firings).
+    ///
+    /// [`run_traced_step`]: crate::process::strategy::run_traced_step
+    pub(crate) fn with_firing_record(mut self) -> Self {
+        self.firings = Some(Vec::new());
+        self
+    }
+
+    pub(crate) fn record_firing(
+        &mut self,
+        strategy_position: &PositionInRewriteProcess,
+        term_position: &PositionInLanguageTerm,
+        redex: &LanguageTerm<LOS>,
+        output: &LanguageTerm<LOS>,
+    ) {
+        if let Some(firings) = &mut self.firings {
+            firings.push(RuleFiring {
+                strategy_position: strategy_position.clone(),
+                term_position: term_position.clone(),
+                redex: redex.clone(),
+                output: output.clone(),
+            });
+        }
+    }
+
+    pub(crate) fn take_firings(&mut self) -> Vec<RuleFiring<LOS>> {
+        self.firings.take().unwrap_or_default()
+    }
+
+    pub(crate) fn get_environment(&self) -> Option<Rc<StrategyEnvironment<LOS>>> {
+        self.environment.clone()
+    }
+
+    /// Sets the environment in which `Call`s are resolved, returning the
+    /// previous one so that it can be restored when leaving a `Let`.
+    pub(crate) fn replace_environment(
+        &mut self,
+        environment: Option<Rc<StrategyEnvironment<LOS>>>,
+    ) -> Option<Rc<StrategyEnvironment<LOS>>> {
+        std::mem::replace(&mut self.environment, environment)
+    }
+
+    pub(crate) fn report_unbound_name(&mut self, name: &str) {
+        if self.error.is_none() {
+            self.error = Some(RewriteExecutionError::UnboundStrategyName(name.to_string()));
+        }
+    }
+
+    pub(crate) fn detects_cycles(&self) -> bool {
+        self.detect_cycles
+    }
+
+    pub(crate) fn report_cycle(&mut self, cycle: RewriteCycle<LOS>) {
+        if self.error.is_none() {
+            self.error = Some(RewriteExecutionError::CycleDetected(cycle));
+        }
+    }
+
+    pub(crate) fn take_error(&mut self) -> Option<RewriteExecutionError<LOS>> {
+        self.error.take()
+    }
+
+    pub(crate) fn take_exhaustion(&mut self) -> Option<BudgetExhaustion> {
+        self.exhaustion.take()
+    }
+
+    pub(crate) fn get_exhaustion(&self) -> Option<BudgetExhaustion> {
+        self.exhaustion
+    }
+
+    /// Number of rules that fired, including the ones spent before
+    /// [`with_budget`](Self::with_budget).
+    pub(crate) fn get_rule_applications(&self) -> usize {
+        self.rule_applications
+    }
+
+    /// Whether the evaluators must stop.  Also polls the deadline and the
+    /// cancellation flag, so that loops which do not fire any rule stop too.
+    pub(crate) fn poll_halt(&mut self) -> bool {
+        if self.error.is_some() || self.exhaustion.is_some() {
+            return true;
+        }
+        self.exhaustion = self.budget.check_interruption();
+        self.exhaustion.is_some()
+    }
+
+    /// Truncates `results` to the maximum frontier size, recording the
+    /// exhaustion if anything was cut.
+    pub(crate) fn limit_results<T>(&mut self, mut results: Vec<T>) -> Vec<T> {
+        if let Some(max) = self.budget.max_frontier_size {
+            if results.len() > max {
+                results.truncate(max);
+                self.exhaust(BudgetExhaustion::FrontierSize);
+            }
+        }
+        results
+    }
+
+    pub(crate) fn exhaust(&mut self, exhaustion: BudgetExhaustion) {
+        if self.exhaustion.is_none() {
+            self.exhaustion = Some(exhaustion);
+        }
+    }
+
+    pub(crate) fn apply_rule(
+        &mut self,
+        rule: &dyn RewriteRule<LOS>,
+        term: &LanguageTerm<LOS>,
+        context_term: &LanguageTerm<LOS>,
+        position: &PositionInLanguageTerm,
+        factory: &mut TermFactory<LOS>,
+    ) -> Option<LanguageTerm<LOS>> {
+        if self.poll_halt() {
+            return None;
+        }
+        let output = rule.try_apply(term, context_term, position, factory)?;
+        // Only rules that fire count; the one firing beyond the limit is
+        // discarded.
+        if self
+            .budget
+            .max_rule_applications
+            .is_some_and(|max| self.rule_applications >= max)
+        {
+            self.exhaust(BudgetExhaustion::RuleApplications);
+            return None;
+        }
+        self.rule_applications += 1;
+        if let Some(max) = self.budget.max_term_size {
+            if size_after_replacement_exceeds(context_term, position, &output, max) {
+                self.exhaust(BudgetExhaustion::TermSize);
+                return None;
+            }
+        }
+        if self.check_signatures {
+            if let Err(ill_formed_positions) = validate_term(&output) {
+                self.error = Some(RewriteExecutionError::IllFormedRuleOutput(
+                    RuleOutputSignatureViolation {
+                        rule_desc: rule.get_desc(),
+                        position: position.clone(),
+                        redex: term.clone(),
+                        output,
+                        ill_formed_positions,
+                    },
+                ));
+                return None;
+            }
+        }
+        if let Some(ordering) = &self.termination_monitor {
+            if !ordering.is_greater(term, &output) {
+                self.error = Some(RewriteExecutionError::NonDecreasingRuleApplication(
+                    TerminationViolation {
+                        rule_desc: rule.get_desc(),
+                        position: position.clone(),
+                        redex: term.clone(),
+                        output,
+                    },
+                ));
+                return None;
+            }
+        }
+        Some(output)
+    }
+}
+
+/// Whether `context` with `replacement` at `position` has more than `max`
+/// nodes.  Counting stops as soon as `max` is exceeded, so this is cheap even
+/// when the tree is exponentially larger than its hash-consed DAG.
+fn size_after_replacement_exceeds<LOS: RewritableLanguageOperatorSymbol>(
+    context: &LanguageTerm<LOS>,
+    position: &PositionInLanguageTerm,
+    replacement: &LanguageTerm<LOS>,
+    max: usize,
+) -> bool {
+    let mut remaining = max;
+    let mut current = context;
+    for &n in position.get_absolute_coordinates_from_root() {
+        // The node on the path itself.
+        if remaining == 0 {
+            return true;
+        }
+        remaining -= 1;
+        for (i, sibling) in current.sub_terms.iter().enumerate() {
+            if i != n && !consume_tree_size(sibling, &mut remaining) {
+                return true;
+            }
+        }
+        current = &current.sub_terms[n];
+    }
+    !consume_tree_size(replacement, &mut remaining)
+}
+
+/// Subtracts the tree size of `term` from `remaining`; returns `false`,
+/// leaving `remaining` unspecified, if it does not fit.
+fn consume_tree_size<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+    remaining: &mut usize,
+) -> bool {
+    if *remaining == 0 {
+        return false;
+    }
+    *remaining -= 1;
+    term.sub_terms
+        .iter()
+        .all(|sub| consume_tree_size(sub, remaining))
+}
@@ -0,0 +1,578 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A concrete syntax for [`RewriteProcess`]es.
+//!
+//! ```text
+//! process := rule:NAME | call:NAME
+//!          | any_child(SIBLING, DEPTH, process)      SIBLING := lm | rm
+//!          | all_children(process)                   DEPTH   := out | inn
+//!          | one_child(process) | some_children(process)
+//!          | pipe(process, process, ...)             right-nested: pipe(a, pipe(b, c))
+//!          | repeat(process)
+//!          | try_one(process, ...) | try_all(process, ...)
+//!          | topdown(process) | bottomup(process)
+//!          | outermost_step(process) | innermost_step(process)
+//!          | parallel_outermost_step(process)
+//!          | let(NAME = process, ...; process)
+//! ```
+//!
+//! A `NAME` is either a bare token (any characters but whitespace and
+//! `( ) , ; = # " :`) or a double-quoted string in which `\"` and `\\` are
+//! escaped.  Whitespace is free and `#` starts a comment that runs to the end
+//! of the line.
+//!
+//! `outermost_step(p)` and `innermost_step(p)` are shorthands for their
+//! encodings with `try_one` and `any_child` (see
+//! [`RewriteProcess::outermost_step`]), which is how they are printed back.
+//!
+//! [`parse_process`] resolves rule names through a [`RewriteRuleRegistry`];
+//! [`print_process`] and [`print_annotated_process`] render a process back,
+//! naming each rule by its [`get_desc`](RewriteRule::get_desc).
+
+use std::fmt;
+use std::rc::Rc;
+
+use crate::position::PositionInRewriteProcess;
+use crate::process::environment::{StrategyEnvironment, StrategyEnvironmentError};
+use crate::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder, Traversal};
+use crate::rule::RewriteRule;
+use crate::term::parser::TextCursor;
+use crate::term::syntax::RewritableLanguageOperatorSymbol;
+
+/// Turns the name of a rule back into a rule.
+///
+/// This is the user-supplied half of [`parse_process`].  As rules are not
+/// `Clone`, every occurrence of `rule:NAME` asks for a new instance.
+///
+/// To parse what [`print_process`] prints, accept the
+/// [`get_desc`](RewriteRule::get_desc) of each rule.
+pub trait RewriteRuleRegistry<LOS: RewritableLanguageOperatorSymbol> {
+    /// Returns a new instance of the rule named `name`, or `None` if it is
+    /// unknown.
+    fn make_rule(&self, name: &str) -> Option<Box<dyn RewriteRule<LOS>>>;
+}
+
+/// What went wrong while parsing a process.  See [`StrategyParsingError`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum StrategyParsingErrorKind {
+    /// The input ended while a process or a delimiter was expected.
+    UnexpectedEndOfInput,
+    /// A character that cannot appear at this point of the input.
+    UnexpectedCharacter(char),
+    /// Neither a combinator nor `rule:` or `call:`.
+    UnknownCombinator(String),
+    /// The name was rejected by the [`RewriteRuleRegistry`].
+    UnknownRule(String),
+    /// Not one of `lm`, `rm`, `out` and `inn`, in `any_child`.
+    UnknownOption(String),
+    /// The combinator was given a number of processes it does not accept;
+    /// `expected` is a minimum for `pipe`.
+    ArgumentCountMismatch {
+        combinator: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A `let` is ill-defined, or a name is called outside of any `let`.
+    Environment(StrategyEnvironmentError),
+}
+
+/// Error returned by [`parse_process`], located at a 1-based line and column.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct StrategyParsingError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: StrategyParsingErrorKind,
+}
+
+impl fmt::Display for StrategyParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            StrategyParsingErrorKind::UnexpectedEndOfInput => write!(f, "unexpected end of input"),
+            StrategyParsingErrorKind::UnexpectedCharacter(c) => {
+                write!(f, "unexpected character '{}'", c)
+            }
+            StrategyParsingErrorKind::UnknownCombinator(token) => {
+                write!(f, "unknown combinator '{}'", token)
+            }
+            StrategyParsingErrorKind::UnknownRule(name) => write!(f, "unknown rule '{}'", name),
+            StrategyParsingErrorKind::UnknownOption(token) => {
+                write!(f, "unknown option '{}'", token)
+            }
+            StrategyParsingErrorKind::ArgumentCountMismatch {
+                combinator,
+                expected,
+                found,
+            } => write!(
+                f,
+                "combinator '{}' expects {} process(es) but was given {}",
+                combinator, expected, found
+            ),
+            StrategyParsingErrorKind::Environment(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Parses a process written in the syntax of this module.
+///
+/// Calls are only allowed inside a `let`, whose definitions are checked as by
+/// [`StrategyEnvironment::new`] and body as by [`RewriteProcess::let_in`].
+pub fn parse_process<LOS: RewritableLanguageOperatorSymbol>(
+    input: &str,
+    registry: &dyn RewriteRuleRegistry<LOS>,
+) -> Result<RewriteProcess<LOS>, StrategyParsingError> {
+    let mut parser = StrategyParser {
+        cursor: TextCursor::new(input),
+        registry,
+        let_depth: 0,
+    };
+    let process = parser.parse_process_rec()?;
+    parser.skip_blanks();
+    match parser.cursor.peek() {
+        None => Ok(process),
+        Some(c) => Err(parser.error(StrategyParsingErrorKind::UnexpectedCharacter(c))),
+    }
+}
+
+struct StrategyParser<'a, 'r, LOS: RewritableLanguageOperatorSymbol> {
+    cursor: TextCursor<'a>,
+    registry: &'r dyn RewriteRuleRegistry<LOS>,
+    /// Number of enclosing `let`s.
+    let_depth: usize,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> StrategyParser<'_, '_, LOS> {
+    fn parse_process_rec(&mut self) -> Result<RewriteProcess<LOS>, StrategyParsingError> {
+        self.skip_blanks();
+        let (line, column) = self.cursor.location();
+        let located = |kind| StrategyParsingError { line, column, kind };
+        let head = self.take_token(true);
+        if head.is_empty() {
+            return Err(self.unexpected());
+        }
+        if self.cursor.peek() == Some(':') {
+            self.cursor.next();
+            let name = self.take_name()?;
+            return match head.as_str() {
+                "rule" => match self.registry.make_rule(&name) {
+                    Some(rule) => Ok(RewriteProcess::Rule(rule)),
+                    None => Err(located(StrategyParsingErrorKind::UnknownRule(name))),
+                },
+                "call" if self.let_depth == 0 => Err(located(
+                    StrategyParsingErrorKind::Environment(StrategyEnvironmentError::UnboundName {
+                        in_definition: None,
+                        name,
+                    }),
+                )),
+                "call" => Ok(RewriteProcess::Call(name)),
+                _ => Err(located(StrategyParsingErrorKind::UnknownCombinator(head))),
+            };
+        }
+        let traversal = match head.as_str() {
+            "topdown" => Some(Traversal::TopDown),
+            "bottomup" => Some(Traversal::BottomUp),
+            "parallel_outermost_step" => Some(Traversal::ParallelOutermostStep),
+            _ => None,
+        };
+        match head.as_str() {
+            "any_child" => {
+                self.expect('(')?;
+                let sibling_order = match self.take_option()?.as_str() {
+                    "lm" => SiblingOrder::Leftmost,
+                    "rm" => SiblingOrder::Rightmost,
+                    option => return Err(self.unknown_option(option)),
+                };
+                self.expect(',')?;
+                let depth_order = match self.take_option()?.as_str() {
+                    "out" => DepthOrder::Outermost,
+                    "inn" => DepthOrder::Innermost,
+                    option => return Err(self.unknown_option(option)),
+                };
+                self.expect(',')?;
+                let process = self.parse_process_rec()?;
+                self.expect(')')?;
+                Ok(RewriteProcess::AnyChild(
+                    sibling_order,
+                    depth_order,
+                    Box::new(process),
+                ))
+            }
+            "all_children"
+            | "one_child"
+            | "some_children"
+            | "repeat"
+            | "topdown"
+            | "bottomup"
+            | "outermost_step"
+            | "innermost_step"
+            | "parallel_outermost_step" => {
+                let mut arguments = self.parse_arguments()?;
+                if arguments.len() != 1 {
+                    return Err(located(StrategyParsingErrorKind::ArgumentCountMismatch {
+                        combinator: head,
+                        expected: 1,
+                        found: arguments.len(),
+                    }));
+                }
+                let process = Box::new(arguments.remove(0));
+                Ok(match (head.as_str(), traversal) {
+                    (_, Some(traversal)) => RewriteProcess::Traverse(traversal, process),
+                    ("all_children", _) => RewriteProcess::AllChildren(process),
+                    ("one_child", _) => RewriteProcess::OneChild(process),
+                    ("outermost_step", _) => RewriteProcess::outermost_step(*process),
+                    ("innermost_step", _) => RewriteProcess::innermost_step(*process),
+                    ("some_children", _) => RewriteProcess::SomeChildren(process),
+                    _ => RewriteProcess::Repeat(process),
+                })
+            }
+            "pipe" => {
+                let arguments = self.parse_arguments()?;
+                if arguments.len() < 2 {
+                    return Err(located(StrategyParsingErrorKind::ArgumentCountMismatch {
+                        combinator: head,
+                        expected: 2,
+                        found: arguments.len(),
+                    }));
+                }
+                let mut arguments = arguments.into_iter().rev();
+                let last = arguments.next().unwrap();
+                Ok(arguments.fold(last, |b, a| RewriteProcess::Pipe(Box::new(a), Box::new(b))))
+            }
+            "try_one" => Ok(RewriteProcess::TryOnePath(self.parse_arguments()?)),
+            "try_all" => Ok(RewriteProcess::TryAllPaths(self.parse_arguments()?)),
+            "let" => {
+                self.expect('(')?;
+                self.let_depth += 1;
+                let mut definitions = Vec::new();
+                self.skip_blanks();
+                if self.cursor.peek() == Some(';') {
+                    self.cursor.next();
+                } else {
+                    loop {
+                        self.skip_blanks();
+                        let name = self.take_name()?;
+                        self.expect('=')?;
+                        definitions.push((name, self.parse_process_rec()?));
+                        self.skip_blanks();
+                        match self.cursor.peek() {
+                            Some(',') => {}
+                            Some(';') => {
+                                self.cursor.next();
+                                break;
+                            }
+                            _ => return Err(self.unexpected()),
+                        }
+                        self.cursor.next();
+                    }
+                }
+                let body = self.parse_process_rec()?;
+                self.expect(')')?;
+                self.let_depth -= 1;
+                StrategyEnvironment::new(definitions)
+                    .and_then(|environment| RewriteProcess::let_in(Rc::new(environment), body))
+                    .map_err(|error| located(StrategyParsingErrorKind::Environment(error)))
+            }
+            _ => Err(located(StrategyParsingErrorKind::UnknownCombinator(head))),
+        }
+    }
+
+    /// Parses `(p1, ..., pn)`, with `n` possibly `0`.
+    fn parse_arguments(&mut self) -> Result<Vec<RewriteProcess<LOS>>, StrategyParsingError> {
+        self.expect('(')?;
+        let mut arguments = Vec::new();
+        self.skip_blanks();
+        if self.cursor.peek() == Some(')') {
+            self.cursor.next();
+            return Ok(arguments);
+        }
+        loop {
+            arguments.push(self.parse_process_rec()?);
+            self.skip_blanks();
+            match self.cursor.peek() {
+                Some(',') => {
+                    self.cursor.next();
+                }
+                Some(')') => {
+                    self.cursor.next();
+                    return Ok(arguments);
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), StrategyParsingError> {
+        self.skip_blanks();
+        if self.cursor.peek() == Some(expected) {
+            self.cursor.next();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn take_option(&mut self) -> Result<String, StrategyParsingError> {
+        self.skip_blanks();
+        let option = self.take_token(true);
+        if option.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(option)
+    }
+
+    /// A bare token, or a double-quoted string.
+    fn take_name(&mut self) -> Result<String, StrategyParsingError> {
+        if self.cursor.peek() != Some('"') {
+            let name = self.take_token(false);
+            if name.is_empty() {
+                return Err(self.unexpected());
+            }
+            return Ok(name);
+        }
+        self.cursor.next();
+        let mut name = String::new();
+        loop {
+            match self.cursor.next() {
+                Some('"') => return Ok(name),
+                Some('\\') => match self.cursor.next() {
+                    Some(c @ ('"' | '\\')) => name.push(c),
+                    Some(c) => {
+                        return Err(self.error(StrategyParsingErrorKind::UnexpectedCharacter(c)))
+                    }
+                    None => return Err(self.error(StrategyParsingErrorKind::UnexpectedEndOfInput)),
+                },
+                Some(c) => name.push(c),
+                None => return Err(self.error(StrategyParsingErrorKind::UnexpectedEndOfInput)),
+            }
+        }
+    }
+
+    /// Consumes a maximal run of characters that may appear in a bare name,
+    /// stopping at `:` too if `stop_at_colon`.
+    fn take_token(&mut self, stop_at_colon: bool) -> String {
+        let mut token = String::new();
+        while let Some(c) = self.cursor.peek() {
+            let accepted = is_name_character(c) || (c == ':' && !stop_at_colon);
+            if !accepted {
+                break;
+            }
+            token.push(c);
+            self.cursor.next();
+        }
+        token
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_blanks(&mut self) {
+        loop {
+            self.cursor.skip_whitespace();
+            if self.cursor.peek() != Some('#') {
+                return;
+            }
+            while self.cursor.next().is_some_and(|c| c != '\n') {}
+        }
+    }
+
+    fn error(&self, kind: StrategyParsingErrorKind) -> StrategyParsingError {
+        let (line, column) = self.cursor.location();
+        StrategyParsingError { line, column, kind }
+    }
+
+    fn unexpected(&mut self) -> StrategyParsingError {
+        match self.cursor.peek() {
+            None => self.error(StrategyParsingErrorKind::UnexpectedEndOfInput),
+            Some(c) => self.error(StrategyParsingErrorKind::UnexpectedCharacter(c)),
+        }
+    }
+
+    fn unknown_option(&self, option: &str) -> StrategyParsingError {
+        let (line, column) = self.cursor.location();
+        StrategyParsingError {
+            line,
+            column: column - option.chars().count(),
+            kind: StrategyParsingErrorKind::UnknownOption(option.to_string()),
+        }
+    }
+}
+
+fn is_name_character(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | ',' | ';' | '=' | '#' | '"' | ':')
+}
+
+// == printers ==================================================================
+
+/// Renders `process` on a single line, in the syntax read by
+/// [`parse_process`].
+pub fn print_process<LOS: RewritableLanguageOperatorSymbol>(
+    process: &RewriteProcess<LOS>,
+) -> String {
+    let mut lines = Vec::new();
+    layout(process, Label::new(None), 0, &mut lines);
+    let mut printed = String::new();
+    for (text, _) in lines {
+        if !printed.is_empty() && !printed.ends_with('(') {
+            printed.push(' ');
+        }
+        printed.push_str(text.trim_start());
+    }
+    printed
+}
+
+/// Renders `process` with one node per line, indented, each line ending with
+/// a `#` comment that holds the [`PositionInRewriteProcess`] of the node, as in
+/// the `rule_chain` of the traced executor.  The output is still read by
+/// [`parse_process`].
+///
+/// The nodes in the definitions of a `let` are labelled `name:position`,
+/// relative to the definition: below a `call:name` at `p`, they sit at `p_0`
+/// followed by `position`.
+pub fn print_annotated_process<LOS: RewritableLanguageOperatorSymbol>(
+    process: &RewriteProcess<LOS>,
+) -> String {
+    let mut lines = Vec::new();
+    layout(process, Label::new(None), 0, &mut lines);
+    let width = lines
+        .iter()
+        .map(|(text, _)| text.chars().count())
+        .max()
+        .unwrap_or(0);
+    lines
+        .into_iter()
+        .map(|(text, label)| format!("{:<width$}  # {}\n", text, label))
+        .collect()
+}
+
+/// The position of a node, within the definition `definition` if any.
+#[derive(Clone)]
+struct Label<'p> {
+    definition: Option<&'p str>,
+    position: PositionInRewriteProcess,
+}
+
+impl<'p> Label<'p> {
+    fn new(definition: Option<&'p str>) -> Self {
+        Self {
+            definition,
+            position: PositionInRewriteProcess::get_root_position(),
+        }
+    }
+
+    fn child(&self, n: usize) -> Self {
+        Self {
+            definition: self.definition,
+            position: self.position.get_position_of_nth_child(n),
+        }
+    }
+}
+
+impl fmt::Display for Label<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.definition {
+            Some(name) => write!(f, "{}:{}", quote_name(name), self.position),
+            None => write!(f, "{}", self.position),
+        }
+    }
+}
+
+/// Pushes one `(indented text, label)` line per node of `process`.
+fn layout<'p, LOS: RewritableLanguageOperatorSymbol>(
+    process: &'p RewriteProcess<LOS>,
+    label: Label<'p>,
+    indent: usize,
+    lines: &mut Vec<(String, Label<'p>)>,
+) {
+    let padding = "  ".repeat(indent);
+    let (head, children): (String, Vec<&RewriteProcess<LOS>>) = match process {
+        RewriteProcess::Rule(rule) => {
+            lines.push((
+                format!("{}rule:{}", padding, quote_name(&rule.get_desc())),
+                label,
+            ));
+            return;
+        }
+        RewriteProcess::Call(name) => {
+            lines.push((format!("{}call:{}", padding, quote_name(name)), label));
+            return;
+        }
+        RewriteProcess::Let(environment, body) => {
+            lines.push((format!("{}let(", padding), label.clone()));
+            let names = environment.get_names();
+            if names.is_empty() {
+                lines.last_mut().unwrap().0.push(';');
+            }
+            for (i, name) in names.iter().enumerate() {
+                let start = lines.len();
+                let definition = environment.get_definition(name).unwrap();
+                layout(definition, Label::new(Some(name)), indent + 1, lines);
+                let first = &mut lines[start].0;
+                first.insert_str(2 * (indent + 1), &format!("{} = ", quote_name(name)));
+                let separator = if i + 1 == names.len() { ";" } else { "," };
+                lines.last_mut().unwrap().0.push_str(separator);
+            }
+            layout(body, label.child(0), indent + 1, lines);
+            lines.last_mut().unwrap().0.push(')');
+            return;
+        }
+        RewriteProcess::AnyChild(sibling_order, depth_order, p) => {
+            let sibling = match sibling_order {
+                SiblingOrder::Leftmost => "lm",
+                SiblingOrder::Rightmost => "rm",
+            };
+            let depth = match depth_order {
+                DepthOrder::Outermost => "out",
+                DepthOrder::Innermost => "inn",
+            };
+            (format!("any_child({}, {},", sibling, depth), vec![p])
+        }
+        RewriteProcess::AllChildren(p) => ("all_children(".to_string(), vec![p]),
+        RewriteProcess::OneChild(p) => ("one_child(".to_string(), vec![p]),
+        RewriteProcess::SomeChildren(p) => ("some_children(".to_string(), vec![p]),
+        RewriteProcess::Pipe(a, b) => ("pipe(".to_string(), vec![a, b]),
+        RewriteProcess::Repeat(p) => ("repeat(".to_string(), vec![p]),
+        RewriteProcess::TryOnePath(alternatives) => {
+            ("try_one(".to_string(), alternatives.iter().collect())
+        }
+        RewriteProcess::TryAllPaths(alternatives) => {
+            ("try_all(".to_string(), alternatives.iter().collect())
+        }
+        RewriteProcess::Traverse(traversal, p) => {
+            let name = match traversal {
+                Traversal::TopDown => "topdown",
+                Traversal::BottomUp => "bottomup",
+                Traversal::ParallelOutermostStep => "parallel_outermost_step",
+            };
+            (format!("{}(", name), vec![p])
+        }
+    };
+    lines.push((format!("{}{}", padding, head), label.clone()));
+    for (n, child) in children.iter().enumerate() {
+        layout(child, label.child(n), indent + 1, lines);
+        if n + 1 < children.len() {
+            lines.last_mut().unwrap().0.push(',');
+        }
+    }
+    lines.last_mut().unwrap().0.push(')');
+}
+
+/// `name` as a bare token if possible, double-quoted otherwise.
+fn quote_name(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(is_name_character) {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
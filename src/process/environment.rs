@@ -0,0 +1,282 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::process::strategy::RewriteProcess;
+use crate::term::syntax::RewritableLanguageOperatorSymbol;
+
+/// Why a [`StrategyEnvironment`] could not be built, or a process could not be
+/// scoped in it.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum StrategyEnvironmentError {
+    /// The name is defined more than once.
+    DuplicateDefinition(String),
+    /// `Call(name)` occurs in the definition of `in_definition`, or in the
+    /// scoped process if `None`, but `name` is not defined.
+    UnboundName {
+        in_definition: Option<String>,
+        name: String,
+    },
+    /// Each definition calls the next one, and the last one calls the first
+    /// one, without firing a rule or descending into a child in between:
+    /// evaluating any of them would never terminate.
+    UnguardedRecursion(Vec<String>),
+}
+
+impl fmt::Display for StrategyEnvironmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrategyEnvironmentError::DuplicateDefinition(name) => {
+                write!(f, "strategy '{}' is defined more than once", name)
+            }
+            StrategyEnvironmentError::UnboundName {
+                in_definition,
+                name,
+            } => {
+                write!(f, "call to the undefined strategy '{}'", name)?;
+                match in_definition {
+                    Some(definition) => write!(f, " in the definition of '{}'", definition),
+                    None => Ok(()),
+                }
+            }
+            StrategyEnvironmentError::UnguardedRecursion(names) => {
+                write!(f, "unguarded recursion: ")?;
+                for name in names {
+                    write!(f, "'{}' calls ", name)?;
+                }
+                write!(f, "'{}'", names[0])
+            }
+        }
+    }
+}
+
+/// Named processes, which may call each other (and themselves) with
+/// [`RewriteProcess::Call`].
+///
+/// A process is evaluated in an environment by wrapping it in
+/// [`RewriteProcess::Let`], see [`RewriteProcess::let_in`].
+///
+/// Every recursive call must be guarded: between entering a definition and
+/// calling it again, either a child is entered (`AnyChild`, `AllChildren`,
+/// `OneChild`, `SomeChildren`), or a rule has necessarily fired (the call is
+/// on the right of a `Pipe` whose left side cannot succeed without firing a
+/// rule).  Unguarded recursion, such as `a = TryOnePath([Rule(r), Call(a)])`,
+/// is rejected by [`new`](Self::new).
+pub struct StrategyEnvironment<LOS: RewritableLanguageOperatorSymbol> {
+    /// The defined names, in the order they were given.
+    names: Vec<String>,
+    definitions: HashMap<String, RewriteProcess<LOS>>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> StrategyEnvironment<LOS> {
+    /// Binds each process to its name, checking that every called name is
+    /// defined and that every recursion is guarded.
+    pub fn new(
+        definitions: Vec<(String, RewriteProcess<LOS>)>,
+    ) -> Result<Self, StrategyEnvironmentError> {
+        let mut names = Vec::with_capacity(definitions.len());
+        let mut map = HashMap::with_capacity(definitions.len());
+        for (name, process) in definitions {
+            if map.contains_key(&name) {
+                return Err(StrategyEnvironmentError::DuplicateDefinition(name));
+            }
+            names.push(name.clone());
+            map.insert(name, process);
+        }
+        let environment = Self {
+            names,
+            definitions: map,
+        };
+        for name in &environment.names {
+            environment.check_bound(&environment.definitions[name], Some(name))?;
+        }
+        environment.check_guarded(&environment.names)?;
+        Ok(environment)
+    }
+
+    /// The defined names, in the order they were given to [`new`](Self::new).
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The process bound to `name`.
+    pub fn get_definition(&self, name: &str) -> Option<&RewriteProcess<LOS>> {
+        self.definitions.get(name)
+    }
+
+    /// Checks that every name called by `process`, outside of nested `Let`s,
+    /// is defined.
+    pub fn check_process(
+        &self,
+        process: &RewriteProcess<LOS>,
+    ) -> Result<(), StrategyEnvironmentError> {
+        self.check_bound(process, None)
+    }
+
+    fn check_bound(
+        &self,
+        process: &RewriteProcess<LOS>,
+        in_definition: Option<&String>,
+    ) -> Result<(), StrategyEnvironmentError> {
+        match process {
+            RewriteProcess::Call(name) if !self.definitions.contains_key(name) => {
+                Err(StrategyEnvironmentError::UnboundName {
+                    in_definition: in_definition.cloned(),
+                    name: name.clone(),
+                })
+            }
+            // A nested environment was checked when it was built.
+            RewriteProcess::Rule(_) | RewriteProcess::Call(_) | RewriteProcess::Let(_, _) => Ok(()),
+            RewriteProcess::AnyChild(_, _, p)
+            | RewriteProcess::AllChildren(p)
+            | RewriteProcess::OneChild(p)
+            | RewriteProcess::SomeChildren(p)
+            | RewriteProcess::Repeat(p)
+            | RewriteProcess::Traverse(_, p) => self.check_bound(p, in_definition),
+            RewriteProcess::Pipe(a, b) => {
+                self.check_bound(a, in_definition)?;
+                self.check_bound(b, in_definition)
+            }
+            RewriteProcess::TryOnePath(alternatives)
+            | RewriteProcess::TryAllPaths(alternatives) => alternatives
+                .iter()
+                .try_for_each(|p| self.check_bound(p, in_definition)),
+        }
+    }
+
+    /// Looks for a cycle in the graph of unguarded calls, visiting the
+    /// definitions in the order they were given.
+    fn check_guarded(&self, names: &[String]) -> Result<(), StrategyEnvironmentError> {
+        let fires = self.always_firing_definitions();
+        let callees: HashMap<&str, Vec<&str>> = names
+            .iter()
+            .map(|name| {
+                let mut calls = Vec::new();
+                unguarded_calls(&self.definitions[name], &fires, &mut calls);
+                (name.as_str(), calls)
+            })
+            .collect();
+        let mut finished: HashSet<&str> = HashSet::new();
+        for name in names {
+            let mut path = Vec::new();
+            if let Some(cycle) = find_call_cycle(name, &callees, &mut path, &mut finished) {
+                return Err(StrategyEnvironmentError::UnguardedRecursion(cycle));
+            }
+        }
+        Ok(())
+    }
+
+    /// The names whose definition cannot succeed without firing a rule, as
+    /// the least fixpoint of [`always_fires`].
+    fn always_firing_definitions(&self) -> HashSet<&str> {
+        let mut fires = HashSet::new();
+        loop {
+            let before = fires.len();
+            for (name, process) in &self.definitions {
+                if !fires.contains(name.as_str()) && always_fires(process, &fires) {
+                    fires.insert(name.as_str());
+                }
+            }
+            if fires.len() == before {
+                return fires;
+            }
+        }
+    }
+}
+
+/// Whether every success of `process` fires at least one rule, given the
+/// names known to do so.
+fn always_fires<LOS: RewritableLanguageOperatorSymbol>(
+    process: &RewriteProcess<LOS>,
+    fires: &HashSet<&str>,
+) -> bool {
+    match process {
+        RewriteProcess::Rule(_) => true,
+        RewriteProcess::AnyChild(_, _, p)
+        | RewriteProcess::OneChild(p)
+        | RewriteProcess::SomeChildren(p)
+        | RewriteProcess::Traverse(_, p) => always_fires(p, fires),
+        // Both may succeed without change.
+        RewriteProcess::AllChildren(_) | RewriteProcess::Repeat(_) => false,
+        RewriteProcess::Pipe(a, b) => always_fires(a, fires) || always_fires(b, fires),
+        RewriteProcess::TryOnePath(alternatives) | RewriteProcess::TryAllPaths(alternatives) => {
+            alternatives.iter().all(|p| always_fires(p, fires))
+        }
+        RewriteProcess::Call(name) => fires.contains(name.as_str()),
+        // The names of a nested environment are not tracked.
+        RewriteProcess::Let(_, _) => false,
+    }
+}
+
+/// Pushes every name that `process` may call on the term it is applied to,
+/// before any rule necessarily fired.
+fn unguarded_calls<'p, LOS: RewritableLanguageOperatorSymbol>(
+    process: &'p RewriteProcess<LOS>,
+    fires: &HashSet<&str>,
+    calls: &mut Vec<&'p str>,
+) {
+    match process {
+        RewriteProcess::Rule(_)
+        | RewriteProcess::AnyChild(_, _, _)
+        | RewriteProcess::AllChildren(_)
+        | RewriteProcess::OneChild(_)
+        | RewriteProcess::SomeChildren(_)
+        | RewriteProcess::Let(_, _) => {}
+        RewriteProcess::Pipe(a, b) => {
+            unguarded_calls(a, fires, calls);
+            if !always_fires(a, fires) {
+                unguarded_calls(b, fires, calls);
+            }
+        }
+        // Every traversal applies its process at the root, too.
+        RewriteProcess::Repeat(p) | RewriteProcess::Traverse(_, p) => {
+            unguarded_calls(p, fires, calls)
+        }
+        RewriteProcess::TryOnePath(alternatives) | RewriteProcess::TryAllPaths(alternatives) => {
+            for p in alternatives {
+                unguarded_calls(p, fires, calls);
+            }
+        }
+        RewriteProcess::Call(name) => calls.push(name),
+    }
+}
+
+/// Depth-first search for a cycle through `name`'s callees; `path` holds the
+/// names being visited.
+fn find_call_cycle<'n>(
+    name: &'n str,
+    callees: &HashMap<&'n str, Vec<&'n str>>,
+    path: &mut Vec<&'n str>,
+    finished: &mut HashSet<&'n str>,
+) -> Option<Vec<String>> {
+    if finished.contains(name) {
+        return None;
+    }
+    if let Some(start) = path.iter().position(|&n| n == name) {
+        return Some(path[start..].iter().map(|n| n.to_string()).collect());
+    }
+    path.push(name);
+    for &callee in &callees[name] {
+        if let Some(cycle) = find_call_cycle(callee, callees, path, finished) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    finished.insert(name);
+    None
+}
@@ -0,0 +1,188 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::position::PositionInRewriteProcess;
+use crate::process::redex::{enumerate_redexes, Redex};
+use crate::process::traced::AtomicRuleApplication;
+use crate::rule::RewriteRule;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
+
+/// A step taken by an [`InteractiveRewriteExecutor`], with the term it was
+/// taken from.
+struct InteractiveStep<LOS: RewritableLanguageOperatorSymbol> {
+    source: LanguageTerm<LOS>,
+    redex: Redex<LOS>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> InteractiveStep<LOS> {
+    fn to_application(&self) -> AtomicRuleApplication {
+        AtomicRuleApplication {
+            left_id: 0,
+            rule_chain: vec![(
+                PositionInRewriteProcess::from_absolute_coordinates(vec![self.redex.rule_index]),
+                self.redex.position.clone(),
+            )],
+            right_id: 0,
+        }
+    }
+}
+
+/// User-driven evaluator: holds a current term, offers every one-step rewrite
+/// of it (see [`enumerate_redexes`]) and applies the one the caller picks.
+///
+/// Steps are kept in a history, so they can be undone and redone.  Each step
+/// is reported as an [`AtomicRuleApplication`] whose frontiers have a single
+/// term (`left_id` and `right_id` are `0`) and whose rule chain has a single
+/// entry: rule `i` sits at strategy position `i`, as in
+/// `TryAllPaths([Rule(rule_0), Rule(rule_1), ...])`.  Traces can therefore be
+/// compared with those of a
+/// [`RewriteProcessTracedExecutor`](crate::process::traced::RewriteProcessTracedExecutor),
+/// or rendered with [`render_trace`](crate::process::trace::render_trace) and
+/// that strategy.
+pub struct InteractiveRewriteExecutor<LOS: RewritableLanguageOperatorSymbol> {
+    rules: Vec<Box<dyn RewriteRule<LOS>>>,
+    factory: TermFactory<LOS>,
+    initial_term: LanguageTerm<LOS>,
+    current_term: LanguageTerm<LOS>,
+    /// The redexes of `current_term`.
+    candidates: Vec<Redex<LOS>>,
+    /// The steps from `initial_term` to `current_term`.
+    history: Vec<InteractiveStep<LOS>>,
+    /// Undone steps, the most recently undone one last.
+    undone: Vec<InteractiveStep<LOS>>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> InteractiveRewriteExecutor<LOS> {
+    /// Creates an executor whose current term is `initial`.
+    pub fn new(
+        rules: Vec<Box<dyn RewriteRule<LOS>>>,
+        initial: LanguageTerm<LOS>,
+        mut factory: TermFactory<LOS>,
+    ) -> Self {
+        let candidates = enumerate_redexes(&rules, &initial, &mut factory);
+        Self {
+            rules,
+            factory,
+            initial_term: initial.clone(),
+            current_term: initial,
+            candidates,
+            history: vec![],
+            undone: vec![],
+        }
+    }
+
+    /// Consumes the executor and returns its factory.
+    pub fn into_factory(self) -> TermFactory<LOS> {
+        self.factory
+    }
+
+    /// The term the next step applies to.
+    pub fn get_current_term(&self) -> &LanguageTerm<LOS> {
+        &self.current_term
+    }
+
+    /// The steps [`apply`](Self::apply) may take from the current term, in
+    /// the order of [`enumerate_redexes`].
+    pub fn get_candidates(&self) -> &[Redex<LOS>] {
+        &self.candidates
+    }
+
+    /// Whether no rule fires anywhere in the current term.
+    pub fn is_normal_form(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Takes the step `candidate`, an index into
+    /// [`get_candidates`](Self::get_candidates), and forgets the undone steps.
+    ///
+    /// Returns `None`, and does nothing, if there is no such candidate.
+    pub fn apply(&mut self, candidate: usize) -> Option<AtomicRuleApplication> {
+        let redex = self.candidates.get(candidate)?.clone();
+        self.undone.clear();
+        let step = InteractiveStep {
+            source: self.current_term.clone(),
+            redex,
+        };
+        Some(self.push_step(step))
+    }
+
+    /// Goes back to the term before the last step.  Returns `false` at the
+    /// initial term.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            None => false,
+            Some(step) => {
+                let source = step.source.clone();
+                self.undone.push(step);
+                self.set_current_term(source);
+                true
+            }
+        }
+    }
+
+    /// Takes the last undone step again.  Returns `None` if no step was undone
+    /// since the last [`apply`](Self::apply).
+    pub fn redo(&mut self) -> Option<AtomicRuleApplication> {
+        let step = self.undone.pop()?;
+        Some(self.push_step(step))
+    }
+
+    /// Whether [`undo`](Self::undo) would go back.
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    /// Whether [`redo`](Self::redo) would take a step.
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// The terms from the initial term to the current term.
+    pub fn get_derivation(&self) -> Vec<LanguageTerm<LOS>> {
+        let mut terms = Vec::with_capacity(self.history.len() + 1);
+        terms.push(self.initial_term.clone());
+        terms.extend(self.history.iter().map(|step| step.redex.result.clone()));
+        terms
+    }
+
+    /// One application per step from the initial term to the current term;
+    /// the `i`-th one goes from `get_derivation()[i]` to
+    /// `get_derivation()[i + 1]`.
+    pub fn get_trace(&self) -> Vec<AtomicRuleApplication> {
+        self.history
+            .iter()
+            .map(InteractiveStep::to_application)
+            .collect()
+    }
+
+    /// The rule of index `rule_index` in the rule set.
+    pub fn get_rule(&self, rule_index: usize) -> Option<&dyn RewriteRule<LOS>> {
+        self.rules.get(rule_index).map(|rule| rule.as_ref())
+    }
+
+    fn push_step(&mut self, step: InteractiveStep<LOS>) -> AtomicRuleApplication {
+        let application = step.to_application();
+        let result = step.redex.result.clone();
+        self.history.push(step);
+        self.set_current_term(result);
+        application
+    }
+
+    fn set_current_term(&mut self, term: LanguageTerm<LOS>) {
+        self.candidates = enumerate_redexes(&self.rules, &term, &mut self.factory);
+        self.current_term = term;
+    }
+}
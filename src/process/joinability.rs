@@ -0,0 +1,244 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use hashconsing::HConsign;
+
+use crate::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use crate::process::budget::{BudgetExhaustion, RewriteBudget};
+use crate::process::control::RewriteExecutionError;
+use crate::process::search::{DerivationDirection, DerivationStep};
+use crate::process::strategy::RewriteProcess;
+use crate::process::traced::RewriteProcessTracedExecutor;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
+
+/// Two derivations ending in the same term.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct JoinabilityWitness<LOS: RewritableLanguageOperatorSymbol> {
+    pub common_reduct: LanguageTerm<LOS>,
+    /// From the left term to `common_reduct`.
+    pub left_derivation: Vec<DerivationStep<LOS>>,
+    /// From the right term to `common_reduct`.
+    pub right_derivation: Vec<DerivationStep<LOS>>,
+}
+
+/// The result of [`JoinabilityCheck::run`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum JoinabilityOutcome<LOS: RewritableLanguageOperatorSymbol> {
+    /// The two terms have a common reduct.
+    Joinable(JoinabilityWitness<LOS>),
+    /// Every reduct of both terms was visited, and none is common.
+    NotJoinable,
+    /// The budget ran out before a common reduct was found.
+    BudgetExhausted(BudgetExhaustion),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// How a reduct was first reached.
+#[allow(clippy::type_complexity)]
+struct Origin<LOS: RewritableLanguageOperatorSymbol> {
+    side: Side,
+    /// The previous term and the rule chain between them, `None` for the two
+    /// initial terms.
+    parent: Option<(
+        LanguageTerm<LOS>,
+        Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+    )>,
+}
+
+/// Checks whether two terms rewrite to a common term under the steps of a
+/// strategy.
+///
+/// Both reduct sets are explored in parallel, breadth-first, by a single
+/// [`RewriteProcessTracedExecutor`] started from the two terms with frontier
+/// deduplication enabled: each reduct is expanded once, and the exploration
+/// stops after the first step in which a term is reached from both sides.
+/// The longer of the two derivations is therefore as short as possible.
+///
+/// ```rust,ignore
+/// let outcome = JoinabilityCheck::new(strategy)
+///     .with_budget(RewriteBudget::new().with_max_rule_applications(10_000))
+///     .run(&left, &right, &mut factory)?;
+/// ```
+pub struct JoinabilityCheck<LOS: RewritableLanguageOperatorSymbol> {
+    strategy: RewriteProcess<LOS>,
+    budget: RewriteBudget,
+    check_signatures: bool,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> JoinabilityCheck<LOS> {
+    /// Creates an unbounded check over the steps of `strategy`.
+    pub fn new(strategy: RewriteProcess<LOS>) -> Self {
+        Self {
+            strategy,
+            budget: RewriteBudget::new(),
+            check_signatures: false,
+        }
+    }
+
+    /// Limits the whole exploration, both sides included, with `budget`; see
+    /// [`RewriteProcessTracedExecutor::with_budget`].
+    pub fn with_budget(mut self, budget: RewriteBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// See [`RewriteProcessTracedExecutor::with_signature_checking`].
+    pub fn with_signature_checking(mut self, enabled: bool) -> Self {
+        self.check_signatures = enabled;
+        self
+    }
+
+    /// Looks for a common reduct of `left` and `right`.
+    ///
+    /// `factory` is lent to the executor for the duration of the check.
+    /// Returns an error if the strategy fails, e.g. on an ill-formed rule
+    /// output when signature checking is enabled.
+    pub fn run(
+        self,
+        left: &LanguageTerm<LOS>,
+        right: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<JoinabilityOutcome<LOS>, RewriteExecutionError<LOS>> {
+        if left == right {
+            return Ok(JoinabilityOutcome::Joinable(JoinabilityWitness {
+                common_reduct: left.clone(),
+                left_derivation: vec![],
+                right_derivation: vec![],
+            }));
+        }
+        let mut executor = RewriteProcessTracedExecutor::from_terms(
+            self.strategy,
+            vec![left.clone(), right.clone()],
+            std::mem::replace(factory, HConsign::empty()),
+        )
+        .with_frontier_deduplication(true)
+        .with_signature_checking(self.check_signatures)
+        .with_budget(self.budget);
+        let outcome = explore(&mut executor, left, right);
+        *factory = executor.into_factory();
+        outcome
+    }
+}
+
+fn explore<LOS: RewritableLanguageOperatorSymbol>(
+    executor: &mut RewriteProcessTracedExecutor<LOS>,
+    left: &LanguageTerm<LOS>,
+    right: &LanguageTerm<LOS>,
+) -> Result<JoinabilityOutcome<LOS>, RewriteExecutionError<LOS>> {
+    let mut origins: HashMap<LanguageTerm<LOS>, Origin<LOS>> = HashMap::new();
+    origins.insert(
+        left.clone(),
+        Origin {
+            side: Side::Left,
+            parent: None,
+        },
+    );
+    origins.insert(
+        right.clone(),
+        Origin {
+            side: Side::Right,
+            parent: None,
+        },
+    );
+    while !executor.get_current_terms().is_empty() {
+        let previous = executor.get_current_terms().to_vec();
+        let applications = match executor.try_progress() {
+            Ok(applications) => applications,
+            Err(RewriteExecutionError::BudgetExhausted(exhaustion)) => {
+                return Ok(JoinabilityOutcome::BudgetExhausted(exhaustion));
+            }
+            Err(error) => return Err(error),
+        };
+        let current = executor.get_current_terms();
+        let steps = applications
+            .into_iter()
+            .map(|app| (app.left_id, app.rule_chain, current[app.right_id].clone()))
+            .chain(
+                executor
+                    .get_revisiting_applications()
+                    .iter()
+                    .map(|app| (app.left_id, app.rule_chain.clone(), app.term.clone())),
+            );
+        let mut meeting = None;
+        for (left_id, rule_chain, target) in steps {
+            let source = &previous[left_id];
+            let side = origins[source].side;
+            match origins.get(&target) {
+                None => {
+                    origins.insert(
+                        target,
+                        Origin {
+                            side,
+                            parent: Some((source.clone(), rule_chain)),
+                        },
+                    );
+                }
+                Some(origin) if origin.side != side && meeting.is_none() => {
+                    meeting = Some((side, source.clone(), rule_chain, target));
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some((side, source, rule_chain, target)) = meeting {
+            // `target` was first reached from the other side.
+            let mut derivation = path_to(&origins, &source);
+            derivation.push(DerivationStep {
+                source,
+                rule_chain,
+                target: target.clone(),
+                direction: DerivationDirection::Forward,
+            });
+            let other_derivation = path_to(&origins, &target);
+            let (left_derivation, right_derivation) = match side {
+                Side::Left => (derivation, other_derivation),
+                Side::Right => (other_derivation, derivation),
+            };
+            return Ok(JoinabilityOutcome::Joinable(JoinabilityWitness {
+                common_reduct: target,
+                left_derivation,
+                right_derivation,
+            }));
+        }
+    }
+    Ok(JoinabilityOutcome::NotJoinable)
+}
+
+/// The derivation from the initial term of `term`'s side to `term`.
+fn path_to<LOS: RewritableLanguageOperatorSymbol>(
+    origins: &HashMap<LanguageTerm<LOS>, Origin<LOS>>,
+    term: &LanguageTerm<LOS>,
+) -> Vec<DerivationStep<LOS>> {
+    let mut derivation = Vec::new();
+    let mut current = term.clone();
+    while let Some((parent, rule_chain)) = &origins[&current].parent {
+        derivation.push(DerivationStep {
+            source: parent.clone(),
+            rule_chain: rule_chain.clone(),
+            target: current.clone(),
+            direction: DerivationDirection::Forward,
+        });
+        current = parent.clone();
+    }
+    derivation.reverse();
+    derivation
+}
@@ -14,6 +14,19 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+pub mod best_first;
+pub mod budget;
+pub mod control;
+pub mod dsl;
+pub mod environment;
+pub mod interactive;
+pub mod joinability;
+pub mod normalizer;
+pub mod redex;
+pub mod rewrite_graph;
+pub mod search;
 pub mod strategy;
+pub mod stream;
+pub mod trace;
 pub mod traced;
 pub mod untraced;
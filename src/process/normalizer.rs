@@ -0,0 +1,196 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use crate::position::PositionInLanguageTerm;
+use crate::rule::RewriteRule;
+use crate::term::syntax::{
+    LanguageTerm, LanguageTermNode, RewritableLanguageOperatorSymbol, TermFactory,
+};
+
+/// Counters of a [`Normalizer`], accumulated across calls.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct NormalizerStatistics {
+    /// Sub-terms whose normal form was found in the cache.
+    pub cache_hits: usize,
+    /// Sub-terms whose normal form had to be computed.
+    pub cache_misses: usize,
+    /// Rules that fired while computing normal forms.
+    pub rule_applications: usize,
+}
+
+/// Memoised innermost normaliser.
+///
+/// Normalises the children of a term first, then tries the rules at its root,
+/// in order, and starts over on the result of the first one that fires; this is
+/// the normal form that
+/// `Repeat(TryOnePath([AnyChild(Leftmost, Innermost, rules), rules]))` reaches.
+/// The normal form of every sub-term met along the way is cached by
+/// hash-consing identity, so shared sub-terms are normalised only once, across
+/// calls and across terms.
+///
+/// This is only sound for a deterministic, context-free and terminating rule
+/// set: each rule is applied to a sub-term as if it were the whole term, at the
+/// root position.
+/// The cache must only be used with terms of the same [`TermFactory`].
+pub struct Normalizer<LOS: RewritableLanguageOperatorSymbol> {
+    rules: Vec<Box<dyn RewriteRule<LOS>>>,
+    cache: HashMap<LanguageTerm<LOS>, LanguageTerm<LOS>>,
+    statistics: NormalizerStatistics,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> Normalizer<LOS> {
+    /// Creates a normaliser with an empty cache.  Earlier rules take priority.
+    pub fn new(rules: Vec<Box<dyn RewriteRule<LOS>>>) -> Self {
+        Self {
+            rules,
+            cache: HashMap::new(),
+            statistics: NormalizerStatistics::default(),
+        }
+    }
+
+    /// Returns the normal form of `term`.
+    pub fn normalize(
+        &mut self,
+        term: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> LanguageTerm<LOS> {
+        if let Some(normal_form) = self.cache.get(term) {
+            self.statistics.cache_hits += 1;
+            return normal_form.clone();
+        }
+        self.statistics.cache_misses += 1;
+        // Every term rewritten on the way has the same normal form.
+        let mut reducts = vec![term.clone()];
+        let mut current = term.clone();
+        let normal_form = loop {
+            let sub_terms: Vec<LanguageTerm<LOS>> = current
+                .sub_terms
+                .iter()
+                .map(|sub| self.normalize(sub, factory))
+                .collect();
+            if sub_terms != current.sub_terms {
+                current = LanguageTermNode::build(current.operator.clone(), sub_terms, factory);
+                if let Some(normal_form) = self.cache.get(&current) {
+                    break normal_form.clone();
+                }
+                reducts.push(current.clone());
+            }
+            match self.apply_at_root(&current, factory) {
+                None => break current,
+                Some(output) => {
+                    if let Some(normal_form) = self.cache.get(&output) {
+                        break normal_form.clone();
+                    }
+                    reducts.push(output.clone());
+                    current = output;
+                }
+            }
+        };
+        for reduct in reducts {
+            self.cache.insert(reduct, normal_form.clone());
+        }
+        normal_form
+    }
+
+    fn apply_at_root(
+        &mut self,
+        term: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Option<LanguageTerm<LOS>> {
+        let root = PositionInLanguageTerm::get_root_position();
+        let output = self
+            .rules
+            .iter()
+            .find_map(|rule| rule.try_apply(term, term, &root, factory))?;
+        self.statistics.rule_applications += 1;
+        Some(output)
+    }
+
+    /// The counters accumulated since the creation of the normaliser or the
+    /// last [`reset_statistics`](Self::reset_statistics).
+    pub fn get_statistics(&self) -> NormalizerStatistics {
+        self.statistics
+    }
+
+    /// Resets every counter to zero; the cache is kept.
+    pub fn reset_statistics(&mut self) {
+        self.statistics = NormalizerStatistics::default();
+    }
+
+    /// Number of terms whose normal form is cached.
+    pub fn get_cache_size(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// The cached normal form of `term`, if any.
+    pub fn get_cached_normal_form(&self, term: &LanguageTerm<LOS>) -> Option<&LanguageTerm<LOS>> {
+        self.cache.get(term)
+    }
+
+    /// Forgets every cached normal form, e.g. after the rules' behaviour has
+    /// changed.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Forgets the cached normal form of `term` and of every cached term that
+    /// may have been normalised through it: the ones containing it and the
+    /// ones sharing its normal form.  Returns the number of entries removed.
+    pub fn invalidate(&mut self, term: &LanguageTerm<LOS>) -> usize {
+        let normal_form = self.cache.get(term).cloned();
+        let mut containing = HashSet::new();
+        let mut not_containing = HashSet::new();
+        let before = self.cache.len();
+        self.cache.retain(|key, value| {
+            Some(&*value) != normal_form.as_ref()
+                && !contains_sub_term(key, term, &mut containing, &mut not_containing)
+        });
+        before - self.cache.len()
+    }
+
+    /// Replaces the rules, clearing the cache.
+    pub fn set_rules(&mut self, rules: Vec<Box<dyn RewriteRule<LOS>>>) {
+        self.rules = rules;
+        self.clear_cache();
+    }
+}
+
+/// Whether `sub_term` occurs in `term`, memoised over the shared sub-terms.
+fn contains_sub_term<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+    sub_term: &LanguageTerm<LOS>,
+    containing: &mut HashSet<LanguageTerm<LOS>>,
+    not_containing: &mut HashSet<LanguageTerm<LOS>>,
+) -> bool {
+    if term == sub_term || containing.contains(term) {
+        return true;
+    }
+    if not_containing.contains(term) {
+        return false;
+    }
+    let found = term
+        .sub_terms
+        .iter()
+        .any(|sub| contains_sub_term(sub, sub_term, containing, not_containing));
+    if found {
+        containing.insert(term.clone());
+    } else {
+        not_containing.insert(term.clone());
+    }
+    found
+}
@@ -0,0 +1,67 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::position::PositionInLanguageTerm;
+use crate::process::strategy::replace_at_position;
+use crate::rule::RewriteRule;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
+
+/// One possible rewrite step: rule `rule_index` fires at `position`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Redex<LOS: RewritableLanguageOperatorSymbol> {
+    /// Index of the rule in the rule set given to [`enumerate_redexes`].
+    pub rule_index: usize,
+    /// Where in the term the rule fires.
+    pub position: PositionInLanguageTerm,
+    /// What the rule replaces the sub-term at `position` with.
+    pub contractum: LanguageTerm<LOS>,
+    /// The whole term after the step.
+    pub result: LanguageTerm<LOS>,
+}
+
+/// Lists every rule of `rules` that fires at every position of `term`,
+/// independently of any strategy.
+///
+/// Each rule is given `term` as context and the actual position of the
+/// sub-term, so a [`GuardedRule`](crate::rules::combinators::guarded::GuardedRule)
+/// only appears where its guard allows it.  Positions are visited in pre-order
+/// (a node before its children, children from left to right) and, at each
+/// position, rules in the order of `rules`.
+pub fn enumerate_redexes<LOS: RewritableLanguageOperatorSymbol>(
+    rules: &[Box<dyn RewriteRule<LOS>>],
+    term: &LanguageTerm<LOS>,
+    factory: &mut TermFactory<LOS>,
+) -> Vec<Redex<LOS>> {
+    let mut redexes = Vec::new();
+    let mut stack = vec![(term.clone(), PositionInLanguageTerm::get_root_position())];
+    while let Some((sub_term, position)) = stack.pop() {
+        for (rule_index, rule) in rules.iter().enumerate() {
+            if let Some(contractum) = rule.try_apply(&sub_term, term, &position, factory) {
+                let result = replace_at_position(term, &position, contractum.clone(), factory);
+                redexes.push(Redex {
+                    rule_index,
+                    position: position.clone(),
+                    contractum,
+                    result,
+                });
+            }
+        }
+        for (n, child) in sub_term.sub_terms.iter().enumerate().rev() {
+            stack.push((child.clone(), position.get_position_of_nth_child(n)));
+        }
+    }
+    redexes
+}
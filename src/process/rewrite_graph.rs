@@ -0,0 +1,244 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+
+use crate::position::{PositionInLanguageTerm, PositionInRewriteProcess, TreePosition};
+use crate::term::dot::escape_dot_string;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol};
+
+/// One rule firing within a [`RewriteGraphEdge`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RewriteGraphStep {
+    /// The [`get_desc`](crate::rule::RewriteRule::get_desc) of the rule.
+    pub rule_desc: String,
+    /// Which `Rule(...)` leaf of the strategy fired.
+    pub strategy_position: PositionInRewriteProcess,
+    /// Where in the term it fired.
+    pub term_position: PositionInLanguageTerm,
+}
+
+/// A transition between two nodes of a [`RewriteGraph`].
+///
+/// Corresponds to one [`AtomicRuleApplication`](super::traced::AtomicRuleApplication);
+/// `steps` follows its `rule_chain`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RewriteGraphEdge {
+    /// Id of the source term.
+    pub source: usize,
+    /// Id of the target term.
+    pub target: usize,
+    /// The rules that fired, in order.
+    pub steps: Vec<RewriteGraphStep>,
+}
+
+/// The derivation graph accumulated by a
+/// [`RewriteProcessTracedExecutor`](super::traced::RewriteProcessTracedExecutor)
+/// over all its [`progress`](super::traced::RewriteProcessTracedExecutor::progress)
+/// calls.
+///
+/// Nodes are keyed by hash-consed identity: a term reached several times, in
+/// the same layer or in different ones, is a single node.  Node ids are
+/// allocated in discovery order, so the initial term has id `0`.  Identical
+/// edges (same endpoints and same steps) are recorded once.
+#[derive(Clone, Debug)]
+pub struct RewriteGraph<LOS: RewritableLanguageOperatorSymbol> {
+    nodes: Vec<LanguageTerm<LOS>>,
+    node_ids: HashMap<LanguageTerm<LOS>, usize>,
+    edges: Vec<RewriteGraphEdge>,
+    known_edges: HashSet<RewriteGraphEdge>,
+    completed: HashSet<usize>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> RewriteGraph<LOS> {
+    pub(crate) fn new(initial_terms: &[LanguageTerm<LOS>]) -> Self {
+        let mut graph = Self {
+            nodes: vec![],
+            node_ids: HashMap::new(),
+            edges: vec![],
+            known_edges: HashSet::new(),
+            completed: HashSet::new(),
+        };
+        for term in initial_terms {
+            graph.add_node(term);
+        }
+        graph
+    }
+
+    pub(crate) fn add_node(&mut self, term: &LanguageTerm<LOS>) -> usize {
+        if let Some(id) = self.node_ids.get(term) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(term.clone());
+        self.node_ids.insert(term.clone(), id);
+        id
+    }
+
+    pub(crate) fn add_edge(&mut self, edge: RewriteGraphEdge) {
+        if self.known_edges.insert(edge.clone()) {
+            self.edges.push(edge);
+        }
+    }
+
+    pub(crate) fn mark_completed(&mut self, term: &LanguageTerm<LOS>) {
+        let id = self.add_node(term);
+        self.completed.insert(id);
+    }
+
+    /// All terms, indexed by node id.
+    pub fn get_nodes(&self) -> &[LanguageTerm<LOS>] {
+        &self.nodes
+    }
+
+    /// The node id of `term`, if it has been reached.
+    pub fn get_node_id(&self, term: &LanguageTerm<LOS>) -> Option<usize> {
+        self.node_ids.get(term).copied()
+    }
+
+    /// All edges, in the order they were first recorded.
+    pub fn get_edges(&self) -> &[RewriteGraphEdge] {
+        &self.edges
+    }
+
+    /// Whether the node has been moved to the executor's completed set.
+    pub fn is_completed(&self, id: usize) -> bool {
+        self.completed.contains(&id)
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol + fmt::Display> RewriteGraph<LOS> {
+    /// Renders the graph as a Graphviz DOT digraph.
+    ///
+    /// Nodes are labelled with the term's `Display` and completed nodes are
+    /// drawn with a double border.  Each edge is labelled with one
+    /// `rule_desc @ term_position` line per step.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph rewrite_graph {{").unwrap();
+        writeln!(dot, "  node [shape=box];").unwrap();
+        for (id, term) in self.nodes.iter().enumerate() {
+            let label = escape_dot_string(&term.to_string());
+            if self.is_completed(id) {
+                writeln!(dot, "  n{} [label=\"{}\", peripheries=2];", id, label).unwrap();
+            } else {
+                writeln!(dot, "  n{} [label=\"{}\"];", id, label).unwrap();
+            }
+        }
+        for edge in &self.edges {
+            let label: Vec<String> = edge
+                .steps
+                .iter()
+                .map(|step| format!("{} @ {}", step.rule_desc, step.term_position))
+                .collect();
+            writeln!(
+                dot,
+                "  n{} -> n{} [label=\"{}\"];",
+                edge.source,
+                edge.target,
+                escape_dot_string(&label.join("\n"))
+            )
+            .unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    /// Serializes the graph as a JSON document of the form
+    ///
+    /// ```json
+    /// {
+    ///   "nodes": [{"id": 0, "term": "…", "completed": false}, …],
+    ///   "edges": [{"source": 0, "target": 1, "steps": [
+    ///     {"rule": "…", "strategy_position": [0, 1], "term_position": []}
+    ///   ]}, …]
+    /// }
+    /// ```
+    ///
+    /// Positions are arrays of child indices from the root.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        writeln!(json, "{{").unwrap();
+        writeln!(json, "  \"nodes\": [").unwrap();
+        for (id, term) in self.nodes.iter().enumerate() {
+            let separator = if id + 1 < self.nodes.len() { "," } else { "" };
+            writeln!(
+                json,
+                "    {{\"id\": {}, \"term\": \"{}\", \"completed\": {}}}{}",
+                id,
+                escape_json_string(&term.to_string()),
+                self.is_completed(id),
+                separator
+            )
+            .unwrap();
+        }
+        writeln!(json, "  ],").unwrap();
+        writeln!(json, "  \"edges\": [").unwrap();
+        for (n, edge) in self.edges.iter().enumerate() {
+            let steps: Vec<String> = edge
+                .steps
+                .iter()
+                .map(|step| {
+                    format!(
+                        "{{\"rule\": \"{}\", \"strategy_position\": {}, \"term_position\": {}}}",
+                        escape_json_string(&step.rule_desc),
+                        position_to_json(&step.strategy_position),
+                        position_to_json(&step.term_position)
+                    )
+                })
+                .collect();
+            let separator = if n + 1 < self.edges.len() { "," } else { "" };
+            writeln!(
+                json,
+                "    {{\"source\": {}, \"target\": {}, \"steps\": [{}]}}{}",
+                edge.source,
+                edge.target,
+                steps.join(", "),
+                separator
+            )
+            .unwrap();
+        }
+        writeln!(json, "  ]").unwrap();
+        writeln!(json, "}}").unwrap();
+        json
+    }
+}
+
+fn position_to_json(position: &TreePosition) -> String {
+    let coords: Vec<String> = position
+        .get_absolute_coordinates_from_root()
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    format!("[{}]", coords.join(", "))
+}
+
+fn escape_json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,352 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use crate::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use crate::process::budget::{BudgetExhaustion, RewriteBudget};
+use crate::process::control::{EvaluationControl, RewriteExecutionError};
+use crate::process::strategy::{run_traced_step, RewriteProcess};
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
+
+/// Which strategy found a [`DerivationStep`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DerivationDirection {
+    /// The strategy rewrote `source` into `target`.
+    Forward,
+    /// The backward strategy (see
+    /// [`with_backward_strategy`](ReachabilitySearch::with_backward_strategy))
+    /// rewrote `target` into `source`.
+    Backward,
+}
+
+/// One step of a derivation found by a [`ReachabilitySearch`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DerivationStep<LOS: RewritableLanguageOperatorSymbol> {
+    pub source: LanguageTerm<LOS>,
+    /// Same as [`AtomicRuleApplication::rule_chain`](super::traced::AtomicRuleApplication::rule_chain),
+    /// with positions in the strategy given by `direction`.
+    pub rule_chain: Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+    pub target: LanguageTerm<LOS>,
+    pub direction: DerivationDirection,
+}
+
+/// The result of [`ReachabilitySearch::search`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ReachabilityOutcome<LOS: RewritableLanguageOperatorSymbol> {
+    /// A shortest derivation from the source to the target, empty if they are
+    /// the same term.  If the budget ran out in the middle of a layer of a
+    /// bidirectional search, the derivation met in that layer may not be the
+    /// shortest one.
+    Found(Vec<DerivationStep<LOS>>),
+    /// Every term reachable from the source (or, in a bidirectional search,
+    /// from which the target is reachable) was visited without meeting the
+    /// target.
+    Unreachable,
+    /// There is no derivation of at most
+    /// [`max_depth`](ReachabilitySearch::with_max_depth) steps.
+    DepthLimitReached,
+    /// The budget ran out before a derivation was found.
+    BudgetExhausted(BudgetExhaustion),
+}
+
+/// How a term was reached by one side of the search.
+#[allow(clippy::type_complexity)]
+struct Visit<LOS: RewritableLanguageOperatorSymbol> {
+    depth: usize,
+    /// The previous term and the rule chain between them, `None` for the
+    /// term the side started from.
+    parent: Option<(
+        LanguageTerm<LOS>,
+        Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+    )>,
+}
+
+/// One side of the search: the terms visited so far and the last layer.
+struct SearchSide<'s, LOS: RewritableLanguageOperatorSymbol> {
+    strategy: &'s RewriteProcess<LOS>,
+    visited: HashMap<LanguageTerm<LOS>, Visit<LOS>>,
+    frontier: Vec<LanguageTerm<LOS>>,
+    depth: usize,
+}
+
+impl<'s, LOS: RewritableLanguageOperatorSymbol> SearchSide<'s, LOS> {
+    fn new(strategy: &'s RewriteProcess<LOS>, start: &LanguageTerm<LOS>) -> Self {
+        let mut visited = HashMap::new();
+        visited.insert(
+            start.clone(),
+            Visit {
+                depth: 0,
+                parent: None,
+            },
+        );
+        Self {
+            strategy,
+            visited,
+            frontier: vec![start.clone()],
+            depth: 0,
+        }
+    }
+
+    /// The path from the start of this side to `term`, start first, as
+    /// `(source, rule chain, target)` triples.
+    #[allow(clippy::type_complexity)]
+    fn path_to(
+        &self,
+        term: &LanguageTerm<LOS>,
+    ) -> Vec<(
+        LanguageTerm<LOS>,
+        Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+        LanguageTerm<LOS>,
+    )> {
+        let mut path = Vec::new();
+        let mut current = term.clone();
+        while let Some((parent, rule_chain)) = &self.visited[&current].parent {
+            path.push((parent.clone(), rule_chain.clone(), current.clone()));
+            current = parent.clone();
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Breadth-first search for a shortest derivation between two terms, over
+/// the one-step rewrite relation of a [`RewriteProcess`]: `t → u` whenever a
+/// single application of the strategy to `t` yields `u`.
+///
+/// Each term is expanded at most once, visited terms being keyed on their
+/// hash-consing identity.  With a backward strategy, the search is
+/// bidirectional: it also searches backwards from the target, always
+/// expanding the smaller of the two frontiers, and stops as soon as they
+/// meet.
+///
+/// ```rust,ignore
+/// let search = ReachabilitySearch::new(strategy).with_max_depth(10);
+/// match search.search(&source, &target, &mut factory)? {
+///     ReachabilityOutcome::Found(derivation) => { /* shortest derivation */ }
+///     _ => { /* not found */ }
+/// }
+/// ```
+pub struct ReachabilitySearch<LOS: RewritableLanguageOperatorSymbol> {
+    strategy: RewriteProcess<LOS>,
+    backward_strategy: Option<RewriteProcess<LOS>>,
+    max_depth: Option<usize>,
+    budget: RewriteBudget,
+    check_signatures: bool,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> ReachabilitySearch<LOS> {
+    /// Creates an unbounded, forward-only search over the steps of `strategy`.
+    pub fn new(strategy: RewriteProcess<LOS>) -> Self {
+        Self {
+            strategy,
+            backward_strategy: None,
+            max_depth: None,
+            budget: RewriteBudget::new(),
+            check_signatures: false,
+        }
+    }
+
+    /// Makes the search bidirectional: `backward_strategy` must rewrite `u`
+    /// into `t` whenever the strategy rewrites `t` into `u`, typically the
+    /// same rules oriented the other way round.
+    pub fn with_backward_strategy(mut self, backward_strategy: RewriteProcess<LOS>) -> Self {
+        self.backward_strategy = Some(backward_strategy);
+        self
+    }
+
+    /// Only looks for derivations of at most `max_depth` steps.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Limits the whole search with `budget`.  The frontier size limit
+    /// applies to each side's frontier.
+    pub fn with_budget(mut self, budget: RewriteBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// See
+    /// [`RewriteProcessTracedExecutor::with_signature_checking`](super::traced::RewriteProcessTracedExecutor::with_signature_checking).
+    pub fn with_signature_checking(mut self, enabled: bool) -> Self {
+        self.check_signatures = enabled;
+        self
+    }
+
+    /// Looks for a shortest derivation from `source` to `target`.
+    ///
+    /// Returns an error if a strategy fails, e.g. on an ill-formed rule
+    /// output when signature checking is enabled.
+    pub fn search(
+        &self,
+        source: &LanguageTerm<LOS>,
+        target: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<ReachabilityOutcome<LOS>, RewriteExecutionError<LOS>> {
+        let mut control =
+            EvaluationControl::new(self.check_signatures).with_budget(self.budget.clone(), 0);
+        let mut forward = SearchSide::new(&self.strategy, source);
+        let mut backward = self
+            .backward_strategy
+            .as_ref()
+            .map(|strategy| SearchSide::new(strategy, target));
+        if source == target {
+            return Ok(ReachabilityOutcome::Found(vec![]));
+        }
+        // Without a backward side, the forward side only has to meet the
+        // target itself.
+        let goal = SearchSide::new(&self.strategy, target);
+        loop {
+            let depth = forward.depth + backward.as_ref().map_or(0, |side| side.depth);
+            if self.max_depth.is_some_and(|max| depth >= max) {
+                return Ok(ReachabilityOutcome::DepthLimitReached);
+            }
+            let (meeting, direction) = match &mut backward {
+                None => {
+                    let meeting = self.expand(&mut forward, &goal, factory, &mut control)?;
+                    (meeting, DerivationDirection::Forward)
+                }
+                Some(backward) if backward.frontier.len() < forward.frontier.len() => {
+                    let meeting = self.expand(backward, &forward, factory, &mut control)?;
+                    (meeting, DerivationDirection::Backward)
+                }
+                Some(backward) => {
+                    let meeting = self.expand(&mut forward, backward, factory, &mut control)?;
+                    (meeting, DerivationDirection::Forward)
+                }
+            };
+            if let Some(meeting) = meeting {
+                return Ok(ReachabilityOutcome::Found(join_paths(
+                    &forward,
+                    backward.as_ref(),
+                    &meeting,
+                )));
+            }
+            if let Some(exhaustion) = control.take_exhaustion() {
+                return Ok(ReachabilityOutcome::BudgetExhausted(exhaustion));
+            }
+            let exhausted = match direction {
+                DerivationDirection::Forward => forward.frontier.is_empty(),
+                DerivationDirection::Backward => backward
+                    .as_ref()
+                    .is_some_and(|side| side.frontier.is_empty()),
+            };
+            if exhausted {
+                return Ok(ReachabilityOutcome::Unreachable);
+            }
+        }
+    }
+
+    /// Replaces the frontier of `side` with the terms it reaches in one step
+    /// and that it had not visited.  Returns the new term visited by `other`
+    /// that is closest to the start of `other`, if any.  If the budget runs
+    /// out, the layer is left unfinished but a term already met is still
+    /// returned.
+    fn expand(
+        &self,
+        side: &mut SearchSide<'_, LOS>,
+        other: &SearchSide<'_, LOS>,
+        factory: &mut TermFactory<LOS>,
+        control: &mut EvaluationControl<LOS>,
+    ) -> Result<Option<LanguageTerm<LOS>>, RewriteExecutionError<LOS>> {
+        let root_sp = PositionInRewriteProcess::get_root_position();
+        let root_tp = PositionInLanguageTerm::get_root_position();
+        let mut next_frontier = Vec::new();
+        let mut meeting: Option<(usize, LanguageTerm<LOS>)> = None;
+        for term in &side.frontier {
+            let results = run_traced_step(
+                side.strategy,
+                term,
+                term,
+                &root_tp,
+                &root_sp,
+                factory,
+                control,
+            );
+            if let Some(error) = control.take_error() {
+                return Err(error);
+            }
+            if control.get_exhaustion().is_some() {
+                return Ok(meeting.map(|(_, term)| term));
+            }
+            for (rule_chain, result) in results {
+                if side.visited.contains_key(&result) {
+                    continue;
+                }
+                side.visited.insert(
+                    result.clone(),
+                    Visit {
+                        depth: side.depth + 1,
+                        parent: Some((term.clone(), rule_chain)),
+                    },
+                );
+                if let Some(visit) = other.visited.get(&result) {
+                    if meeting
+                        .as_ref()
+                        .is_none_or(|(depth, _)| visit.depth < *depth)
+                    {
+                        meeting = Some((visit.depth, result.clone()));
+                    }
+                }
+                next_frontier.push(result);
+            }
+            if self
+                .budget
+                .max_frontier_size
+                .is_some_and(|max| next_frontier.len() > max)
+            {
+                control.exhaust(BudgetExhaustion::FrontierSize);
+                return Ok(meeting.map(|(_, term)| term));
+            }
+        }
+        side.frontier = next_frontier;
+        side.depth += 1;
+        Ok(meeting.map(|(_, term)| term))
+    }
+}
+
+/// The derivation from the start of `forward` to `meeting`, followed by the
+/// derivation from `meeting` to the start of `backward`.
+fn join_paths<LOS: RewritableLanguageOperatorSymbol>(
+    forward: &SearchSide<'_, LOS>,
+    backward: Option<&SearchSide<'_, LOS>>,
+    meeting: &LanguageTerm<LOS>,
+) -> Vec<DerivationStep<LOS>> {
+    let mut derivation: Vec<DerivationStep<LOS>> = forward
+        .path_to(meeting)
+        .into_iter()
+        .map(|(source, rule_chain, target)| DerivationStep {
+            source,
+            rule_chain,
+            target,
+            direction: DerivationDirection::Forward,
+        })
+        .collect();
+    if let Some(backward) = backward {
+        // The backward side rewrote each target into its source.
+        derivation.extend(backward.path_to(meeting).into_iter().rev().map(
+            |(target, rule_chain, source)| DerivationStep {
+                source,
+                rule_chain,
+                target,
+                direction: DerivationDirection::Backward,
+            },
+        ));
+    }
+    derivation
+}
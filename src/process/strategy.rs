@@ -14,7 +14,12 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+use std::collections::HashMap;
+use std::rc::Rc;
+
 use crate::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use crate::process::control::{CycleRuleApplication, EvaluationControl, RewriteCycle};
+use crate::process::environment::{StrategyEnvironment, StrategyEnvironmentError};
 use crate::rule::RewriteRule;
 use crate::term::syntax::{
     LanguageTerm, LanguageTermNode, RewritableLanguageOperatorSymbol, TermFactory,
@@ -40,6 +45,33 @@ pub enum DepthOrder {
     Innermost,
 }
 
+/// A generic traversal, applied by [`RewriteProcess::Traverse`].
+///
+/// In the descriptions below, `s` is the traversed process.  When `s` has
+/// several results, every combination is returned.  Each traversal tries `s`
+/// at the node and recurses into the children with
+/// [`SomeChildren`](RewriteProcess::SomeChildren), so that it fails exactly
+/// when `s` succeeds nowhere, as Stratego's `topdown(try(s))` would if it
+/// failed instead of returning its argument unchanged.
+///
+/// The one-step traversals `outermost_step` and `innermost_step` need no
+/// variant: see [`RewriteProcess::outermost_step`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Traversal {
+    /// One pre-order pass: try `s` at the node, keeping the node if it fails,
+    /// then traverse the children of the result.  Fails if `s` succeeds
+    /// nowhere.
+    TopDown,
+    /// One post-order pass: traverse the children, then try `s` at the
+    /// rebuilt node, keeping it if `s` fails.  Fails if `s` succeeds nowhere.
+    BottomUp,
+    /// One parallel step: `s` at the node if it succeeds there, else the step
+    /// on every child where it succeeds, simultaneously.  Rewrites every
+    /// outermost position where `s` succeeds, which are disjoint.  Fails if
+    /// `s` succeeds nowhere.
+    ParallelOutermostStep,
+}
+
 /// A composable rewriting strategy.
 ///
 /// Evaluating a `RewriteProcess` on a term via [`run_to_completion`] returns
@@ -64,12 +96,44 @@ pub enum DepthOrder {
 /// Innermost  AnyChild(Leftmost, Innermost)  AnyChild(Rightmost, Innermost)
 /// ```
 ///
-/// Combining with [`TryOnePath`](RewriteProcess::TryOnePath):
+/// Combining with [`TryOnePath`](RewriteProcess::TryOnePath), which is what
+/// [`outermost_step`](RewriteProcess::outermost_step) and
+/// [`innermost_step`](RewriteProcess::innermost_step) build:
 ///
 /// ```text
 /// outermost_step(r) = TryOnePath([ Rule(r), AnyChild(Leftmost, Outermost, Rule(r)) ])
 /// innermost_step(r) = TryOnePath([ AnyChild(Leftmost, Innermost, Rule(r)), Rule(r) ])
 /// ```
+///
+/// ## Congruences
+///
+/// [`AllChildren`](RewriteProcess::AllChildren),
+/// [`OneChild`](RewriteProcess::OneChild) and
+/// [`SomeChildren`](RewriteProcess::SomeChildren) apply a process one level
+/// down, to all, one or some of the children, as Stratego's `all`, `one` and
+/// `some`.  Each child is rewritten independently with the same context.
+///
+/// ## Generic traversals
+///
+/// [`Traverse`](RewriteProcess::Traverse) applies a process throughout a term
+/// according to a [`Traversal`], for the traversals that recurse into every
+/// child and thus cannot be spelled out with the other combinators.  Use the
+/// constructors ([`topdown`](RewriteProcess::topdown),
+/// [`innermost`](RewriteProcess::innermost), ...) for these as well as for
+/// the encodings above.
+///
+/// ## Named strategies
+///
+/// [`Call`](RewriteProcess::Call) evaluates a process bound to a name in a
+/// [`StrategyEnvironment`], which makes recursive strategies possible:
+///
+/// ```text
+/// simplify = Pipe( AllChildren(Call(simplify)),
+///                  Repeat(Pipe(root_rules, Call(simplify))) )
+/// ```
+///
+/// The environment is given by an enclosing [`Let`](RewriteProcess::Let),
+/// see [`let_in`](RewriteProcess::let_in).
 pub enum RewriteProcess<LOS: RewritableLanguageOperatorSymbol> {
     /// Apply a single rule at the root of the current term.
     /// Returns one result if the rule fires, nothing if it does not.
@@ -80,6 +144,22 @@ pub enum RewriteProcess<LOS: RewritableLanguageOperatorSymbol> {
     /// Fails if no child admits a successful application.
     AnyChild(SiblingOrder, DepthOrder, Box<Self>),
 
+    /// Apply the inner process to every child at once; returns every
+    /// combination of the children's results.  Fails if it fails on any child.
+    /// Succeeds without change on a term without children.
+    /// Corresponds to `all` in Stratego.
+    AllChildren(Box<Self>),
+
+    /// Apply the inner process to exactly one child; returns the union of the
+    /// results over all children, left to right.  Fails if it fails on every
+    /// child.  Corresponds to `one` in Stratego, with all alternatives kept.
+    OneChild(Box<Self>),
+
+    /// Apply the inner process to every child where it succeeds, leaving the
+    /// others unchanged; returns every combination of the results.  Fails if
+    /// it fails on every child.  Corresponds to `some` in Stratego.
+    SomeChildren(Box<Self>),
+
     /// Sequential composition: apply `a`, then apply `b` to every result of `a`.
     /// Fails if `a` fails.
     Pipe(Box<Self>, Box<Self>),
@@ -96,6 +176,258 @@ pub enum RewriteProcess<LOS: RewritableLanguageOperatorSymbol> {
     /// Try all alternatives; return the union of all their results.
     /// Corresponds to `|` / `+` in Maude / Stratego.
     TryAllPaths(Vec<Self>),
+
+    /// Apply the inner process throughout the term, according to the
+    /// [`Traversal`].  In a traced execution, the whole traversal is one step.
+    Traverse(Traversal, Box<Self>),
+
+    /// Evaluate the process bound to the name in the environment of the
+    /// innermost enclosing [`Let`](RewriteProcess::Let).  Evaluating a name
+    /// that is not bound is an error.
+    Call(String),
+
+    /// Evaluate the inner process, resolving its `Call`s in the
+    /// [`StrategyEnvironment`].
+    Let(Rc<StrategyEnvironment<LOS>>, Box<Self>),
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> RewriteProcess<LOS> {
+    /// One top-down pass of `process`; see [`Traversal::TopDown`].
+    pub fn topdown(process: Self) -> Self {
+        RewriteProcess::Traverse(Traversal::TopDown, Box::new(process))
+    }
+
+    /// One bottom-up pass of `process`; see [`Traversal::BottomUp`].
+    pub fn bottomup(process: Self) -> Self {
+        RewriteProcess::Traverse(Traversal::BottomUp, Box::new(process))
+    }
+
+    /// One step at the leftmost-outermost position where `process` succeeds:
+    /// `TryOnePath([process, AnyChild(Leftmost, Outermost, process)])`.
+    /// Fails if `process` succeeds nowhere.
+    pub fn outermost_step(process: Self) -> Self {
+        let (at_root, below) = process.share();
+        RewriteProcess::TryOnePath(vec![
+            at_root,
+            RewriteProcess::AnyChild(
+                SiblingOrder::Leftmost,
+                DepthOrder::Outermost,
+                Box::new(below),
+            ),
+        ])
+    }
+
+    /// One step at the leftmost-innermost position where `process` succeeds:
+    /// `TryOnePath([AnyChild(Leftmost, Innermost, process), process])`.
+    /// Fails if `process` succeeds nowhere.
+    pub fn innermost_step(process: Self) -> Self {
+        let (below, at_root) = process.share();
+        RewriteProcess::TryOnePath(vec![
+            RewriteProcess::AnyChild(
+                SiblingOrder::Leftmost,
+                DepthOrder::Innermost,
+                Box::new(below),
+            ),
+            at_root,
+        ])
+    }
+
+    /// One parallel-outermost step; see [`Traversal::ParallelOutermostStep`].
+    pub fn parallel_outermost_step(process: Self) -> Self {
+        RewriteProcess::Traverse(Traversal::ParallelOutermostStep, Box::new(process))
+    }
+
+    /// Leftmost-outermost normalization (call-by-name): repeats
+    /// [`outermost_step`](Self::outermost_step) until `process` succeeds
+    /// nowhere.
+    pub fn outermost(process: Self) -> Self {
+        RewriteProcess::Repeat(Box::new(Self::outermost_step(process)))
+    }
+
+    /// Leftmost-innermost normalization (call-by-value): repeats
+    /// [`innermost_step`](Self::innermost_step) until `process` succeeds
+    /// nowhere.
+    pub fn innermost(process: Self) -> Self {
+        RewriteProcess::Repeat(Box::new(Self::innermost_step(process)))
+    }
+
+    /// Parallel-outermost normalization: repeats
+    /// [`parallel_outermost_step`](Self::parallel_outermost_step) until
+    /// `process` succeeds nowhere.
+    pub fn parallel_outermost(process: Self) -> Self {
+        RewriteProcess::Repeat(Box::new(Self::parallel_outermost_step(process)))
+    }
+
+    /// Evaluates `process` in `environment`, checking that every name it
+    /// calls is defined there.
+    pub fn let_in(
+        environment: Rc<StrategyEnvironment<LOS>>,
+        process: Self,
+    ) -> Result<Self, StrategyEnvironmentError> {
+        environment.check_process(&process)?;
+        Ok(RewriteProcess::Let(environment, Box::new(process)))
+    }
+
+    /// Splits `process` into two copies of itself, which share its rules.
+    fn share(self) -> (Self, Self) {
+        let share_box = |process: Box<Self>| {
+            let (a, b) = process.share();
+            (Box::new(a), Box::new(b))
+        };
+        let share_all = |processes: Vec<Self>| -> (Vec<Self>, Vec<Self>) {
+            processes.into_iter().map(Self::share).unzip()
+        };
+        match self {
+            RewriteProcess::Rule(rule) => {
+                let rule: Rc<dyn RewriteRule<LOS>> = Rc::from(rule);
+                (
+                    RewriteProcess::Rule(Box::new(SharedRule(rule.clone()))),
+                    RewriteProcess::Rule(Box::new(SharedRule(rule))),
+                )
+            }
+            RewriteProcess::AnyChild(sibling_order, depth_order, p) => {
+                let (a, b) = share_box(p);
+                (
+                    RewriteProcess::AnyChild(sibling_order, depth_order, a),
+                    RewriteProcess::AnyChild(sibling_order, depth_order, b),
+                )
+            }
+            RewriteProcess::AllChildren(p) => {
+                let (a, b) = share_box(p);
+                (
+                    RewriteProcess::AllChildren(a),
+                    RewriteProcess::AllChildren(b),
+                )
+            }
+            RewriteProcess::OneChild(p) => {
+                let (a, b) = share_box(p);
+                (RewriteProcess::OneChild(a), RewriteProcess::OneChild(b))
+            }
+            RewriteProcess::SomeChildren(p) => {
+                let (a, b) = share_box(p);
+                (
+                    RewriteProcess::SomeChildren(a),
+                    RewriteProcess::SomeChildren(b),
+                )
+            }
+            RewriteProcess::Pipe(p, q) => {
+                let (pa, pb) = share_box(p);
+                let (qa, qb) = share_box(q);
+                (RewriteProcess::Pipe(pa, qa), RewriteProcess::Pipe(pb, qb))
+            }
+            RewriteProcess::Repeat(p) => {
+                let (a, b) = share_box(p);
+                (RewriteProcess::Repeat(a), RewriteProcess::Repeat(b))
+            }
+            RewriteProcess::TryOnePath(alternatives) => {
+                let (a, b) = share_all(alternatives);
+                (RewriteProcess::TryOnePath(a), RewriteProcess::TryOnePath(b))
+            }
+            RewriteProcess::TryAllPaths(alternatives) => {
+                let (a, b) = share_all(alternatives);
+                (
+                    RewriteProcess::TryAllPaths(a),
+                    RewriteProcess::TryAllPaths(b),
+                )
+            }
+            RewriteProcess::Traverse(traversal, p) => {
+                let (a, b) = share_box(p);
+                (
+                    RewriteProcess::Traverse(traversal, a),
+                    RewriteProcess::Traverse(traversal, b),
+                )
+            }
+            RewriteProcess::Call(name) => (
+                RewriteProcess::Call(name.clone()),
+                RewriteProcess::Call(name),
+            ),
+            RewriteProcess::Let(environment, p) => {
+                let (a, b) = share_box(p);
+                (
+                    RewriteProcess::Let(environment.clone(), a),
+                    RewriteProcess::Let(environment, b),
+                )
+            }
+        }
+    }
+
+    /// Returns the sub-process at `position`, numbered as in the
+    /// `rule_chain` of the traced executor: the inner process of `AnyChild`,
+    /// the congruences, `Repeat` and `Traverse` is at child `0`, `Pipe(a, b)`
+    /// has `a` at `0` and `b` at `1`, and the alternatives of `TryOnePath` and
+    /// `TryAllPaths` are numbered from `0`.
+    ///
+    /// The process of a `Let`, and the definition evaluated by a `Call`, are
+    /// at child `0`, so that positions go through the called definitions.
+    /// Returns `None` if there is no such sub-process, e.g. below a `Call`
+    /// outside of any `Let`.
+    pub fn get_process_at_position(&self, position: &PositionInRewriteProcess) -> Option<&Self> {
+        self.get_process_at_position_in(position, None)
+    }
+
+    /// Returns the rule of the `Rule(...)` leaf at `position`, see
+    /// [`get_process_at_position`](Self::get_process_at_position).
+    pub fn get_rule_at_position(
+        &self,
+        position: &PositionInRewriteProcess,
+    ) -> Option<&dyn RewriteRule<LOS>> {
+        self.get_rule_at_position_in(position, None)
+    }
+
+    /// As [`get_rule_at_position`](Self::get_rule_at_position), `environment`
+    /// resolving the `Call`s outside of any `Let`.
+    pub(crate) fn get_rule_at_position_in<'p>(
+        &'p self,
+        position: &PositionInRewriteProcess,
+        environment: Option<&'p StrategyEnvironment<LOS>>,
+    ) -> Option<&'p dyn RewriteRule<LOS>> {
+        match self.get_process_at_position_in(position, environment)? {
+            RewriteProcess::Rule(rule) => Some(rule.as_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_process_at_position_in<'p>(
+        &'p self,
+        position: &PositionInRewriteProcess,
+        mut environment: Option<&'p StrategyEnvironment<LOS>>,
+    ) -> Option<&'p Self> {
+        let mut current = self;
+        for &n in position.get_absolute_coordinates_from_root() {
+            if n != 0 {
+                if let RewriteProcess::Call(_) | RewriteProcess::Let(_, _) = current {
+                    return None;
+                }
+            }
+            current = match current {
+                RewriteProcess::Rule(_) => return None,
+                RewriteProcess::Call(name) => environment?.get_definition(name)?,
+                RewriteProcess::Let(inner_environment, process) => {
+                    environment = Some(inner_environment.as_ref());
+                    process.as_ref()
+                }
+                RewriteProcess::AnyChild(_, _, process)
+                | RewriteProcess::AllChildren(process)
+                | RewriteProcess::OneChild(process)
+                | RewriteProcess::SomeChildren(process)
+                | RewriteProcess::Repeat(process)
+                | RewriteProcess::Traverse(_, process) => {
+                    if n != 0 {
+                        return None;
+                    }
+                    process.as_ref()
+                }
+                RewriteProcess::Pipe(a, b) => match n {
+                    0 => a.as_ref(),
+                    1 => b.as_ref(),
+                    _ => return None,
+                },
+                RewriteProcess::TryOnePath(alternatives)
+                | RewriteProcess::TryAllPaths(alternatives) => alternatives.get(n)?,
+            };
+        }
+        Some(current)
+    }
 }
 
 // == evaluators ================================================================
@@ -110,10 +442,11 @@ pub(crate) fn run_to_completion<LOS: RewritableLanguageOperatorSymbol>(
     context_term: &LanguageTerm<LOS>,
     position: &PositionInLanguageTerm,
     factory: &mut TermFactory<LOS>,
+    control: &mut EvaluationControl<LOS>,
 ) -> Vec<LanguageTerm<LOS>> {
     match this {
-        RewriteProcess::Rule(rule) => rule
-            .try_apply(term, context_term, position, factory)
+        RewriteProcess::Rule(rule) => control
+            .apply_rule(rule.as_ref(), term, context_term, position, factory)
             .into_iter()
             .collect(),
 
@@ -125,59 +458,130 @@ pub(crate) fn run_to_completion<LOS: RewritableLanguageOperatorSymbol>(
             for n in indices {
                 let child = &term.sub_terms[n];
                 let child_pos = position.get_position_of_nth_child(n);
-                let (first, second) = match depth_order {
-                    DepthOrder::Outermost => (
-                        run_to_completion(process, child, context_term, &child_pos, factory),
-                        run_to_completion(this, child, context_term, &child_pos, factory),
-                    ),
-                    DepthOrder::Innermost => (
-                        run_to_completion(this, child, context_term, &child_pos, factory),
-                        run_to_completion(process, child, context_term, &child_pos, factory),
-                    ),
+                // The second process is only evaluated if the first one fails.
+                let processes = match depth_order {
+                    DepthOrder::Outermost => [process.as_ref(), this],
+                    DepthOrder::Innermost => [this, process.as_ref()],
                 };
-                if !first.is_empty() {
-                    return first
-                        .into_iter()
-                        .map(|rw| rebuild_child(term, n, rw, factory))
-                        .collect();
-                }
-                if !second.is_empty() {
-                    return second
-                        .into_iter()
-                        .map(|rw| rebuild_child(term, n, rw, factory))
-                        .collect();
+                for p in processes {
+                    let results =
+                        run_to_completion(p, child, context_term, &child_pos, factory, control);
+                    if !results.is_empty() {
+                        return results
+                            .into_iter()
+                            .map(|rw| rebuild_child(term, n, rw, factory))
+                            .collect();
+                    }
                 }
             }
             vec![]
         }
 
-        RewriteProcess::Pipe(a, b) => run_to_completion(a, term, context_term, position, factory)
-            .into_iter()
-            .flat_map(|ti| {
-                let new_ctx = replace_at_position(context_term, position, ti.clone(), factory);
-                run_to_completion(b, &ti, &new_ctx, position, factory)
-            })
-            .collect(),
+        RewriteProcess::AllChildren(process) | RewriteProcess::SomeChildren(process) => {
+            let keep_failing = matches!(this, RewriteProcess::SomeChildren(_));
+            run_on_children(
+                process,
+                keep_failing,
+                term,
+                context_term,
+                position,
+                factory,
+                control,
+            )
+        }
 
-        RewriteProcess::Repeat(process) => {
-            let results = run_to_completion(process, term, context_term, position, factory);
-            if results.is_empty() {
-                vec![term.clone()]
-            } else {
-                results
+        RewriteProcess::OneChild(process) => {
+            let mut results = Vec::new();
+            for (n, child) in term.sub_terms.iter().enumerate() {
+                let child_pos = position.get_position_of_nth_child(n);
+                for rw in
+                    run_to_completion(process, child, context_term, &child_pos, factory, control)
+                {
+                    results.push(rebuild_child(term, n, rw, factory));
+                }
+            }
+            control.limit_results(results)
+        }
+
+        RewriteProcess::Pipe(a, b) => {
+            let results: Vec<LanguageTerm<LOS>> =
+                run_to_completion(a, term, context_term, position, factory, control)
                     .into_iter()
                     .flat_map(|ti| {
                         let new_ctx =
                             replace_at_position(context_term, position, ti.clone(), factory);
-                        run_to_completion(this, &ti, &new_ctx, position, factory)
+                        run_to_completion(b, &ti, &new_ctx, position, factory, control)
                     })
-                    .collect()
+                    .collect();
+            control.limit_results(results)
+        }
+
+        RewriteProcess::Repeat(process) => {
+            // Depth-first over the branches, with an explicit stack so that
+            // long (or, under a budget, endless) loops do not overflow.  Each
+            // pending entry records its depth in the branch.
+            let mut results = Vec::new();
+            let mut pending = vec![(term.clone(), context_term.clone(), 0)];
+            // The current branch, only tracked when detecting cycles.
+            let mut branch: Vec<(LanguageTerm<LOS>, LanguageTerm<LOS>)> = Vec::new();
+            let mut on_branch: HashMap<LanguageTerm<LOS>, usize> = HashMap::new();
+            while let Some((current, current_ctx, depth)) = pending.pop() {
+                // When halted, the current term is the partial result.
+                if control.poll_halt() {
+                    results.push(current);
+                    continue;
+                }
+                if control.detects_cycles() {
+                    for (left, _) in branch.drain(depth..) {
+                        on_branch.remove(&left);
+                    }
+                    if let Some(&start) = on_branch.get(&current) {
+                        let cycle = replay_cycle(
+                            process,
+                            &branch[start..],
+                            &current_ctx,
+                            position,
+                            factory,
+                            control,
+                        );
+                        control.report_cycle(cycle);
+                        results.push(current);
+                        continue;
+                    }
+                    on_branch.insert(current.clone(), branch.len());
+                    branch.push((current.clone(), current_ctx.clone()));
+                }
+                let next =
+                    run_to_completion(process, &current, &current_ctx, position, factory, control);
+                if next.is_empty() {
+                    results.push(current);
+                }
+                for ti in next.into_iter().rev() {
+                    let new_ctx = replace_at_position(&current_ctx, position, ti.clone(), factory);
+                    pending.push((ti, new_ctx, depth + 1));
+                }
             }
+            control.limit_results(results)
+        }
+
+        RewriteProcess::Traverse(traversal, process) => {
+            let results = traverse(
+                this,
+                *traversal,
+                process,
+                term,
+                context_term,
+                position,
+                factory,
+                control,
+            );
+            control.limit_results(results)
         }
 
         RewriteProcess::TryOnePath(processes) => {
             for process in processes {
-                let results = run_to_completion(process, term, context_term, position, factory);
+                let results =
+                    run_to_completion(process, term, context_term, position, factory, control);
                 if !results.is_empty() {
                     return results;
                 }
@@ -185,10 +589,39 @@ pub(crate) fn run_to_completion<LOS: RewritableLanguageOperatorSymbol>(
             vec![]
         }
 
-        RewriteProcess::TryAllPaths(processes) => processes
-            .iter()
-            .flat_map(|process| run_to_completion(process, term, context_term, position, factory))
-            .collect(),
+        RewriteProcess::TryAllPaths(processes) => {
+            let results: Vec<LanguageTerm<LOS>> = processes
+                .iter()
+                .flat_map(|process| {
+                    run_to_completion(process, term, context_term, position, factory, control)
+                })
+                .collect();
+            control.limit_results(results)
+        }
+
+        RewriteProcess::Call(name) => {
+            let environment = control.get_environment();
+            match environment
+                .as_ref()
+                .and_then(|env| env.get_definition(name))
+            {
+                Some(definition) => {
+                    run_to_completion(definition, term, context_term, position, factory, control)
+                }
+                None => {
+                    control.report_unbound_name(name);
+                    vec![]
+                }
+            }
+        }
+
+        RewriteProcess::Let(environment, process) => {
+            let enclosing = control.replace_environment(Some(environment.clone()));
+            let results =
+                run_to_completion(process, term, context_term, position, factory, control);
+            control.replace_environment(enclosing);
+            results
+        }
     }
 }
 
@@ -208,14 +641,16 @@ pub(crate) fn run_traced_step<LOS: RewritableLanguageOperatorSymbol>(
     term_position: &PositionInLanguageTerm,
     strategy_position: &PositionInRewriteProcess,
     factory: &mut TermFactory<LOS>,
+    control: &mut EvaluationControl<LOS>,
 ) -> Vec<(
     Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
     LanguageTerm<LOS>,
 )> {
     match this {
-        RewriteProcess::Rule(rule) => rule
-            .try_apply(term, context_term, term_position, factory)
+        RewriteProcess::Rule(rule) => control
+            .apply_rule(rule.as_ref(), term, context_term, term_position, factory)
             .map(|result| {
+                control.record_firing(strategy_position, term_position, term, &result);
                 (
                     vec![(strategy_position.clone(), term_position.clone())],
                     result,
@@ -233,77 +668,88 @@ pub(crate) fn run_traced_step<LOS: RewritableLanguageOperatorSymbol>(
             for n in indices {
                 let child = &term.sub_terms[n];
                 let child_tp = term_position.get_position_of_nth_child(n);
-                let (first, second) = match depth_order {
-                    DepthOrder::Outermost => (
-                        run_traced_step(
-                            process,
-                            child,
-                            context_term,
-                            &child_tp,
-                            &inner_sp,
-                            factory,
-                        ),
-                        run_traced_step(
-                            this,
-                            child,
-                            context_term,
-                            &child_tp,
-                            strategy_position,
-                            factory,
-                        ),
-                    ),
-                    DepthOrder::Innermost => (
-                        run_traced_step(
-                            this,
-                            child,
-                            context_term,
-                            &child_tp,
-                            strategy_position,
-                            factory,
-                        ),
-                        run_traced_step(
-                            process,
-                            child,
-                            context_term,
-                            &child_tp,
-                            &inner_sp,
-                            factory,
-                        ),
-                    ),
+                // The second process is only evaluated if the first one fails.
+                let processes = match depth_order {
+                    DepthOrder::Outermost => {
+                        [(process.as_ref(), &inner_sp), (this, strategy_position)]
+                    }
+                    DepthOrder::Innermost => {
+                        [(this, strategy_position), (process.as_ref(), &inner_sp)]
+                    }
                 };
-                if !first.is_empty() {
-                    return first
-                        .into_iter()
-                        .map(|(chain, rw)| (chain, rebuild_child(term, n, rw, factory)))
-                        .collect();
-                }
-                if !second.is_empty() {
-                    return second
-                        .into_iter()
-                        .map(|(chain, rw)| (chain, rebuild_child(term, n, rw, factory)))
-                        .collect();
+                for (p, sp) in processes {
+                    let results =
+                        run_traced_step(p, child, context_term, &child_tp, sp, factory, control);
+                    if !results.is_empty() {
+                        return results
+                            .into_iter()
+                            .map(|(chain, rw)| (chain, rebuild_child(term, n, rw, factory)))
+                            .collect();
+                    }
                 }
             }
             vec![]
         }
 
+        RewriteProcess::AllChildren(process) | RewriteProcess::SomeChildren(process) => {
+            let keep_failing = matches!(this, RewriteProcess::SomeChildren(_));
+            run_on_children_traced(
+                process,
+                keep_failing,
+                term,
+                context_term,
+                term_position,
+                &strategy_position.get_position_of_nth_child(0),
+                factory,
+                control,
+            )
+        }
+
+        RewriteProcess::OneChild(process) => {
+            let inner_sp = strategy_position.get_position_of_nth_child(0);
+            let mut results = Vec::new();
+            for (n, child) in term.sub_terms.iter().enumerate() {
+                let child_tp = term_position.get_position_of_nth_child(n);
+                for (chain, rw) in run_traced_step(
+                    process,
+                    child,
+                    context_term,
+                    &child_tp,
+                    &inner_sp,
+                    factory,
+                    control,
+                ) {
+                    results.push((chain, rebuild_child(term, n, rw, factory)));
+                }
+            }
+            control.limit_results(results)
+        }
+
         RewriteProcess::Pipe(a, b) => {
             let sp_a = strategy_position.get_position_of_nth_child(0);
             let sp_b = strategy_position.get_position_of_nth_child(1);
-            run_traced_step(a, term, context_term, term_position, &sp_a, factory)
-                .into_iter()
-                .flat_map(|(chain_a, ti)| {
-                    let new_ctx =
-                        replace_at_position(context_term, term_position, ti.clone(), factory);
-                    run_traced_step(b, &ti, &new_ctx, term_position, &sp_b, factory)
-                        .into_iter()
-                        .map(move |(chain_b, result)| {
-                            let mut full_chain = chain_a.clone();
-                            full_chain.extend(chain_b);
-                            (full_chain, result)
-                        })
-                })
-                .collect()
+            let results: Vec<_> = run_traced_step(
+                a,
+                term,
+                context_term,
+                term_position,
+                &sp_a,
+                factory,
+                control,
+            )
+            .into_iter()
+            .flat_map(|(chain_a, ti)| {
+                let new_ctx = replace_at_position(context_term, term_position, ti.clone(), factory);
+                run_traced_step(b, &ti, &new_ctx, term_position, &sp_b, factory, control)
+                    .into_iter()
+                    .map(move |(chain_b, result)| {
+                        let mut full_chain = chain_a.clone();
+                        full_chain.extend(chain_b);
+                        (full_chain, result)
+                    })
+            })
+            .collect();
+            control.limit_results(results)
         }
 
         RewriteProcess::Repeat(process) => run_traced_step(
@@ -313,13 +759,36 @@ pub(crate) fn run_traced_step<LOS: RewritableLanguageOperatorSymbol>(
             term_position,
             &strategy_position.get_position_of_nth_child(0),
             factory,
+            control,
         ),
 
+        RewriteProcess::Traverse(traversal, process) => {
+            let results = traverse_traced(
+                this,
+                *traversal,
+                process,
+                term,
+                context_term,
+                term_position,
+                strategy_position,
+                factory,
+                control,
+            );
+            control.limit_results(results)
+        }
+
         RewriteProcess::TryOnePath(processes) => {
             for (i, process) in processes.iter().enumerate() {
                 let sp_i = strategy_position.get_position_of_nth_child(i);
-                let results =
-                    run_traced_step(process, term, context_term, term_position, &sp_i, factory);
+                let results = run_traced_step(
+                    process,
+                    term,
+                    context_term,
+                    term_position,
+                    &sp_i,
+                    factory,
+                    control,
+                );
                 if !results.is_empty() {
                     return results;
                 }
@@ -327,20 +796,367 @@ pub(crate) fn run_traced_step<LOS: RewritableLanguageOperatorSymbol>(
             vec![]
         }
 
-        RewriteProcess::TryAllPaths(processes) => processes
-            .iter()
-            .enumerate()
-            .flat_map(|(i, process)| {
-                let sp_i = strategy_position.get_position_of_nth_child(i);
-                run_traced_step(process, term, context_term, term_position, &sp_i, factory)
-            })
-            .collect(),
+        RewriteProcess::TryAllPaths(processes) => {
+            let results: Vec<_> = processes
+                .iter()
+                .enumerate()
+                .flat_map(|(i, process)| {
+                    let sp_i = strategy_position.get_position_of_nth_child(i);
+                    run_traced_step(
+                        process,
+                        term,
+                        context_term,
+                        term_position,
+                        &sp_i,
+                        factory,
+                        control,
+                    )
+                })
+                .collect();
+            control.limit_results(results)
+        }
+
+        RewriteProcess::Call(name) => {
+            let environment = control.get_environment();
+            match environment
+                .as_ref()
+                .and_then(|env| env.get_definition(name))
+            {
+                Some(definition) => run_traced_step(
+                    definition,
+                    term,
+                    context_term,
+                    term_position,
+                    &strategy_position.get_position_of_nth_child(0),
+                    factory,
+                    control,
+                ),
+                None => {
+                    control.report_unbound_name(name);
+                    vec![]
+                }
+            }
+        }
+
+        RewriteProcess::Let(environment, process) => {
+            let enclosing = control.replace_environment(Some(environment.clone()));
+            let results = run_traced_step(
+                process,
+                term,
+                context_term,
+                term_position,
+                &strategy_position.get_position_of_nth_child(0),
+                factory,
+                control,
+            );
+            control.replace_environment(enclosing);
+            results
+        }
     }
 }
 
+/// Evaluates `AllChildren(process)`, or `SomeChildren(process)` if
+/// `keep_failing`, for [`run_to_completion`].
+fn run_on_children<LOS: RewritableLanguageOperatorSymbol>(
+    process: &RewriteProcess<LOS>,
+    keep_failing: bool,
+    term: &LanguageTerm<LOS>,
+    context_term: &LanguageTerm<LOS>,
+    position: &PositionInLanguageTerm,
+    factory: &mut TermFactory<LOS>,
+    control: &mut EvaluationControl<LOS>,
+) -> Vec<LanguageTerm<LOS>> {
+    let mut choices = Vec::with_capacity(term.sub_terms.len());
+    let mut any_success = false;
+    for (n, child) in term.sub_terms.iter().enumerate() {
+        let child_pos = position.get_position_of_nth_child(n);
+        let results = run_to_completion(process, child, context_term, &child_pos, factory, control);
+        if !results.is_empty() {
+            any_success = true;
+            choices.push(results);
+        } else if keep_failing {
+            choices.push(vec![child.clone()]);
+        } else {
+            return vec![];
+        }
+    }
+    if keep_failing && !any_success {
+        return vec![];
+    }
+    let results: Vec<LanguageTerm<LOS>> = cartesian_product(choices)
+        .into_iter()
+        .map(|sub_terms| LanguageTermNode::build(term.operator.clone(), sub_terms, factory))
+        .collect();
+    control.limit_results(results)
+}
+
+/// Evaluates `AllChildren(process)`, or `SomeChildren(process)` if
+/// `keep_failing`, for [`run_traced_step`]; `strategy_position` is the
+/// position of `process`.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn run_on_children_traced<LOS: RewritableLanguageOperatorSymbol>(
+    process: &RewriteProcess<LOS>,
+    keep_failing: bool,
+    term: &LanguageTerm<LOS>,
+    context_term: &LanguageTerm<LOS>,
+    term_position: &PositionInLanguageTerm,
+    strategy_position: &PositionInRewriteProcess,
+    factory: &mut TermFactory<LOS>,
+    control: &mut EvaluationControl<LOS>,
+) -> Vec<(
+    Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+    LanguageTerm<LOS>,
+)> {
+    let mut choices = Vec::with_capacity(term.sub_terms.len());
+    let mut any_success = false;
+    for (n, child) in term.sub_terms.iter().enumerate() {
+        let child_tp = term_position.get_position_of_nth_child(n);
+        let results = run_traced_step(
+            process,
+            child,
+            context_term,
+            &child_tp,
+            strategy_position,
+            factory,
+            control,
+        );
+        if !results.is_empty() {
+            any_success = true;
+            choices.push(results);
+        } else if keep_failing {
+            choices.push(vec![(vec![], child.clone())]);
+        } else {
+            return vec![];
+        }
+    }
+    if keep_failing && !any_success {
+        return vec![];
+    }
+    // The chains of the children are concatenated, left to right.
+    let results: Vec<_> = cartesian_product(choices)
+        .into_iter()
+        .map(|combination| {
+            let (chains, sub_terms): (Vec<_>, Vec<_>) = combination.into_iter().unzip();
+            (
+                chains.concat(),
+                LanguageTermNode::build(term.operator.clone(), sub_terms, factory),
+            )
+        })
+        .collect();
+    control.limit_results(results)
+}
+
+/// Evaluates `this = Traverse(traversal, process)` for [`run_to_completion`].
+///
+/// Each traversal is two stages, `process` at the node and
+/// `SomeChildren(this)` below it: the second stage is applied to every result
+/// of the first one (except for the parallel step), keeping that result if it
+/// fails, and alone if the first stage fails.
+#[allow(clippy::too_many_arguments)]
+fn traverse<LOS: RewritableLanguageOperatorSymbol>(
+    this: &RewriteProcess<LOS>,
+    traversal: Traversal,
+    process: &RewriteProcess<LOS>,
+    term: &LanguageTerm<LOS>,
+    context_term: &LanguageTerm<LOS>,
+    position: &PositionInLanguageTerm,
+    factory: &mut TermFactory<LOS>,
+    control: &mut EvaluationControl<LOS>,
+) -> Vec<LanguageTerm<LOS>> {
+    let stage = |below: bool,
+                 term: &LanguageTerm<LOS>,
+                 context_term: &LanguageTerm<LOS>,
+                 factory: &mut TermFactory<LOS>,
+                 control: &mut EvaluationControl<LOS>| {
+        if below {
+            run_on_children(this, true, term, context_term, position, factory, control)
+        } else {
+            run_to_completion(process, term, context_term, position, factory, control)
+        }
+    };
+    let below_first = traversal == Traversal::BottomUp;
+    let first = stage(below_first, term, context_term, factory, control);
+    if first.is_empty() {
+        return stage(!below_first, term, context_term, factory, control);
+    }
+    if traversal == Traversal::ParallelOutermostStep {
+        return first;
+    }
+    let mut results = Vec::new();
+    for rewritten in first {
+        let new_ctx = replace_at_position(context_term, position, rewritten.clone(), factory);
+        let second = stage(!below_first, &rewritten, &new_ctx, factory, control);
+        if second.is_empty() {
+            results.push(rewritten);
+        } else {
+            results.extend(second);
+        }
+    }
+    results
+}
+
+/// Evaluates `this = Traverse(traversal, process)` for [`run_traced_step`],
+/// as [`traverse`]; `strategy_position` is the position of `this`.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn traverse_traced<LOS: RewritableLanguageOperatorSymbol>(
+    this: &RewriteProcess<LOS>,
+    traversal: Traversal,
+    process: &RewriteProcess<LOS>,
+    term: &LanguageTerm<LOS>,
+    context_term: &LanguageTerm<LOS>,
+    term_position: &PositionInLanguageTerm,
+    strategy_position: &PositionInRewriteProcess,
+    factory: &mut TermFactory<LOS>,
+    control: &mut EvaluationControl<LOS>,
+) -> Vec<(
+    Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+    LanguageTerm<LOS>,
+)> {
+    let inner_sp = strategy_position.get_position_of_nth_child(0);
+    let stage = |below: bool,
+                 term: &LanguageTerm<LOS>,
+                 context_term: &LanguageTerm<LOS>,
+                 factory: &mut TermFactory<LOS>,
+                 control: &mut EvaluationControl<LOS>| {
+        if below {
+            run_on_children_traced(
+                this,
+                true,
+                term,
+                context_term,
+                term_position,
+                strategy_position,
+                factory,
+                control,
+            )
+        } else {
+            run_traced_step(
+                process,
+                term,
+                context_term,
+                term_position,
+                &inner_sp,
+                factory,
+                control,
+            )
+        }
+    };
+    let below_first = traversal == Traversal::BottomUp;
+    let first = stage(below_first, term, context_term, factory, control);
+    if first.is_empty() {
+        return stage(!below_first, term, context_term, factory, control);
+    }
+    if traversal == Traversal::ParallelOutermostStep {
+        return first;
+    }
+    let mut results = Vec::new();
+    for (first_chain, rewritten) in first {
+        let new_ctx = replace_at_position(context_term, term_position, rewritten.clone(), factory);
+        let second = stage(!below_first, &rewritten, &new_ctx, factory, control);
+        if second.is_empty() {
+            results.push((first_chain, rewritten));
+        } else {
+            for (second_chain, result) in second {
+                let mut chain = first_chain.clone();
+                chain.extend(second_chain);
+                results.push((chain, result));
+            }
+        }
+    }
+    results
+}
+
 // == helpers ===================================================================
 
-fn rebuild_child<LOS: RewritableLanguageOperatorSymbol>(
+/// Builds the witness of a cycle found by `Repeat(process)` at `position`:
+/// `branch` holds the `(term, context)` pairs from the first occurrence of the
+/// repeated term, and `closing_context` is the context in which it reappeared.
+///
+/// The untraced evaluator does not record which rules fired, so every step is
+/// replayed with [`run_traced_step`] under `control`, whose budget and
+/// environment are those of the `Repeat`.  A step that cannot be replayed this
+/// way (when `process` contains a nested `Repeat`, or the budget runs out)
+/// gets no rule chain.
+pub(crate) fn replay_cycle<LOS: RewritableLanguageOperatorSymbol>(
+    process: &RewriteProcess<LOS>,
+    branch: &[(LanguageTerm<LOS>, LanguageTerm<LOS>)],
+    closing_context: &LanguageTerm<LOS>,
+    position: &PositionInLanguageTerm,
+    factory: &mut TermFactory<LOS>,
+    control: &mut EvaluationControl<LOS>,
+) -> RewriteCycle<LOS> {
+    let mut terms: Vec<LanguageTerm<LOS>> = branch.iter().map(|(_, ctx)| ctx.clone()).collect();
+    terms.push(closing_context.clone());
+    let root_sp = PositionInRewriteProcess::get_root_position();
+    let environment = control.get_environment();
+    let mut rule_chains = Vec::new();
+    for (k, (from, from_ctx)) in branch.iter().enumerate() {
+        let to = branch.get(k + 1).map_or(&branch[0].0, |(t, _)| t);
+        let replayed = run_traced_step(
+            process, from, from_ctx, position, &root_sp, factory, control,
+        );
+        let chain = replayed
+            .into_iter()
+            .find(|(_, result)| result == to)
+            .map(|(chain, _)| {
+                chain
+                    .into_iter()
+                    .map(|(sp, tp)| CycleRuleApplication {
+                        rule_desc: process
+                            .get_rule_at_position_in(&sp, environment.as_deref())
+                            .expect("rule_chain position resolves to a Rule leaf")
+                            .get_desc(),
+                        position: tp,
+                    })
+                    .collect()
+            });
+        rule_chains.push(chain);
+    }
+    RewriteCycle { terms, rule_chains }
+}
+
+/// A rule shared between the copies of a process made by
+/// [`RewriteProcess::share`].
+struct SharedRule<LOS: RewritableLanguageOperatorSymbol>(Rc<dyn RewriteRule<LOS>>);
+
+impl<LOS: RewritableLanguageOperatorSymbol> RewriteRule<LOS> for SharedRule<LOS> {
+    fn get_desc(&self) -> String {
+        self.0.get_desc()
+    }
+
+    fn try_apply(
+        &self,
+        term: &LanguageTerm<LOS>,
+        context_term: &LanguageTerm<LOS>,
+        position_in_context_term: &PositionInLanguageTerm,
+        factory: &mut TermFactory<LOS>,
+    ) -> Option<LanguageTerm<LOS>> {
+        self.0
+            .try_apply(term, context_term, position_in_context_term, factory)
+    }
+}
+
+/// Every way of picking one element in each of `choices`, in lexicographic
+/// order.  Empty if any of `choices` is empty; a single empty combination if
+/// there is no choice to make.
+pub(crate) fn cartesian_product<T: Clone>(choices: Vec<Vec<T>>) -> Vec<Vec<T>> {
+    let mut combinations = vec![vec![]];
+    for options in choices {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|prefix| {
+                options.iter().map(move |option| {
+                    let mut combination = prefix.clone();
+                    combination.push(option.clone());
+                    combination
+                })
+            })
+            .collect();
+    }
+    combinations
+}
+
+pub(crate) fn rebuild_child<LOS: RewritableLanguageOperatorSymbol>(
     parent: &LanguageTerm<LOS>,
     child_index: usize,
     new_child: LanguageTerm<LOS>,
@@ -353,7 +1169,7 @@ fn rebuild_child<LOS: RewritableLanguageOperatorSymbol>(
 
 /// Rebuild `context` with `replacement` substituted at `position`.
 /// Used by `Pipe` and `Repeat` to keep the context current after a rule fires.
-fn replace_at_position<LOS: RewritableLanguageOperatorSymbol>(
+pub(crate) fn replace_at_position<LOS: RewritableLanguageOperatorSymbol>(
     context: &LanguageTerm<LOS>,
     position: &PositionInLanguageTerm,
     replacement: LanguageTerm<LOS>,
@@ -0,0 +1,605 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use crate::position::PositionInLanguageTerm;
+use crate::process::budget::BudgetExhaustion;
+use crate::process::control::{EvaluationControl, RewriteExecutionError};
+use crate::process::environment::StrategyEnvironment;
+use crate::process::strategy::{
+    rebuild_child, replace_at_position, replay_cycle, run_to_completion, DepthOrder,
+    RewriteProcess, SiblingOrder,
+};
+use crate::term::syntax::{
+    LanguageTerm, LanguageTermNode, RewritableLanguageOperatorSymbol, TermFactory,
+};
+
+/// The results of a [`RewriteProcess`], computed on demand.
+///
+/// Created by
+/// [`RewriteProcessUntracedExecutor::stream`](super::untraced::RewriteProcessUntracedExecutor::stream).
+/// Yields the same terms, in the same order, as
+/// [`execute`](super::untraced::RewriteProcessUntracedExecutor::execute), but
+/// each one is computed only when it is pulled: `Pipe` and `TryAllPaths` move
+/// to their next alternative only once the current one is exhausted, and
+/// `Repeat` explores its branches depth-first.  Stopping early (`take(1)`,
+/// `find`, ...) therefore skips the rest of the exploration.
+///
+/// The iteration ends early if an enabled check fails or the budget runs out;
+/// see [`get_error`](Self::get_error) and
+/// [`get_exhaustion`](Self::get_exhaustion).  As no result set is ever held in
+/// full, the maximum frontier size of the budget does not apply.
+pub struct RewriteResultStream<'a, LOS: RewritableLanguageOperatorSymbol> {
+    evaluation: LazyEvaluation<'a, LOS>,
+    factory: &'a mut TermFactory<LOS>,
+    control: EvaluationControl<LOS>,
+    error: Option<RewriteExecutionError<LOS>>,
+}
+
+impl<'a, LOS: RewritableLanguageOperatorSymbol> RewriteResultStream<'a, LOS> {
+    pub(crate) fn new(
+        strategy: &'a RewriteProcess<LOS>,
+        term: &LanguageTerm<LOS>,
+        factory: &'a mut TermFactory<LOS>,
+        control: EvaluationControl<LOS>,
+    ) -> Self {
+        Self {
+            evaluation: LazyEvaluation::new(
+                strategy,
+                term.clone(),
+                term.clone(),
+                PositionInLanguageTerm::get_root_position(),
+                None,
+            ),
+            factory,
+            control,
+            error: None,
+        }
+    }
+
+    /// The error that ended the iteration, if any.
+    pub fn get_error(&self) -> Option<&RewriteExecutionError<LOS>> {
+        self.error.as_ref()
+    }
+
+    /// The budget limit that ended the iteration, if any.  The terms yielded
+    /// after it ran out are partial results, as for
+    /// [`run_within_budget`](super::untraced::RewriteProcessUntracedExecutor::run_within_budget).
+    pub fn get_exhaustion(&self) -> Option<BudgetExhaustion> {
+        self.control.get_exhaustion()
+    }
+
+    /// Number of rules that fired so far.
+    pub fn get_rule_applications(&self) -> usize {
+        self.control.get_rule_applications()
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> Iterator for RewriteResultStream<'_, LOS> {
+    type Item = LanguageTerm<LOS>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        let result = self.evaluation.next(self.factory, &mut self.control);
+        if let Some(error) = self.control.take_error() {
+            self.error = Some(error);
+            return None;
+        }
+        result
+    }
+}
+
+// == lazy evaluator ============================================================
+
+/// The suspended evaluation of `process` on `term`, mirroring
+/// [`run_to_completion`](super::strategy::run_to_completion) one result at a
+/// time.
+struct LazyEvaluation<'a, LOS: RewritableLanguageOperatorSymbol> {
+    process: &'a RewriteProcess<LOS>,
+    term: LanguageTerm<LOS>,
+    context_term: LanguageTerm<LOS>,
+    position: PositionInLanguageTerm,
+    /// The environment of the innermost enclosing `Let`.
+    environment: Option<&'a StrategyEnvironment<LOS>>,
+    state: LazyState<'a, LOS>,
+}
+
+enum LazyState<'a, LOS: RewritableLanguageOperatorSymbol> {
+    Rule {
+        tried: bool,
+    },
+    AnyChild {
+        /// The `(child index, use the inner process)` pairs still to try,
+        /// last one first.
+        candidates: Vec<(usize, bool)>,
+        current: Option<(usize, Box<LazyEvaluation<'a, LOS>>)>,
+        committed: bool,
+    },
+    OneChild {
+        next_child: usize,
+        current: Option<(usize, Box<LazyEvaluation<'a, LOS>>)>,
+    },
+    /// `AllChildren` and `SomeChildren`: the results of each child are
+    /// computed eagerly on the first call, their combinations lazily.
+    Combinations {
+        choices: Option<Vec<Vec<LanguageTerm<LOS>>>>,
+        /// The index, in each of `choices`, of the next combination.
+        next_combination: Vec<usize>,
+        done: bool,
+    },
+    /// `Traverse`: the results are computed eagerly on the first call.
+    Eager {
+        results: Option<std::vec::IntoIter<LanguageTerm<LOS>>>,
+    },
+    Pipe {
+        first: Box<LazyEvaluation<'a, LOS>>,
+        second: Option<Box<LazyEvaluation<'a, LOS>>>,
+    },
+    Repeat {
+        /// The current branch: every frame but the last has fired at least
+        /// once and the next frame is one of its results.
+        frames: Vec<RepeatFrame<'a, LOS>>,
+        /// Index of each term of the branch, only tracked when detecting
+        /// cycles.
+        on_branch: HashMap<LanguageTerm<LOS>, usize>,
+    },
+    TryOnePath {
+        next_alternative: usize,
+        current: Option<Box<LazyEvaluation<'a, LOS>>>,
+        committed: bool,
+    },
+    TryAllPaths {
+        next_alternative: usize,
+        current: Option<Box<LazyEvaluation<'a, LOS>>>,
+    },
+    /// The definition is only looked up on the first call.
+    Call {
+        definition: Option<Box<LazyEvaluation<'a, LOS>>>,
+    },
+    Let {
+        inner: Box<LazyEvaluation<'a, LOS>>,
+    },
+}
+
+struct RepeatFrame<'a, LOS: RewritableLanguageOperatorSymbol> {
+    inner: LazyEvaluation<'a, LOS>,
+    fired: bool,
+}
+
+impl<'a, LOS: RewritableLanguageOperatorSymbol> LazyEvaluation<'a, LOS> {
+    /// Suspends the evaluation; nothing is computed until [`next`](Self::next).
+    fn new(
+        process: &'a RewriteProcess<LOS>,
+        term: LanguageTerm<LOS>,
+        context_term: LanguageTerm<LOS>,
+        position: PositionInLanguageTerm,
+        environment: Option<&'a StrategyEnvironment<LOS>>,
+    ) -> Self {
+        let state = match process {
+            RewriteProcess::Rule(_) => LazyState::Rule { tried: false },
+            RewriteProcess::AnyChild(sibling_order, depth_order, _) => {
+                let indices: Vec<usize> = match sibling_order {
+                    SiblingOrder::Leftmost => (0..term.sub_terms.len()).collect(),
+                    SiblingOrder::Rightmost => (0..term.sub_terms.len()).rev().collect(),
+                };
+                let inner_first = *depth_order == DepthOrder::Outermost;
+                let mut candidates: Vec<(usize, bool)> = indices
+                    .into_iter()
+                    .flat_map(|n| [(n, inner_first), (n, !inner_first)])
+                    .collect();
+                candidates.reverse();
+                LazyState::AnyChild {
+                    candidates,
+                    current: None,
+                    committed: false,
+                }
+            }
+            RewriteProcess::OneChild(_) => LazyState::OneChild {
+                next_child: 0,
+                current: None,
+            },
+            RewriteProcess::AllChildren(_) | RewriteProcess::SomeChildren(_) => {
+                LazyState::Combinations {
+                    choices: None,
+                    next_combination: vec![],
+                    done: false,
+                }
+            }
+            RewriteProcess::Traverse(_, _) => LazyState::Eager { results: None },
+            RewriteProcess::Pipe(a, _) => LazyState::Pipe {
+                first: Box::new(LazyEvaluation::new(
+                    a,
+                    term.clone(),
+                    context_term.clone(),
+                    position.clone(),
+                    environment,
+                )),
+                second: None,
+            },
+            RewriteProcess::Repeat(inner) => LazyState::Repeat {
+                frames: vec![RepeatFrame {
+                    inner: LazyEvaluation::new(
+                        inner,
+                        term.clone(),
+                        context_term.clone(),
+                        position.clone(),
+                        environment,
+                    ),
+                    fired: false,
+                }],
+                on_branch: HashMap::new(),
+            },
+            RewriteProcess::TryOnePath(_) => LazyState::TryOnePath {
+                next_alternative: 0,
+                current: None,
+                committed: false,
+            },
+            RewriteProcess::TryAllPaths(_) => LazyState::TryAllPaths {
+                next_alternative: 0,
+                current: None,
+            },
+            RewriteProcess::Call(_) => LazyState::Call { definition: None },
+            RewriteProcess::Let(inner_environment, inner) => LazyState::Let {
+                inner: Box::new(LazyEvaluation::new(
+                    inner,
+                    term.clone(),
+                    context_term.clone(),
+                    position.clone(),
+                    Some(inner_environment.as_ref()),
+                )),
+            },
+        };
+        Self {
+            process,
+            term,
+            context_term,
+            position,
+            environment,
+            state,
+        }
+    }
+
+    /// Computes the next result, or `None` once every result was produced.
+    fn next(
+        &mut self,
+        factory: &mut TermFactory<LOS>,
+        control: &mut EvaluationControl<LOS>,
+    ) -> Option<LanguageTerm<LOS>> {
+        match (self.process, &mut self.state) {
+            (RewriteProcess::Rule(rule), LazyState::Rule { tried }) => {
+                if *tried {
+                    return None;
+                }
+                *tried = true;
+                control.apply_rule(
+                    rule.as_ref(),
+                    &self.term,
+                    &self.context_term,
+                    &self.position,
+                    factory,
+                )
+            }
+
+            (
+                RewriteProcess::AnyChild(_, _, process),
+                LazyState::AnyChild {
+                    candidates,
+                    current,
+                    committed,
+                },
+            ) => loop {
+                if let Some((n, evaluation)) = current {
+                    if let Some(rw) = evaluation.next(factory, control) {
+                        *committed = true;
+                        return Some(rebuild_child(&self.term, *n, rw, factory));
+                    }
+                    // Only the first child where the process succeeds counts.
+                    if *committed {
+                        return None;
+                    }
+                }
+                let (n, use_inner) = candidates.pop()?;
+                let p = if use_inner {
+                    process.as_ref()
+                } else {
+                    self.process
+                };
+                *current = Some((
+                    n,
+                    Box::new(LazyEvaluation::new(
+                        p,
+                        self.term.sub_terms[n].clone(),
+                        self.context_term.clone(),
+                        self.position.get_position_of_nth_child(n),
+                        self.environment,
+                    )),
+                ));
+            },
+
+            (
+                RewriteProcess::OneChild(process),
+                LazyState::OneChild {
+                    next_child,
+                    current,
+                },
+            ) => loop {
+                if let Some((n, evaluation)) = current {
+                    if let Some(rw) = evaluation.next(factory, control) {
+                        return Some(rebuild_child(&self.term, *n, rw, factory));
+                    }
+                }
+                let n = *next_child;
+                let child = self.term.sub_terms.get(n)?;
+                *next_child += 1;
+                *current = Some((
+                    n,
+                    Box::new(LazyEvaluation::new(
+                        process,
+                        child.clone(),
+                        self.context_term.clone(),
+                        self.position.get_position_of_nth_child(n),
+                        self.environment,
+                    )),
+                ));
+            },
+
+            (
+                RewriteProcess::AllChildren(process) | RewriteProcess::SomeChildren(process),
+                LazyState::Combinations {
+                    choices,
+                    next_combination,
+                    done,
+                },
+            ) => {
+                if *done {
+                    return None;
+                }
+                if choices.is_none() {
+                    let keep_failing = matches!(self.process, RewriteProcess::SomeChildren(_));
+                    let mut computed = Vec::with_capacity(self.term.sub_terms.len());
+                    let mut any_success = false;
+                    for (n, child) in self.term.sub_terms.iter().enumerate() {
+                        let results = run_to_completion(
+                            process,
+                            child,
+                            &self.context_term,
+                            &self.position.get_position_of_nth_child(n),
+                            factory,
+                            control,
+                        );
+                        if !results.is_empty() {
+                            any_success = true;
+                            computed.push(results);
+                        } else if keep_failing {
+                            computed.push(vec![child.clone()]);
+                        } else {
+                            *done = true;
+                            return None;
+                        }
+                    }
+                    if keep_failing && !any_success {
+                        *done = true;
+                        return None;
+                    }
+                    *next_combination = vec![0; computed.len()];
+                    *choices = Some(computed);
+                }
+                let choices = choices.as_ref()?;
+                let sub_terms: Vec<LanguageTerm<LOS>> = choices
+                    .iter()
+                    .zip(next_combination.iter())
+                    .map(|(options, &k)| options[k].clone())
+                    .collect();
+                // Advances the odometer, the last child varying fastest.
+                *done = true;
+                for (k, options) in next_combination.iter_mut().zip(choices).rev() {
+                    *k += 1;
+                    if *k < options.len() {
+                        *done = false;
+                        break;
+                    }
+                    *k = 0;
+                }
+                Some(LanguageTermNode::build(
+                    self.term.operator.clone(),
+                    sub_terms,
+                    factory,
+                ))
+            }
+
+            (RewriteProcess::Traverse(_, _), LazyState::Eager { results }) => results
+                .get_or_insert_with(|| {
+                    run_to_completion(
+                        self.process,
+                        &self.term,
+                        &self.context_term,
+                        &self.position,
+                        factory,
+                        control,
+                    )
+                    .into_iter()
+                })
+                .next(),
+
+            (RewriteProcess::Pipe(_, b), LazyState::Pipe { first, second }) => loop {
+                if let Some(evaluation) = second {
+                    if let Some(result) = evaluation.next(factory, control) {
+                        return Some(result);
+                    }
+                }
+                let ti = first.next(factory, control)?;
+                let new_ctx =
+                    replace_at_position(&self.context_term, &self.position, ti.clone(), factory);
+                *second = Some(Box::new(LazyEvaluation::new(
+                    b,
+                    ti,
+                    new_ctx,
+                    self.position.clone(),
+                    self.environment,
+                )));
+            },
+
+            (RewriteProcess::Repeat(process), LazyState::Repeat { frames, on_branch }) => loop {
+                // When halted, the term reached on the current branch is the
+                // partial result.
+                if control.poll_halt() {
+                    let reached = frames.pop()?.inner.term;
+                    frames.clear();
+                    return Some(reached);
+                }
+                let top = frames.last_mut()?;
+                match top.inner.next(factory, control) {
+                    Some(ti) => {
+                        top.fired = true;
+                        let new_ctx = replace_at_position(
+                            &top.inner.context_term,
+                            &self.position,
+                            ti.clone(),
+                            factory,
+                        );
+                        if control.detects_cycles() {
+                            if on_branch.is_empty() {
+                                on_branch.insert(frames[0].inner.term.clone(), 0);
+                            }
+                            if let Some(&start) = on_branch.get(&ti) {
+                                let branch: Vec<_> = frames[start..]
+                                    .iter()
+                                    .map(|f| (f.inner.term.clone(), f.inner.context_term.clone()))
+                                    .collect();
+                                let cycle = replay_cycle(
+                                    process,
+                                    &branch,
+                                    &new_ctx,
+                                    &self.position,
+                                    factory,
+                                    control,
+                                );
+                                control.report_cycle(cycle);
+                                continue;
+                            }
+                            on_branch.insert(ti.clone(), frames.len());
+                        }
+                        frames.push(RepeatFrame {
+                            inner: LazyEvaluation::new(
+                                process,
+                                ti,
+                                new_ctx,
+                                self.position.clone(),
+                                self.environment,
+                            ),
+                            fired: false,
+                        });
+                    }
+                    None => {
+                        let frame = frames.pop()?;
+                        on_branch.remove(&frame.inner.term);
+                        if control.poll_halt() {
+                            frames.clear();
+                            return Some(frame.inner.term);
+                        }
+                        if !frame.fired {
+                            return Some(frame.inner.term);
+                        }
+                    }
+                }
+            },
+
+            (
+                RewriteProcess::TryOnePath(processes),
+                LazyState::TryOnePath {
+                    next_alternative,
+                    current,
+                    committed,
+                },
+            ) => loop {
+                if let Some(evaluation) = current {
+                    if let Some(result) = evaluation.next(factory, control) {
+                        *committed = true;
+                        return Some(result);
+                    }
+                    // Only the first alternative that succeeds counts.
+                    if *committed {
+                        return None;
+                    }
+                }
+                let process = processes.get(*next_alternative)?;
+                *next_alternative += 1;
+                *current = Some(Box::new(LazyEvaluation::new(
+                    process,
+                    self.term.clone(),
+                    self.context_term.clone(),
+                    self.position.clone(),
+                    self.environment,
+                )));
+            },
+
+            (
+                RewriteProcess::TryAllPaths(processes),
+                LazyState::TryAllPaths {
+                    next_alternative,
+                    current,
+                },
+            ) => loop {
+                if let Some(evaluation) = current {
+                    if let Some(result) = evaluation.next(factory, control) {
+                        return Some(result);
+                    }
+                }
+                let process = processes.get(*next_alternative)?;
+                *next_alternative += 1;
+                *current = Some(Box::new(LazyEvaluation::new(
+                    process,
+                    self.term.clone(),
+                    self.context_term.clone(),
+                    self.position.clone(),
+                    self.environment,
+                )));
+            },
+
+            (RewriteProcess::Call(name), LazyState::Call { definition }) => {
+                if definition.is_none() {
+                    let Some(process) = self.environment.and_then(|env| env.get_definition(name))
+                    else {
+                        control.report_unbound_name(name);
+                        return None;
+                    };
+                    *definition = Some(Box::new(LazyEvaluation::new(
+                        process,
+                        self.term.clone(),
+                        self.context_term.clone(),
+                        self.position.clone(),
+                        self.environment,
+                    )));
+                }
+                definition.as_mut()?.next(factory, control)
+            }
+
+            (RewriteProcess::Let(environment, _), LazyState::Let { inner }) => {
+                // The eager parts of the inner evaluation resolve their calls
+                // through the control.
+                let enclosing = control.replace_environment(Some(environment.clone()));
+                let result = inner.next(factory, control);
+                control.replace_environment(enclosing);
+                result
+            }
+
+            _ => unreachable!("the state always matches the process"),
+        }
+    }
+}
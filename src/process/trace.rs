@@ -0,0 +1,193 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::fmt::{self, Write};
+
+use crate::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use crate::process::control::EvaluationControl;
+use crate::process::strategy::{replace_at_position, run_traced_step, RewriteProcess};
+use crate::process::traced::AtomicRuleApplication;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
+
+/// One rule firing of a traced transition, with the sub-term it rewrote.
+///
+/// Displayed as `rule DESC at position P: REDEX → REPLACEMENT`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TraceStep<LOS: RewritableLanguageOperatorSymbol> {
+    /// The [`get_desc`](crate::rule::RewriteRule::get_desc) of the rule.
+    pub rule_desc: String,
+    /// Which `Rule(...)` leaf of the strategy fired.
+    pub strategy_position: PositionInRewriteProcess,
+    /// Where in the term it fired.
+    pub term_position: PositionInLanguageTerm,
+    /// The sub-term at `term_position` before the rule fired.
+    pub redex: LanguageTerm<LOS>,
+    /// What the rule replaced it with.
+    pub replacement: LanguageTerm<LOS>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol + fmt::Display> fmt::Display for TraceStep<LOS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rule {} at position {}: {} → {}",
+            self.rule_desc, self.term_position, self.redex, self.replacement
+        )
+    }
+}
+
+/// Recovers the sub-terms rewritten along `rule_chain`, as found in an
+/// [`AtomicRuleApplication`] of `strategy` on `source`.
+///
+/// The chain only records positions, so the step is evaluated again from
+/// `source`, recording what each rule rewrote.  Each rule is thus given the
+/// context it had in the traced step: under a congruence, the term before any
+/// of the children was rewritten.  A chain that `strategy` does not produce
+/// from `source`, such as a step of the
+/// [`InteractiveRewriteExecutor`](super::interactive::InteractiveRewriteExecutor),
+/// is replayed by applying every rule again, in order, to the term obtained
+/// so far, which is also the context given to the rule.
+///
+/// Returns the steps and the final term, or `None` if a position does not
+/// resolve to a rule or a rule does not fire again.
+#[allow(clippy::type_complexity)]
+pub fn replay_rule_chain<LOS: RewritableLanguageOperatorSymbol>(
+    strategy: &RewriteProcess<LOS>,
+    source: &LanguageTerm<LOS>,
+    rule_chain: &[(PositionInRewriteProcess, PositionInLanguageTerm)],
+    factory: &mut TermFactory<LOS>,
+) -> Option<(Vec<TraceStep<LOS>>, LanguageTerm<LOS>)> {
+    let mut control = EvaluationControl::new(false).with_firing_record();
+    let results = run_traced_step(
+        strategy,
+        source,
+        source,
+        &PositionInLanguageTerm::get_root_position(),
+        &PositionInRewriteProcess::get_root_position(),
+        factory,
+        &mut control,
+    );
+    if !results.iter().any(|(chain, _)| chain == rule_chain) {
+        return replay_in_sequence(strategy, source, rule_chain, factory);
+    }
+    // The chain is a subsequence of the firings, which also hold those of
+    // the other results.
+    let mut firings = control.take_firings().into_iter();
+    let mut current = source.clone();
+    let mut steps = Vec::with_capacity(rule_chain.len());
+    for (strategy_position, term_position) in rule_chain {
+        let redex = get_sub_term(&current, term_position)?;
+        let firing = firings.find(|firing| {
+            firing.strategy_position == *strategy_position
+                && firing.term_position == *term_position
+                && firing.redex == redex
+        })?;
+        current = replace_at_position(&current, term_position, firing.output.clone(), factory);
+        steps.push(TraceStep {
+            rule_desc: strategy.get_rule_at_position(strategy_position)?.get_desc(),
+            strategy_position: strategy_position.clone(),
+            term_position: term_position.clone(),
+            redex,
+            replacement: firing.output,
+        });
+    }
+    Some((steps, current))
+}
+
+#[allow(clippy::type_complexity)]
+fn replay_in_sequence<LOS: RewritableLanguageOperatorSymbol>(
+    strategy: &RewriteProcess<LOS>,
+    source: &LanguageTerm<LOS>,
+    rule_chain: &[(PositionInRewriteProcess, PositionInLanguageTerm)],
+    factory: &mut TermFactory<LOS>,
+) -> Option<(Vec<TraceStep<LOS>>, LanguageTerm<LOS>)> {
+    let mut current = source.clone();
+    let mut steps = Vec::with_capacity(rule_chain.len());
+    for (strategy_position, term_position) in rule_chain {
+        let rule = strategy.get_rule_at_position(strategy_position)?;
+        let redex = get_sub_term(&current, term_position)?;
+        let replacement = rule.try_apply(&redex, &current, term_position, factory)?;
+        current = replace_at_position(&current, term_position, replacement.clone(), factory);
+        steps.push(TraceStep {
+            rule_desc: rule.get_desc(),
+            strategy_position: strategy_position.clone(),
+            term_position: term_position.clone(),
+            redex,
+            replacement,
+        });
+    }
+    Some((steps, current))
+}
+
+/// Renders the result of one
+/// [`progress`](super::traced::RewriteProcessTracedExecutor::progress) call:
+/// for each application, a `left_id → right_id: source ⇒ target` line
+/// followed by one indented line per [`TraceStep`].
+///
+/// `previous_frontier` is the frontier before the call, which `left_id`
+/// indexes.  An application that cannot be replayed (see
+/// [`replay_rule_chain`]) lists its rules and positions only.
+pub fn render_trace<LOS: RewritableLanguageOperatorSymbol + fmt::Display>(
+    strategy: &RewriteProcess<LOS>,
+    previous_frontier: &[LanguageTerm<LOS>],
+    applications: &[AtomicRuleApplication],
+    factory: &mut TermFactory<LOS>,
+) -> String {
+    let mut rendered = String::new();
+    for application in applications {
+        let source = &previous_frontier[application.left_id];
+        match replay_rule_chain(strategy, source, &application.rule_chain, factory) {
+            Some((steps, target)) => {
+                writeln!(
+                    rendered,
+                    "{} → {}: {} ⇒ {}",
+                    application.left_id, application.right_id, source, target
+                )
+                .unwrap();
+                for step in steps {
+                    writeln!(rendered, "  {}", step).unwrap();
+                }
+            }
+            None => {
+                writeln!(
+                    rendered,
+                    "{} → {}: {}",
+                    application.left_id, application.right_id, source
+                )
+                .unwrap();
+                for (strategy_position, term_position) in &application.rule_chain {
+                    let desc = strategy
+                        .get_rule_at_position(strategy_position)
+                        .expect("rule_chain position resolves to a Rule leaf")
+                        .get_desc();
+                    writeln!(rendered, "  rule {} at position {}", desc, term_position).unwrap();
+                }
+            }
+        }
+    }
+    rendered
+}
+
+fn get_sub_term<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+    position: &PositionInLanguageTerm,
+) -> Option<LanguageTerm<LOS>> {
+    let mut current = term;
+    for &n in position.get_absolute_coordinates_from_root() {
+        current = current.sub_terms.get(n)?;
+    }
+    Some(current.clone())
+}
@@ -14,8 +14,19 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
 use crate::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use crate::process::budget::{BudgetExhaustion, RewriteBudget, RewriteOutcome};
+use crate::process::control::{
+    CycleRuleApplication, EvaluationControl, RewriteCycle, RewriteExecutionError,
+};
+use crate::process::rewrite_graph::{RewriteGraph, RewriteGraphEdge, RewriteGraphStep};
 use crate::process::strategy::{run_traced_step, RewriteProcess};
+use crate::process::trace::render_trace;
+use crate::rules::util::ordering::TermOrdering;
 use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
 
 /// Records one atomic transition produced by a single [`RewriteProcessTracedExecutor::progress`] call.
@@ -31,6 +42,7 @@ use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFa
 /// - which `Rule(...)` leaf in the [`RewriteProcess`] tree fired
 ///   (`PositionInRewriteProcess`),
 /// - where in the term it fired (`PositionInLanguageTerm`).
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct AtomicRuleApplication {
     /// Index of the source term in the **pre-progress** frontier.
     pub left_id: usize,
@@ -41,6 +53,31 @@ pub struct AtomicRuleApplication {
     pub right_id: usize,
 }
 
+/// A transition whose result was not added to the new frontier because
+/// frontier deduplication is enabled and the term had already been reached in
+/// an earlier layer (see
+/// [`with_frontier_deduplication`](RewriteProcessTracedExecutor::with_frontier_deduplication)).
+///
+/// Reported by [`RewriteProcessTracedExecutor::get_revisiting_applications`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RevisitingRuleApplication<LOS: RewritableLanguageOperatorSymbol> {
+    /// Index of the source term in the **pre-progress** frontier.
+    pub left_id: usize,
+    /// Same as [`AtomicRuleApplication::rule_chain`].
+    pub rule_chain: Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+    /// The already visited result term.
+    pub term: LanguageTerm<LOS>,
+}
+
+/// A frontier term together with the branch that led to it.
+struct Ancestry<LOS: RewritableLanguageOperatorSymbol> {
+    term: LanguageTerm<LOS>,
+    /// The previous term of the branch, `None` for an initial term.
+    parent: Option<Rc<Ancestry<LOS>>>,
+    /// The rule chain from `parent` to `term`.
+    rule_chain: Vec<(PositionInRewriteProcess, PositionInLanguageTerm)>,
+}
+
 /// Stateful, step-by-step evaluator for a [`RewriteProcess`].
 ///
 /// The frontier of "live" terms is advanced one layer at a time via
@@ -59,6 +96,18 @@ pub struct RewriteProcessTracedExecutor<LOS: RewritableLanguageOperatorSymbol> {
     factory: TermFactory<LOS>,
     current_terms: Vec<LanguageTerm<LOS>>,
     completed_terms: Vec<LanguageTerm<LOS>>,
+    check_signatures: bool,
+    budget: RewriteBudget,
+    /// Rule applications spent by the successful steps so far.
+    rule_applications: usize,
+    rewrite_graph: Option<RewriteGraph<LOS>>,
+    /// `Some` iff frontier deduplication is enabled: every term that has been
+    /// in a frontier so far (this includes the completed terms).
+    visited_terms: Option<HashSet<LanguageTerm<LOS>>>,
+    revisiting_applications: Vec<RevisitingRuleApplication<LOS>>,
+    /// `Some` iff cycle detection is enabled: the ancestry of each current term.
+    ancestries: Option<Vec<Rc<Ancestry<LOS>>>>,
+    termination_monitor: Option<Rc<dyn TermOrdering<LOS>>>,
 }
 
 impl<LOS: RewritableLanguageOperatorSymbol> RewriteProcessTracedExecutor<LOS> {
@@ -67,15 +116,145 @@ impl<LOS: RewritableLanguageOperatorSymbol> RewriteProcessTracedExecutor<LOS> {
         strategy: RewriteProcess<LOS>,
         initial: LanguageTerm<LOS>,
         factory: TermFactory<LOS>,
+    ) -> Self {
+        Self::from_terms(strategy, vec![initial], factory)
+    }
+
+    /// Creates an executor whose first frontier is `initial_terms`, in order.
+    pub fn from_terms(
+        strategy: RewriteProcess<LOS>,
+        initial_terms: Vec<LanguageTerm<LOS>>,
+        factory: TermFactory<LOS>,
     ) -> Self {
         Self {
             strategy,
             factory,
-            current_terms: vec![initial],
+            current_terms: initial_terms,
             completed_terms: vec![],
+            check_signatures: false,
+            budget: RewriteBudget::new(),
+            rule_applications: 0,
+            rewrite_graph: None,
+            visited_terms: None,
+            revisiting_applications: vec![],
+            ancestries: None,
+            termination_monitor: None,
         }
     }
 
+    /// When enabled, every rule output is checked with
+    /// [`validate_term`](crate::term::syntax::validate_term); an ill-formed
+    /// output makes [`try_progress`](Self::try_progress) return
+    /// [`RewriteExecutionError::IllFormedRuleOutput`].
+    pub fn with_signature_checking(mut self, enabled: bool) -> Self {
+        self.check_signatures = enabled;
+        self
+    }
+
+    /// When enabled, each current term remembers the branch that led to it,
+    /// and a step reaching a term of its own branch again makes
+    /// [`try_progress`](Self::try_progress) return
+    /// [`RewriteExecutionError::CycleDetected`].
+    ///
+    /// Checking a result costs a walk along its branch.  With frontier
+    /// deduplication, a term reached from several terms keeps the branch of
+    /// the first one.
+    pub fn with_cycle_detection(mut self, enabled: bool) -> Self {
+        self.ancestries = if enabled {
+            Some(
+                self.current_terms
+                    .iter()
+                    .map(|term| {
+                        Rc::new(Ancestry {
+                            term: term.clone(),
+                            parent: None,
+                            rule_chain: vec![],
+                        })
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Checks every rule application against `ordering`: a rule whose output
+    /// is not smaller than the sub-term it rewrote makes
+    /// [`try_progress`](Self::try_progress) return
+    /// [`RewriteExecutionError::NonDecreasingRuleApplication`].
+    ///
+    /// With a monotone ordering, such as the
+    /// [`LexicographicPathOrdering`](crate::rules::util::lpo::LexicographicPathOrdering)
+    /// or a [`MeasureOrdering`](crate::rules::util::ordering::MeasureOrdering)
+    /// over an additive measure, this catches the rules that could make
+    /// rewriting loop before they do.
+    pub fn with_termination_monitor(mut self, ordering: impl TermOrdering<LOS> + 'static) -> Self {
+        self.termination_monitor = Some(Rc::new(ordering));
+        self
+    }
+
+    /// Limits the whole execution, over all steps, with `budget`.
+    ///
+    /// A step during which the budget runs out is rolled back and
+    /// [`try_progress`](Self::try_progress) returns
+    /// [`RewriteExecutionError::BudgetExhausted`]; the interrupted terms stay
+    /// in the frontier.  [`run_within_budget`](Self::run_within_budget) turns
+    /// this into a [`RewriteOutcome`].
+    pub fn with_budget(mut self, budget: RewriteBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// When enabled, every step taken from now on is accumulated into a
+    /// [`RewriteGraph`], available through
+    /// [`get_rewrite_graph`](Self::get_rewrite_graph).  The current frontier
+    /// becomes the first nodes of the graph.
+    pub fn with_rewrite_graph_recording(mut self, enabled: bool) -> Self {
+        self.rewrite_graph = if enabled {
+            Some(RewriteGraph::new(&self.current_terms))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// The derivation graph recorded so far, if recording is enabled.
+    pub fn get_rewrite_graph(&self) -> Option<&RewriteGraph<LOS>> {
+        self.rewrite_graph.as_ref()
+    }
+
+    /// When enabled, the frontier never contains the same term twice and never
+    /// contains a term that was already in an earlier frontier (completed terms
+    /// included).  Since terms are hash-consed this costs one hash lookup per
+    /// result.
+    ///
+    /// No provenance is lost: a result that is already in the new frontier
+    /// gets an [`AtomicRuleApplication`] whose `right_id` points to the
+    /// existing entry, and a result reached in an earlier layer is reported by
+    /// [`get_revisiting_applications`](Self::get_revisiting_applications).
+    pub fn with_frontier_deduplication(mut self, enabled: bool) -> Self {
+        self.visited_terms = if enabled {
+            Some(
+                self.current_terms
+                    .iter()
+                    .chain(self.completed_terms.iter())
+                    .cloned()
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        self
+    }
+
+    /// The transitions of the last [`progress`](Self::progress) call that
+    /// reached an already visited term.  Always empty unless frontier
+    /// deduplication is enabled.
+    pub fn get_revisiting_applications(&self) -> &[RevisitingRuleApplication<LOS>] {
+        &self.revisiting_applications
+    }
+
     /// Consumes the executor and returns its factory.
     ///
     /// Call this after the executor has run to completion to reclaim the
@@ -110,12 +289,37 @@ impl<LOS: RewritableLanguageOperatorSymbol> RewriteProcessTracedExecutor<LOS> {
     /// with `left_id` / `right_id` indexing into the old / new frontiers.
     /// An empty return value together with an empty
     /// [`get_current_terms`](Self::get_current_terms) means all terms are done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the opt-in checks fails or if the budget runs out; use
+    /// [`try_progress`](Self::try_progress) to handle such errors.
     pub fn progress(&mut self) -> Vec<AtomicRuleApplication> {
+        match self.try_progress() {
+            Ok(applications) => applications,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Same as [`progress`](Self::progress), but returns the error raised by
+    /// an opt-in check or the budget instead of panicking.
+    ///
+    /// On error the executor is left exactly as it was before the call.
+    pub fn try_progress(
+        &mut self,
+    ) -> Result<Vec<AtomicRuleApplication>, RewriteExecutionError<LOS>> {
         let root_sp = PositionInRewriteProcess::get_root_position();
         let root_tp = PositionInLanguageTerm::get_root_position();
+        let mut control = EvaluationControl::new(self.check_signatures)
+            .with_budget(self.budget.clone(), self.rule_applications)
+            .with_termination_monitor(self.termination_monitor.clone());
 
         let mut next_terms: Vec<LanguageTerm<LOS>> = Vec::new();
+        let mut next_term_ids: HashMap<LanguageTerm<LOS>, usize> = HashMap::new();
+        let mut newly_completed: Vec<LanguageTerm<LOS>> = Vec::new();
         let mut applications: Vec<AtomicRuleApplication> = Vec::new();
+        let mut revisiting: Vec<RevisitingRuleApplication<LOS>> = Vec::new();
+        let mut next_ancestries: Vec<Rc<Ancestry<LOS>>> = Vec::new();
 
         for (left_id, term) in self.current_terms.iter().enumerate() {
             let results = run_traced_step(
@@ -125,23 +329,221 @@ impl<LOS: RewritableLanguageOperatorSymbol> RewriteProcessTracedExecutor<LOS> {
                 &root_tp,
                 &root_sp,
                 &mut self.factory,
+                &mut control,
             );
+            if let Some(error) = control.take_error() {
+                return Err(error);
+            }
+            if let Some(exhaustion) = control.take_exhaustion() {
+                return Err(RewriteExecutionError::BudgetExhausted(exhaustion));
+            }
             if results.is_empty() {
-                self.completed_terms.push(term.clone());
-            } else {
-                for (rule_chain, result) in results {
-                    let right_id = next_terms.len();
-                    next_terms.push(result);
-                    applications.push(AtomicRuleApplication {
+                newly_completed.push(term.clone());
+                continue;
+            }
+            for (rule_chain, result) in results {
+                if let Some(ancestries) = &self.ancestries {
+                    if let Some(cycle) =
+                        find_cycle(&self.strategy, &ancestries[left_id], &rule_chain, &result)
+                    {
+                        return Err(RewriteExecutionError::CycleDetected(cycle));
+                    }
+                }
+                let already_visited = self
+                    .visited_terms
+                    .as_ref()
+                    .is_some_and(|visited| visited.contains(&result));
+                if already_visited {
+                    revisiting.push(RevisitingRuleApplication {
                         left_id,
                         rule_chain,
-                        right_id,
+                        term: result,
                     });
+                    continue;
                 }
+                // Only filled when deduplicating.
+                let right_id = match next_term_ids.get(&result) {
+                    Some(right_id) => *right_id,
+                    None => {
+                        let right_id = next_terms.len();
+                        if self.visited_terms.is_some() {
+                            next_term_ids.insert(result.clone(), right_id);
+                        }
+                        if let Some(ancestries) = &self.ancestries {
+                            next_ancestries.push(Rc::new(Ancestry {
+                                term: result.clone(),
+                                parent: Some(ancestries[left_id].clone()),
+                                rule_chain: rule_chain.clone(),
+                            }));
+                        }
+                        next_terms.push(result);
+                        right_id
+                    }
+                };
+                applications.push(AtomicRuleApplication {
+                    left_id,
+                    rule_chain,
+                    right_id,
+                });
+            }
+            if self
+                .budget
+                .max_frontier_size
+                .is_some_and(|max| next_terms.len() > max)
+            {
+                return Err(RewriteExecutionError::BudgetExhausted(
+                    BudgetExhaustion::FrontierSize,
+                ));
             }
         }
 
+        if let Some(graph) = &mut self.rewrite_graph {
+            for app in &applications {
+                record_edge(
+                    graph,
+                    &self.strategy,
+                    &self.current_terms[app.left_id],
+                    &next_terms[app.right_id],
+                    &app.rule_chain,
+                );
+            }
+            for app in &revisiting {
+                record_edge(
+                    graph,
+                    &self.strategy,
+                    &self.current_terms[app.left_id],
+                    &app.term,
+                    &app.rule_chain,
+                );
+            }
+            for term in &newly_completed {
+                graph.mark_completed(term);
+            }
+        }
+        if let Some(visited) = &mut self.visited_terms {
+            visited.extend(next_terms.iter().cloned());
+        }
+
+        if self.ancestries.is_some() {
+            self.ancestries = Some(next_ancestries);
+        }
+
+        self.completed_terms.extend(newly_completed);
         self.current_terms = next_terms;
-        applications
+        self.revisiting_applications = revisiting;
+        self.rule_applications = control.get_rule_applications();
+        Ok(applications)
     }
+
+    /// Calls [`try_progress`](Self::try_progress) until the frontier is empty
+    /// or the budget runs out.
+    ///
+    /// Returns the completed terms if the execution finished.  Otherwise, the
+    /// partial results are the completed terms followed by the frontier, which
+    /// holds the terms whose step was interrupted.
+    pub fn run_within_budget(&mut self) -> Result<RewriteOutcome<LOS>, RewriteExecutionError<LOS>> {
+        while !self.current_terms.is_empty() {
+            match self.try_progress() {
+                Ok(_) => {}
+                Err(RewriteExecutionError::BudgetExhausted(exhaustion)) => {
+                    let mut partial_results = self.completed_terms.clone();
+                    partial_results.extend(self.current_terms.iter().cloned());
+                    return Ok(RewriteOutcome::BudgetExhausted {
+                        exhaustion,
+                        partial_results,
+                    });
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(RewriteOutcome::Finished(self.completed_terms.clone()))
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol + fmt::Display> RewriteProcessTracedExecutor<LOS> {
+    /// Renders `applications`, as returned by a [`progress`](Self::progress)
+    /// call, with the rules and the sub-terms they rewrote; see
+    /// [`render_trace`].  `previous_frontier` is
+    /// [`get_current_terms`](Self::get_current_terms) before that call.
+    pub fn render_trace(
+        &mut self,
+        previous_frontier: &[LanguageTerm<LOS>],
+        applications: &[AtomicRuleApplication],
+    ) -> String {
+        render_trace(
+            &self.strategy,
+            previous_frontier,
+            applications,
+            &mut self.factory,
+        )
+    }
+}
+
+/// If `result`, reached from `ancestry` through `rule_chain`, already is on the
+/// branch of `ancestry`, returns the cycle.
+fn find_cycle<LOS: RewritableLanguageOperatorSymbol>(
+    strategy: &RewriteProcess<LOS>,
+    ancestry: &Rc<Ancestry<LOS>>,
+    rule_chain: &[(PositionInRewriteProcess, PositionInLanguageTerm)],
+    result: &LanguageTerm<LOS>,
+) -> Option<RewriteCycle<LOS>> {
+    // Walk up the branch, collecting it in reverse order.
+    let mut terms = vec![result.clone()];
+    let mut rule_chains = vec![Some(describe_rule_chain(strategy, rule_chain))];
+    let mut current = ancestry;
+    loop {
+        terms.push(current.term.clone());
+        if current.term == *result {
+            break;
+        }
+        let parent = current.parent.as_ref()?;
+        rule_chains.push(Some(describe_rule_chain(strategy, &current.rule_chain)));
+        current = parent;
+    }
+    terms.reverse();
+    rule_chains.reverse();
+    Some(RewriteCycle { terms, rule_chains })
+}
+
+fn describe_rule_chain<LOS: RewritableLanguageOperatorSymbol>(
+    strategy: &RewriteProcess<LOS>,
+    rule_chain: &[(PositionInRewriteProcess, PositionInLanguageTerm)],
+) -> Vec<CycleRuleApplication> {
+    rule_chain
+        .iter()
+        .map(|(strategy_position, term_position)| CycleRuleApplication {
+            rule_desc: strategy
+                .get_rule_at_position(strategy_position)
+                .expect("rule_chain position resolves to a Rule leaf")
+                .get_desc(),
+            position: term_position.clone(),
+        })
+        .collect()
+}
+
+fn record_edge<LOS: RewritableLanguageOperatorSymbol>(
+    graph: &mut RewriteGraph<LOS>,
+    strategy: &RewriteProcess<LOS>,
+    source: &LanguageTerm<LOS>,
+    target: &LanguageTerm<LOS>,
+    rule_chain: &[(PositionInRewriteProcess, PositionInLanguageTerm)],
+) {
+    let source = graph.add_node(source);
+    let target = graph.add_node(target);
+    let steps = rule_chain
+        .iter()
+        .map(|(strategy_position, term_position)| RewriteGraphStep {
+            rule_desc: strategy
+                .get_rule_at_position(strategy_position)
+                .expect("rule_chain position resolves to a Rule leaf")
+                .get_desc(),
+            strategy_position: strategy_position.clone(),
+            term_position: term_position.clone(),
+        })
+        .collect();
+    graph.add_edge(RewriteGraphEdge {
+        source,
+        target,
+        steps,
+    });
 }
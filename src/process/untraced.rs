@@ -14,8 +14,15 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+use std::fmt;
+use std::rc::Rc;
+
 use crate::position::PositionInLanguageTerm;
+use crate::process::budget::{BudgetExhaustion, RewriteBudget, RewriteOutcome};
+use crate::process::control::{EvaluationControl, RewriteExecutionError};
 use crate::process::strategy::{run_to_completion, RewriteProcess};
+use crate::process::stream::RewriteResultStream;
+use crate::rules::util::ordering::TermOrdering;
 use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
 
 /// One-shot, untraced executor for a [`RewriteProcess`](super::strategy::RewriteProcess).
@@ -23,12 +30,172 @@ use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFa
 /// Applies the strategy to a term and returns all possible results without
 /// recording which rules fired or where.  For step-by-step traces use
 /// [`RewriteProcessTracedExecutor`](super::traced::RewriteProcessTracedExecutor).
-pub struct RewriteProcessUntracedExecutor {}
+///
+/// [`rewrite`](Self::rewrite) runs the strategy without any check.  To enable
+/// opt-in checks or a [`RewriteBudget`], configure an executor with the
+/// `with_*` methods and call [`execute`](Self::execute) or
+/// [`run_within_budget`](Self::run_within_budget).
+#[derive(Clone)]
+pub struct RewriteProcessUntracedExecutor<LOS: RewritableLanguageOperatorSymbol> {
+    check_signatures: bool,
+    detect_cycles: bool,
+    budget: RewriteBudget,
+    termination_monitor: Option<Rc<dyn TermOrdering<LOS>>>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> Default for RewriteProcessUntracedExecutor<LOS> {
+    fn default() -> Self {
+        Self {
+            check_signatures: false,
+            detect_cycles: false,
+            budget: RewriteBudget::default(),
+            termination_monitor: None,
+        }
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> fmt::Debug for RewriteProcessUntracedExecutor<LOS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RewriteProcessUntracedExecutor")
+            .field("check_signatures", &self.check_signatures)
+            .field("detect_cycles", &self.detect_cycles)
+            .field("budget", &self.budget)
+            .field(
+                "termination_monitor",
+                &self.termination_monitor.as_ref().map(|_| ".."),
+            )
+            .finish()
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> RewriteProcessUntracedExecutor<LOS> {
+    /// Creates an executor with every check disabled and an unlimited budget.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When enabled, every rule output is checked with
+    /// [`validate_term`](crate::term::syntax::validate_term) and the first
+    /// ill-formed one aborts the execution with
+    /// [`RewriteExecutionError::IllFormedRuleOutput`].
+    pub fn with_signature_checking(mut self, enabled: bool) -> Self {
+        self.check_signatures = enabled;
+        self
+    }
+
+    /// When enabled, every `Repeat` tracks the terms along each of its
+    /// branches, and coming back to one of them aborts the execution with
+    /// [`RewriteExecutionError::CycleDetected`] instead of looping forever.
+    pub fn with_cycle_detection(mut self, enabled: bool) -> Self {
+        self.detect_cycles = enabled;
+        self
+    }
+
+    /// Checks every rule application against `ordering`: a rule whose output
+    /// is not smaller than the sub-term it rewrote aborts the execution with
+    /// [`RewriteExecutionError::NonDecreasingRuleApplication`]; see
+    /// [`RewriteProcessTracedExecutor::with_termination_monitor`](super::traced::RewriteProcessTracedExecutor::with_termination_monitor).
+    pub fn with_termination_monitor(mut self, ordering: impl TermOrdering<LOS> + 'static) -> Self {
+        self.termination_monitor = Some(Rc::new(ordering));
+        self
+    }
+
+    /// Limits the execution with `budget`; see
+    /// [`run_within_budget`](Self::run_within_budget).
+    pub fn with_budget(mut self, budget: RewriteBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Apply the strategy to `term` and return all possible resulting terms,
+    /// or the error raised by one of the enabled checks.
+    ///
+    /// If the budget runs out, returns
+    /// [`RewriteExecutionError::BudgetExhausted`]; use
+    /// [`run_within_budget`](Self::run_within_budget) to get the partial
+    /// results instead.
+    pub fn execute(
+        &self,
+        strategy: &RewriteProcess<LOS>,
+        term: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<Vec<LanguageTerm<LOS>>, RewriteExecutionError<LOS>> {
+        let (results, exhaustion) = self.evaluate(strategy, term, factory)?;
+        match exhaustion {
+            Some(exhaustion) => Err(RewriteExecutionError::BudgetExhausted(exhaustion)),
+            None => Ok(results),
+        }
+    }
+
+    /// Apply the strategy to `term` within the budget.
+    ///
+    /// If the budget runs out, the partial results are the terms that the
+    /// interrupted evaluation still produced: every `Repeat` that was cut
+    /// short returns the term it had reached, whereas other branches that were
+    /// cut short produce nothing.  Result sets larger than the maximum frontier
+    /// size are truncated.
+    pub fn run_within_budget(
+        &self,
+        strategy: &RewriteProcess<LOS>,
+        term: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<RewriteOutcome<LOS>, RewriteExecutionError<LOS>> {
+        let (results, exhaustion) = self.evaluate(strategy, term, factory)?;
+        Ok(match exhaustion {
+            Some(exhaustion) => RewriteOutcome::BudgetExhausted {
+                exhaustion,
+                partial_results: results,
+            },
+            None => RewriteOutcome::Finished(results),
+        })
+    }
+
+    /// Apply the strategy to `term` lazily: the returned iterator computes
+    /// each result only when it is pulled, so that callers can stop early
+    /// without paying for the whole exploration.  The configured checks and
+    /// budget apply; see [`RewriteResultStream`].
+    pub fn stream<'a>(
+        &self,
+        strategy: &'a RewriteProcess<LOS>,
+        term: &LanguageTerm<LOS>,
+        factory: &'a mut TermFactory<LOS>,
+    ) -> RewriteResultStream<'a, LOS> {
+        let control = EvaluationControl::new(self.check_signatures)
+            .with_cycle_detection(self.detect_cycles)
+            .with_termination_monitor(self.termination_monitor.clone())
+            .with_budget(self.budget.clone(), 0);
+        RewriteResultStream::new(strategy, term, factory, control)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn evaluate(
+        &self,
+        strategy: &RewriteProcess<LOS>,
+        term: &LanguageTerm<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<(Vec<LanguageTerm<LOS>>, Option<BudgetExhaustion>), RewriteExecutionError<LOS>>
+    {
+        let mut control = EvaluationControl::new(self.check_signatures)
+            .with_cycle_detection(self.detect_cycles)
+            .with_termination_monitor(self.termination_monitor.clone())
+            .with_budget(self.budget.clone(), 0);
+        let results = run_to_completion(
+            strategy,
+            term,
+            term,
+            &PositionInLanguageTerm::get_root_position(),
+            factory,
+            &mut control,
+        );
+        match control.take_error() {
+            Some(error) => Err(error),
+            None => Ok((results, control.take_exhaustion())),
+        }
+    }
 
-impl RewriteProcessUntracedExecutor {
     /// Apply the strategy to `term` and return all possible resulting terms.
     /// For a step-by-step traced execution see [`RewriteProcessTracedExecutor`](super::traced::RewriteProcessTracedExecutor).
-    pub fn rewrite<LOS: RewritableLanguageOperatorSymbol>(
+    pub fn rewrite(
         strategy: &RewriteProcess<LOS>,
         term: &LanguageTerm<LOS>,
         factory: &mut TermFactory<LOS>,
@@ -39,6 +206,7 @@ impl RewriteProcessUntracedExecutor {
             term,
             &PositionInLanguageTerm::get_root_position(),
             factory,
+            &mut EvaluationControl::new(false),
         )
     }
 }
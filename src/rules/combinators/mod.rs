@@ -16,3 +16,4 @@ limitations under the License.
 
 pub mod guard;
 pub mod guarded;
+pub mod monitored;
@@ -0,0 +1,118 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::position::PositionInLanguageTerm;
+use crate::process::control::TerminationViolation;
+use crate::rule::RewriteRule;
+use crate::rules::util::ordering::TermOrdering;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
+
+/// Wraps any rewrite rule with a termination monitor: in builds with debug
+/// assertions, every output of the rule is checked to be smaller than the
+/// sub-term it rewrote under a [`TermOrdering`], such as the
+/// [`LexicographicPathOrdering`](crate::rules::util::lpo::LexicographicPathOrdering)
+/// or a [`MeasureOrdering`](crate::rules::util::ordering::MeasureOrdering).
+///
+/// A violation panics, reporting the rule's description, the position and
+/// both terms, unless a handler is set with
+/// [`with_violation_handler`](Self::with_violation_handler).  Either way the
+/// rule's output is returned unchanged.  Like `debug_assert!`, the check costs
+/// nothing in release builds; for a check that reports an error instead, see
+/// [`RewriteProcessTracedExecutor::with_termination_monitor`](crate::process::traced::RewriteProcessTracedExecutor::with_termination_monitor).
+///
+/// # Example
+///
+/// ```rust
+/// use simple_term_rewriter::metrics::builtin::tree_size;
+/// use simple_term_rewriter::rules::combinators::monitored::TerminationMonitoredRule;
+/// use simple_term_rewriter::rules::primitives::root::RootRule;
+/// use simple_term_rewriter::rules::util::ordering::MeasureOrdering;
+/// use simple_term_rewriter::term::syntax::RewritableLanguageOperatorSymbol;
+///
+/// #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// enum Op { Not, True, False }
+/// impl RewritableLanguageOperatorSymbol for Op {
+///     fn arity(&self) -> simple_term_rewriter::term::syntax::LanguageOperatorArity {
+///         use simple_term_rewriter::term::syntax::LanguageOperatorArity::Fixed;
+///         match self { Op::Not => Fixed(1), _ => Fixed(0) }
+///     }
+/// }
+///
+/// let rule = TerminationMonitoredRule::new(
+///     RootRule::new("example", |op: &Op| *op == Op::Not, |_op, _children, _f| None),
+///     MeasureOrdering::new(tree_size),
+/// );
+/// ```
+#[allow(clippy::type_complexity)]
+pub struct TerminationMonitoredRule<LOS: RewritableLanguageOperatorSymbol> {
+    inner: Box<dyn RewriteRule<LOS>>,
+    ordering: Box<dyn TermOrdering<LOS>>,
+    on_violation: Option<Box<dyn Fn(&TerminationViolation<LOS>)>>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> TerminationMonitoredRule<LOS> {
+    pub fn new(
+        inner: impl RewriteRule<LOS> + 'static,
+        ordering: impl TermOrdering<LOS> + 'static,
+    ) -> Self {
+        Self {
+            inner: Box::new(inner),
+            ordering: Box::new(ordering),
+            on_violation: None,
+        }
+    }
+
+    /// Calls `on_violation` instead of panicking, e.g. to log violations.
+    pub fn with_violation_handler(
+        mut self,
+        on_violation: impl Fn(&TerminationViolation<LOS>) + 'static,
+    ) -> Self {
+        self.on_violation = Some(Box::new(on_violation));
+        self
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> RewriteRule<LOS> for TerminationMonitoredRule<LOS> {
+    fn get_desc(&self) -> String {
+        self.inner.get_desc()
+    }
+
+    fn try_apply(
+        &self,
+        term: &LanguageTerm<LOS>,
+        ctx: &LanguageTerm<LOS>,
+        pos: &PositionInLanguageTerm,
+        factory: &mut TermFactory<LOS>,
+    ) -> Option<LanguageTerm<LOS>> {
+        let output = self.inner.try_apply(term, ctx, pos, factory)?;
+        if cfg!(debug_assertions) && !self.ordering.is_greater(term, &output) {
+            let violation = TerminationViolation {
+                rule_desc: self.inner.get_desc(),
+                position: pos.clone(),
+                redex: term.clone(),
+                output: output.clone(),
+            };
+            match &self.on_violation {
+                Some(on_violation) => on_violation(&violation),
+                None => panic!(
+                    "rule '{}' fired at position {} without decreasing the rewritten sub-term: {:?} became {:?}",
+                    violation.rule_desc, violation.position, violation.redex, violation.output
+                ),
+            }
+        }
+        Some(output)
+    }
+}
@@ -17,6 +17,7 @@ limitations under the License.
 pub mod factorization;
 pub mod flat_apc;
 pub mod flush;
+pub mod pattern;
 pub mod reorder_apc;
 pub mod reorder_pc;
 pub mod root;
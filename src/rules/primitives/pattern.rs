@@ -0,0 +1,125 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::position::PositionInLanguageTerm;
+use crate::rule::RewriteRule;
+use crate::term::pattern::Pattern;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
+
+/// Errors returned by [`PatternRule::new`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PatternRuleError {
+    /// The left-hand side is a bare variable, which would match every term.
+    LeftHandSideIsAVariable,
+    /// The right-hand side uses a variable that does not occur in the
+    /// left-hand side, so it could never be instantiated.
+    UnboundRightHandSideVariable(String),
+}
+
+/// A rewrite rule `lhs → rhs` given by two [`Pattern`]s.
+///
+/// The rule fires at the current term when `lhs` matches it syntactically;
+/// the result is `rhs` instantiated with the matching substitution.
+///
+/// # Example
+///
+/// ```rust
+/// use simple_term_rewriter::rules::primitives::pattern::PatternRule;
+/// use simple_term_rewriter::term::pattern::Pattern;
+/// use simple_term_rewriter::term::syntax::RewritableLanguageOperatorSymbol;
+///
+/// #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// enum Op { Not, True, False }
+/// impl RewritableLanguageOperatorSymbol for Op {
+///     fn arity(&self) -> simple_term_rewriter::term::syntax::LanguageOperatorArity {
+///         use simple_term_rewriter::term::syntax::LanguageOperatorArity::Fixed;
+///         match self { Op::Not => Fixed(1), _ => Fixed(0) }
+///     }
+/// }
+///
+/// // NOT(NOT(x)) → x
+/// let double_neg = PatternRule::new(
+///     "double negation",
+///     Pattern::op(Op::Not, vec![Pattern::op(Op::Not, vec![Pattern::var("x")])]),
+///     Pattern::var("x"),
+/// )
+/// .unwrap();
+/// ```
+pub struct PatternRule<LOS: RewritableLanguageOperatorSymbol> {
+    desc: String,
+    lhs: Pattern<LOS>,
+    rhs: Pattern<LOS>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> PatternRule<LOS> {
+    /// Creates the rule `lhs → rhs`.
+    ///
+    /// Returns an error if `lhs` is a variable or if `rhs` contains a variable
+    /// that does not occur in `lhs`.
+    pub fn new(
+        desc: impl Into<String>,
+        lhs: Pattern<LOS>,
+        rhs: Pattern<LOS>,
+    ) -> Result<Self, PatternRuleError> {
+        if let Pattern::Variable(_) = lhs {
+            return Err(PatternRuleError::LeftHandSideIsAVariable);
+        }
+        let lhs_vars = lhs.get_variables();
+        let mut unbound: Vec<&str> = rhs
+            .get_variables()
+            .into_iter()
+            .filter(|v| !lhs_vars.contains(v))
+            .collect();
+        unbound.sort();
+        if let Some(var) = unbound.first() {
+            return Err(PatternRuleError::UnboundRightHandSideVariable(
+                var.to_string(),
+            ));
+        }
+        Ok(Self {
+            desc: desc.into(),
+            lhs,
+            rhs,
+        })
+    }
+
+    /// Returns the left-hand side pattern.
+    pub fn get_lhs(&self) -> &Pattern<LOS> {
+        &self.lhs
+    }
+
+    /// Returns the right-hand side pattern.
+    pub fn get_rhs(&self) -> &Pattern<LOS> {
+        &self.rhs
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> RewriteRule<LOS> for PatternRule<LOS> {
+    fn get_desc(&self) -> String {
+        self.desc.clone()
+    }
+
+    fn try_apply(
+        &self,
+        term: &LanguageTerm<LOS>,
+        _ctx: &LanguageTerm<LOS>,
+        _pos: &PositionInLanguageTerm,
+        factory: &mut TermFactory<LOS>,
+    ) -> Option<LanguageTerm<LOS>> {
+        let substitution = self.lhs.match_term(term)?;
+        self.rhs.instantiate(&substitution, factory)
+    }
+}
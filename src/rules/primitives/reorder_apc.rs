@@ -20,6 +20,7 @@ use crate::rules::util::assoc::{
     fold_associative_sub_terms_recursively, get_associative_sub_terms_recursively,
 };
 use crate::rules::util::lpo::is_greater_as_per_lexicographic_path_ordering;
+use crate::rules::util::ordering::TermOrdering;
 use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory};
 
 /// Everything needed to canonically reorder an associative, partially commutative
@@ -42,6 +43,7 @@ pub trait ModuloAssociativePartialReorderer<LOS: RewritableLanguageOperatorSymbo
 
 fn ad_hoc_partially_commutative_recursive_reorderer<LOS: RewritableLanguageOperatorSymbol>(
     checker: &dyn ModuloAssociativePartialReorderer<LOS>,
+    is_greater: &dyn Fn(&LanguageTerm<LOS>, &LanguageTerm<LOS>) -> bool,
     considered_ac_operator: &LOS,
     flattened_sub_terms: Vec<LanguageTerm<LOS>>,
     has_changed: &mut bool,
@@ -54,6 +56,7 @@ fn ad_hoc_partially_commutative_recursive_reorderer<LOS: RewritableLanguageOpera
     let head = items.remove(0);
     let mut sorted_tail = ad_hoc_partially_commutative_recursive_reorderer(
         checker,
+        is_greater,
         considered_ac_operator,
         items,
         has_changed,
@@ -62,14 +65,13 @@ fn ad_hoc_partially_commutative_recursive_reorderer<LOS: RewritableLanguageOpera
     let mut remainder = sorted_tail;
 
     if checker.may_commute_under(considered_ac_operator, &head, &head_of_tail)
-        && is_greater_as_per_lexicographic_path_ordering(&head, &head_of_tail, &|x, y| {
-            checker.compare_operators(x, y)
-        })
+        && is_greater(&head, &head_of_tail)
     {
         *has_changed = true;
         remainder.insert(0, head);
         let mut remainder = ad_hoc_partially_commutative_recursive_reorderer(
             checker,
+            is_greater,
             considered_ac_operator,
             remainder,
             has_changed,
@@ -85,6 +87,7 @@ fn ad_hoc_partially_commutative_recursive_reorderer<LOS: RewritableLanguageOpera
 
 fn transformation_modulo_assoc_partial_reordering<LOS: RewritableLanguageOperatorSymbol>(
     checker: &dyn ModuloAssociativePartialReorderer<LOS>,
+    is_greater: &dyn Fn(&LanguageTerm<LOS>, &LanguageTerm<LOS>) -> bool,
     term: &LanguageTerm<LOS>,
     factory: &mut TermFactory<LOS>,
 ) -> Option<LanguageTerm<LOS>> {
@@ -101,8 +104,13 @@ fn transformation_modulo_assoc_partial_reordering<LOS: RewritableLanguageOperato
         .collect();
 
     let mut has_changed = false;
-    let mut sorted =
-        ad_hoc_partially_commutative_recursive_reorderer(checker, op, flat, &mut has_changed);
+    let mut sorted = ad_hoc_partially_commutative_recursive_reorderer(
+        checker,
+        is_greater,
+        op,
+        flat,
+        &mut has_changed,
+    );
 
     if has_changed {
         fold_associative_sub_terms_recursively(op, &mut sorted, &None, factory)
@@ -113,9 +121,14 @@ fn transformation_modulo_assoc_partial_reordering<LOS: RewritableLanguageOperato
 
 /// Rewrite rule that canonically reorders operands of a partially commutative,
 /// associative operator.
+///
+/// Operands are sorted by the LPO induced by the checker's
+/// `compare_operators`, unless another [`TermOrdering`] is given with
+/// [`with_term_ordering`](Self::with_term_ordering).
 pub struct PartialACReorderRule<LOS: RewritableLanguageOperatorSymbol> {
     desc: String,
     checker: Box<dyn ModuloAssociativePartialReorderer<LOS>>,
+    ordering: Option<Box<dyn TermOrdering<LOS>>>,
 }
 
 impl<LOS: RewritableLanguageOperatorSymbol> PartialACReorderRule<LOS> {
@@ -126,8 +139,15 @@ impl<LOS: RewritableLanguageOperatorSymbol> PartialACReorderRule<LOS> {
         Self {
             desc: desc.into(),
             checker: Box::new(checker),
+            ordering: None,
         }
     }
+
+    /// Sorts the operands with `ordering` instead of the LPO.
+    pub fn with_term_ordering(mut self, ordering: impl TermOrdering<LOS> + 'static) -> Self {
+        self.ordering = Some(Box::new(ordering));
+        self
+    }
 }
 
 impl<LOS: RewritableLanguageOperatorSymbol> RewriteRule<LOS> for PartialACReorderRule<LOS> {
@@ -141,6 +161,13 @@ impl<LOS: RewritableLanguageOperatorSymbol> RewriteRule<LOS> for PartialACReorde
         _pos: &PositionInLanguageTerm,
         factory: &mut TermFactory<LOS>,
     ) -> Option<LanguageTerm<LOS>> {
-        transformation_modulo_assoc_partial_reordering(self.checker.as_ref(), term, factory)
+        let checker = self.checker.as_ref();
+        let is_greater = |x: &LanguageTerm<LOS>, y: &LanguageTerm<LOS>| match &self.ordering {
+            Some(ordering) => ordering.is_greater(x, y),
+            None => is_greater_as_per_lexicographic_path_ordering(x, y, &|a, b| {
+                checker.compare_operators(a, b)
+            }),
+        };
+        transformation_modulo_assoc_partial_reordering(checker, &is_greater, term, factory)
     }
 }
@@ -17,6 +17,7 @@ limitations under the License.
 use crate::position::PositionInLanguageTerm;
 use crate::rule::RewriteRule;
 use crate::rules::util::lpo::is_greater_as_per_lexicographic_path_ordering;
+use crate::rules::util::ordering::TermOrdering;
 use crate::term::syntax::{
     LanguageTerm, LanguageTermNode, RewritableLanguageOperatorSymbol, TermFactory,
 };
@@ -38,9 +39,13 @@ pub trait CommutativeCheckerAndOrderer<LOS: RewritableLanguageOperatorSymbol> {
 
 /// Swaps the two operands of a commutative binary operator when they are
 /// out of LPO order: `op(y, x) → op(x, y)` when `x <_lpo y`.
+///
+/// The LPO is induced by the checker's `compare_operators`, unless another
+/// [`TermOrdering`] is given with [`with_term_ordering`](Self::with_term_ordering).
 pub struct CommuteReorderRule<LOS: RewritableLanguageOperatorSymbol> {
     desc: String,
     checker: Box<dyn CommutativeCheckerAndOrderer<LOS>>,
+    ordering: Option<Box<dyn TermOrdering<LOS>>>,
 }
 
 impl<LOS: RewritableLanguageOperatorSymbol> CommuteReorderRule<LOS> {
@@ -51,6 +56,22 @@ impl<LOS: RewritableLanguageOperatorSymbol> CommuteReorderRule<LOS> {
         Self {
             desc: desc.into(),
             checker: Box::new(checker),
+            ordering: None,
+        }
+    }
+
+    /// Orders the operands with `ordering` instead of the LPO.
+    pub fn with_term_ordering(mut self, ordering: impl TermOrdering<LOS> + 'static) -> Self {
+        self.ordering = Some(Box::new(ordering));
+        self
+    }
+
+    fn is_greater(&self, left: &LanguageTerm<LOS>, right: &LanguageTerm<LOS>) -> bool {
+        match &self.ordering {
+            Some(ordering) => ordering.is_greater(left, right),
+            None => is_greater_as_per_lexicographic_path_ordering::<LOS>(left, right, &|x, y| {
+                self.checker.compare_operators(x, y)
+            }),
         }
     }
 }
@@ -73,11 +94,7 @@ impl<LOS: RewritableLanguageOperatorSymbol> RewriteRule<LOS> for CommuteReorderR
         }
         let left = &term.sub_terms[0];
         let right = &term.sub_terms[1];
-        if self.checker.may_commute_under(op, left, right)
-            && is_greater_as_per_lexicographic_path_ordering::<LOS>(left, right, &|x, y| {
-                self.checker.compare_operators(x, y)
-            })
-        {
+        if self.checker.may_commute_under(op, left, right) && self.is_greater(left, right) {
             Some(LanguageTermNode::build(
                 op.clone(),
                 vec![right.clone(), left.clone()],
@@ -0,0 +1,282 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::cmp::Ordering;
+
+use crate::rules::util::assoc::{
+    fold_associative_sub_terms_recursively, get_associative_sub_terms_recursively,
+};
+use crate::rules::util::ordering::TermOrdering;
+use crate::rules::util::rpo::{
+    are_equivalent_as_per_path_ordering, is_greater_as_per_path_ordering, path_ordering,
+    OperatorStatus,
+};
+use crate::term::syntax::{
+    LanguageTerm, LanguageTermNode, RewritableLanguageOperatorSymbol, TermFactory,
+};
+
+/// The sub-terms of `term` seen by the AC-RPO: the elements of the flattened
+/// chain if the root operator is associative-commutative, the actual
+/// sub-terms otherwise.
+pub fn get_ac_flattened_sub_terms<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+    is_ac_operator: &dyn Fn(&LOS) -> bool,
+) -> Vec<LanguageTerm<LOS>> {
+    if is_ac_operator(&term.operator) {
+        term.sub_terms
+            .iter()
+            .flat_map(|sub_term| get_associative_sub_terms_recursively(sub_term, &term.operator))
+            .cloned()
+            .collect()
+    } else {
+        term.sub_terms.clone()
+    }
+}
+
+/// Returns `true` iff `s` and `t` are equal modulo the associativity and
+/// commutativity of the operators selected by `is_ac_operator`.
+pub fn are_equivalent_as_per_ac_recursive_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    is_ac_operator: &dyn Fn(&LOS) -> bool,
+) -> bool {
+    are_equivalent_as_per_path_ordering(
+        s,
+        t,
+        &|op| ac_status(op, is_ac_operator, &|_| OperatorStatus::Lexicographic),
+        &|term| get_ac_flattened_sub_terms(term, is_ac_operator),
+    )
+}
+
+/// Returns the [`Ordering`] between two terms under the AC-compatible
+/// recursive path ordering induced by the given total order on operator
+/// symbols, see [`is_greater_as_per_ac_recursive_path_ordering`].
+///
+/// Returns `Equal` iff `s` and `t` are AC-equal
+/// (see [`are_equivalent_as_per_ac_recursive_path_ordering`]).
+pub fn ac_recursive_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+    is_ac_operator: &dyn Fn(&LOS) -> bool,
+) -> Ordering {
+    path_ordering(
+        s,
+        t,
+        compare_operators,
+        &|op| ac_status(op, is_ac_operator, &|_| OperatorStatus::Lexicographic),
+        &|term| get_ac_flattened_sub_terms(term, is_ac_operator),
+    )
+}
+
+/// Returns `true` iff `s` is greater than `t` under the AC-compatible
+/// recursive path ordering (AC-RPO).
+///
+/// # Definition
+///
+/// This is the [recursive path ordering](super::rpo::is_greater_as_per_recursive_path_ordering)
+/// on flattened terms: the sub-terms of a term whose root operator `f` is
+/// associative-commutative are the elements of its maximal `f`-chain (see
+/// [`get_ac_flattened_sub_terms`]), compared as multisets.  Every other
+/// operator has lexicographic status.
+///
+/// # Properties
+///
+/// When `compare_operators` is a total order, the AC-RPO is well-founded and
+/// total on ground terms up to AC-equality, and AC-equal terms compare
+/// identically against any other term, which is what the LPO, looking at
+/// binary trees, cannot offer.  Unlike the refined AC-RPO of Rubio and
+/// Nieuwenhuis, this flattened ordering is not monotone for every precedence,
+/// so it is meant to compare whole terms, e.g. to pick AC-canonical
+/// representatives or as a termination measure on terms.
+pub fn is_greater_as_per_ac_recursive_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+    is_ac_operator: &dyn Fn(&LOS) -> bool,
+) -> bool {
+    is_greater_as_per_path_ordering(
+        s,
+        t,
+        compare_operators,
+        &|op| ac_status(op, is_ac_operator, &|_| OperatorStatus::Lexicographic),
+        &|term| get_ac_flattened_sub_terms(term, is_ac_operator),
+    )
+}
+
+fn ac_status<LOS: RewritableLanguageOperatorSymbol>(
+    op: &LOS,
+    is_ac_operator: &dyn Fn(&LOS) -> bool,
+    get_status: &dyn Fn(&LOS) -> OperatorStatus,
+) -> OperatorStatus {
+    if is_ac_operator(op) {
+        OperatorStatus::Multiset
+    } else {
+        get_status(op)
+    }
+}
+
+/// The AC-compatible recursive path ordering as a [`TermOrdering`], see
+/// [`is_greater_as_per_ac_recursive_path_ordering`].
+///
+/// Operators that are not associative-commutative have
+/// [`OperatorStatus::Lexicographic`] status unless specified otherwise with
+/// [`Self::with_operator_status`].
+#[allow(clippy::type_complexity)]
+pub struct ACRecursivePathOrdering<LOS: RewritableLanguageOperatorSymbol> {
+    compare_operators: Box<dyn Fn(&LOS, &LOS) -> Ordering>,
+    is_ac_operator: Box<dyn Fn(&LOS) -> bool>,
+    get_status: Box<dyn Fn(&LOS) -> OperatorStatus>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> ACRecursivePathOrdering<LOS> {
+    /// `compare_operators` must be a total order on operator symbols, and
+    /// `is_ac_operator` selects the binary associative-commutative operators.
+    pub fn new(
+        compare_operators: impl Fn(&LOS, &LOS) -> Ordering + 'static,
+        is_ac_operator: impl Fn(&LOS) -> bool + 'static,
+    ) -> Self {
+        Self {
+            compare_operators: Box::new(compare_operators),
+            is_ac_operator: Box::new(is_ac_operator),
+            get_status: Box::new(|_| OperatorStatus::Lexicographic),
+        }
+    }
+
+    /// Sets the status of the operators that are not associative-commutative,
+    /// e.g. [`OperatorStatus::Multiset`] for those that are only commutative.
+    pub fn with_operator_status(
+        mut self,
+        get_status: impl Fn(&LOS) -> OperatorStatus + 'static,
+    ) -> Self {
+        self.get_status = Box::new(get_status);
+        self
+    }
+
+    /// Returns `true` iff `s` and `t` are equal modulo AC (and modulo the
+    /// commutativity of multiset-status operators).
+    pub fn are_equivalent(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> bool {
+        are_equivalent_as_per_path_ordering(s, t, &|op| self.get_operator_status(op), &|term| {
+            get_ac_flattened_sub_terms(term, self.is_ac_operator.as_ref())
+        })
+    }
+
+    fn get_operator_status(&self, op: &LOS) -> OperatorStatus {
+        ac_status(op, self.is_ac_operator.as_ref(), self.get_status.as_ref())
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> TermOrdering<LOS> for ACRecursivePathOrdering<LOS> {
+    fn compare_terms(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> Ordering {
+        path_ordering(
+            s,
+            t,
+            self.compare_operators.as_ref(),
+            &|op| self.get_operator_status(op),
+            &|term| get_ac_flattened_sub_terms(term, self.is_ac_operator.as_ref()),
+        )
+    }
+
+    fn is_greater(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> bool {
+        is_greater_as_per_path_ordering(
+            s,
+            t,
+            self.compare_operators.as_ref(),
+            &|op| self.get_operator_status(op),
+            &|term| get_ac_flattened_sub_terms(term, self.is_ac_operator.as_ref()),
+        )
+    }
+}
+
+/// Checks that `ordering` is compatible with AC on the given (e.g. randomly
+/// generated) terms: every term must compare `Equal` to its AC-variants, and
+/// a term and its AC-variants must compare identically against every other
+/// term of the list.
+///
+/// The AC-variants of a term are obtained by rebuilding each of its
+/// associative-commutative chains with the elements reversed and
+/// right-associated, and rotated by one and left-associated.
+///
+/// Returns the first pair `(term, variant)` for which this does not hold,
+/// or `None`.
+pub fn find_ac_compatibility_violation<LOS: RewritableLanguageOperatorSymbol>(
+    ordering: &dyn TermOrdering<LOS>,
+    terms: &[LanguageTerm<LOS>],
+    is_ac_operator: &dyn Fn(&LOS) -> bool,
+    factory: &mut TermFactory<LOS>,
+) -> Option<(LanguageTerm<LOS>, LanguageTerm<LOS>)> {
+    for term in terms {
+        let variants = [
+            rebuild_ac_chains(term, is_ac_operator, &reversed_right_associated, factory),
+            rebuild_ac_chains(term, is_ac_operator, &rotated_left_associated, factory),
+        ];
+        for variant in variants {
+            let compatible = ordering.compare_terms(term, &variant) == Ordering::Equal
+                && terms.iter().all(|other| {
+                    ordering.compare_terms(term, other) == ordering.compare_terms(&variant, other)
+                });
+            if !compatible {
+                return Some((term.clone(), variant));
+            }
+        }
+    }
+    None
+}
+
+#[allow(clippy::type_complexity)]
+fn rebuild_ac_chains<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+    is_ac_operator: &dyn Fn(&LOS) -> bool,
+    fold: &dyn Fn(&LOS, Vec<LanguageTerm<LOS>>, &mut TermFactory<LOS>) -> LanguageTerm<LOS>,
+    factory: &mut TermFactory<LOS>,
+) -> LanguageTerm<LOS> {
+    if is_ac_operator(&term.operator) {
+        let elements = get_ac_flattened_sub_terms(term, is_ac_operator)
+            .iter()
+            .map(|element| rebuild_ac_chains(element, is_ac_operator, fold, factory))
+            .collect();
+        fold(&term.operator, elements, factory)
+    } else {
+        let sub_terms = term
+            .sub_terms
+            .iter()
+            .map(|sub_term| rebuild_ac_chains(sub_term, is_ac_operator, fold, factory))
+            .collect();
+        LanguageTermNode::build(term.operator.clone(), sub_terms, factory)
+    }
+}
+
+fn reversed_right_associated<LOS: RewritableLanguageOperatorSymbol>(
+    op: &LOS,
+    mut elements: Vec<LanguageTerm<LOS>>,
+    factory: &mut TermFactory<LOS>,
+) -> LanguageTerm<LOS> {
+    elements.reverse();
+    fold_associative_sub_terms_recursively(op, &mut elements, &None, factory).unwrap()
+}
+
+fn rotated_left_associated<LOS: RewritableLanguageOperatorSymbol>(
+    op: &LOS,
+    mut elements: Vec<LanguageTerm<LOS>>,
+    factory: &mut TermFactory<LOS>,
+) -> LanguageTerm<LOS> {
+    elements.rotate_left(1);
+    let mut elements = elements.into_iter();
+    let first = elements.next().unwrap();
+    elements.fold(first, |left, right| {
+        LanguageTermNode::build(op.clone(), vec![left, right], factory)
+    })
+}
@@ -0,0 +1,220 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::rules::util::ordering::TermOrdering;
+use crate::term::syntax::{LanguageOperatorArity, LanguageTerm, RewritableLanguageOperatorSymbol};
+
+/// Why a weight function is not admissible for the Knuth–Bendix ordering,
+/// as reported by [`KnuthBendixOrdering::check_admissibility`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum KnuthBendixAdmissibilityError<LOS: RewritableLanguageOperatorSymbol> {
+    /// A constant has weight 0.
+    ZeroWeightConstant(LOS),
+    /// `symbol`, unary or variadic, has weight 0 but is not greater than
+    /// `other` in the precedence.
+    ZeroWeightSymbolNotMaximal { symbol: LOS, other: LOS },
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> fmt::Display for KnuthBendixAdmissibilityError<LOS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KnuthBendixAdmissibilityError::ZeroWeightConstant(constant) => {
+                write!(f, "constant {:?} has weight 0", constant)
+            }
+            KnuthBendixAdmissibilityError::ZeroWeightSymbolNotMaximal { symbol, other } => write!(
+                f,
+                "operator {:?} has weight 0 but is not greater than {:?} in the precedence",
+                symbol, other
+            ),
+        }
+    }
+}
+
+/// The weight of `term`: the sum of the weights of its operators, saturating
+/// at `usize::MAX`.
+pub fn term_weight<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+    weight: &dyn Fn(&LOS) -> usize,
+) -> usize {
+    term.sub_terms
+        .iter()
+        .fold(weight(&term.operator), |sum, sub_term| {
+            sum.saturating_add(term_weight(sub_term, weight))
+        })
+}
+
+/// [`term_weight`], computed once per distinct sub-term.
+fn memoized_term_weight<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+    weight: &dyn Fn(&LOS) -> usize,
+    weights: &mut HashMap<LanguageTerm<LOS>, usize>,
+) -> usize {
+    if let Some(term_weight) = weights.get(term) {
+        return *term_weight;
+    }
+    let term_weight = term
+        .sub_terms
+        .iter()
+        .fold(weight(&term.operator), |sum, sub_term| {
+            sum.saturating_add(memoized_term_weight(sub_term, weight, weights))
+        });
+    weights.insert(term.clone(), term_weight);
+    term_weight
+}
+
+/// Returns the [`Ordering`] between two terms under the Knuth–Bendix
+/// ordering induced by the given operator weights and total order on
+/// operator symbols (the precedence).
+///
+/// # Definition
+///
+/// On ground terms `s = f(s₁,…,sₙ)` and `t = g(t₁,…,tₘ)` (Baader & Nipkow,
+/// *Term Rewriting and All That*, §5.4.4), `s >_kbo t` iff:
+/// - `w(s) > w(t)`, or
+/// - `w(s) = w(t)` and `f > g`, or
+/// - `w(s) = w(t)`, `f = g`, and `(s₁,…,sₙ)` is greater than `(t₁,…,tₘ)` in
+///   the lexicographic extension of `>_kbo` (a proper prefix being smaller,
+///   for variadic operators).
+///
+/// where `w(s)` is the [`term_weight`] of `s`.  The weight of every sub-term
+/// is computed once per comparison.
+///
+/// # Properties
+///
+/// When `compare_operators` is a total order and the weights are admissible
+/// (see [`KnuthBendixOrdering::check_admissibility`]), the resulting KBO is a
+/// simplification ordering, total on ground terms.  Unlike the LPO, it
+/// orients every rule whose left-hand side is heavier than its right-hand
+/// side, whatever their root operators.
+pub fn knuth_bendix_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    weight: &dyn Fn(&LOS) -> usize,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+) -> Ordering {
+    compare_with_weights(s, t, weight, compare_operators, &mut HashMap::new())
+}
+
+/// [`knuth_bendix_ordering`], with the weights of the sub-terms already
+/// compared kept in `weights`.
+fn compare_with_weights<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    weight: &dyn Fn(&LOS) -> usize,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+    weights: &mut HashMap<LanguageTerm<LOS>, usize>,
+) -> Ordering {
+    if s == t {
+        return Ordering::Equal;
+    }
+    let s_weight = memoized_term_weight(s, weight, weights);
+    let t_weight = memoized_term_weight(t, weight, weights);
+    s_weight
+        .cmp(&t_weight)
+        .then_with(|| compare_operators(&s.operator, &t.operator))
+        .then_with(|| {
+            s.sub_terms
+                .iter()
+                .zip(t.sub_terms.iter())
+                .map(|(si, ti)| compare_with_weights(si, ti, weight, compare_operators, weights))
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or_else(|| s.sub_terms.len().cmp(&t.sub_terms.len()))
+        })
+}
+
+/// Returns `true` iff `s >_kbo t`; see [`knuth_bendix_ordering`].
+pub fn is_greater_as_per_knuth_bendix_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    weight: &dyn Fn(&LOS) -> usize,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+) -> bool {
+    knuth_bendix_ordering(s, t, weight, compare_operators) == Ordering::Greater
+}
+
+/// The Knuth–Bendix ordering as a [`TermOrdering`], see
+/// [`knuth_bendix_ordering`].
+#[allow(clippy::type_complexity)]
+pub struct KnuthBendixOrdering<LOS: RewritableLanguageOperatorSymbol> {
+    weight: Box<dyn Fn(&LOS) -> usize>,
+    compare_operators: Box<dyn Fn(&LOS, &LOS) -> Ordering>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> KnuthBendixOrdering<LOS> {
+    /// `compare_operators` must be a total order on operator symbols.
+    pub fn new(
+        weight: impl Fn(&LOS) -> usize + 'static,
+        compare_operators: impl Fn(&LOS, &LOS) -> Ordering + 'static,
+    ) -> Self {
+        Self {
+            weight: Box::new(weight),
+            compare_operators: Box::new(compare_operators),
+        }
+    }
+
+    /// Checks that the weights are admissible for the terms built on
+    /// `symbols`: every constant has a positive weight, and a unary operator
+    /// of weight 0 is greater than every other symbol in the precedence.
+    ///
+    /// Variadic operators of weight 0 are held to the same condition as unary
+    /// ones, since they may have a single sub-term.
+    pub fn check_admissibility(
+        &self,
+        symbols: &[LOS],
+    ) -> Result<(), KnuthBendixAdmissibilityError<LOS>> {
+        for symbol in symbols {
+            if (self.weight)(symbol) > 0 {
+                continue;
+            }
+            match symbol.arity() {
+                LanguageOperatorArity::Fixed(0) => {
+                    return Err(KnuthBendixAdmissibilityError::ZeroWeightConstant(
+                        symbol.clone(),
+                    ));
+                }
+                LanguageOperatorArity::Fixed(1) | LanguageOperatorArity::Variadic => {
+                    let dominated = symbols.iter().find(|other| {
+                        *other != symbol
+                            && (self.compare_operators)(symbol, other) != Ordering::Greater
+                    });
+                    if let Some(other) = dominated {
+                        return Err(KnuthBendixAdmissibilityError::ZeroWeightSymbolNotMaximal {
+                            symbol: symbol.clone(),
+                            other: other.clone(),
+                        });
+                    }
+                }
+                LanguageOperatorArity::Fixed(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The [`term_weight`] of `term` under these weights.
+    pub fn get_term_weight(&self, term: &LanguageTerm<LOS>) -> usize {
+        term_weight(term, self.weight.as_ref())
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> TermOrdering<LOS> for KnuthBendixOrdering<LOS> {
+    fn compare_terms(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> Ordering {
+        knuth_bendix_ordering(s, t, self.weight.as_ref(), self.compare_operators.as_ref())
+    }
+}
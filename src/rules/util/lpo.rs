@@ -16,6 +16,7 @@ limitations under the License.
 
 use std::cmp::Ordering;
 
+use crate::rules::util::ordering::TermOrdering;
 use crate::term::syntax::{LanguageOperatorArity, LanguageTerm, RewritableLanguageOperatorSymbol};
 
 fn resolve_arity<LOS: RewritableLanguageOperatorSymbol>(term: &LanguageTerm<LOS>) -> usize {
@@ -71,7 +72,10 @@ pub fn lexicographic_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
 /// **Case `f = g`** — `s >_lpo t` iff there exists a critical index `k` such that:
 /// - `s₁ = t₁, …, s_{k-1} = t_{k-1}` (all earlier children are equal),
 /// - `sₖ >_lpo tₖ`, and
-/// - `s >_lpo tⱼ` for every `j > k` (s dominates all remaining children of t).
+/// - `s >_lpo tⱼ` for every `j > k` (s dominates all remaining children of t),
+///
+/// or, as in the case `f < g`, some child `sᵢ` of `s` satisfies `sᵢ = t` or
+/// `sᵢ >_lpo t`.
 ///
 /// # Parameters
 ///
@@ -142,10 +146,45 @@ pub fn is_greater_as_per_lexicographic_path_ordering<LOS: RewritableLanguageOper
                     return true;
                 }
                 if is_greater_as_per_lexicographic_path_ordering(ti, si, compare_operators) {
-                    return false;
+                    break;
+                }
+            }
+            // Otherwise s still dominates t if one of its sub-terms does,
+            // e.g. f(b, f(a, b)) >_lpo f(a, b) whatever the order of a and b.
+            for si in &s.sub_terms {
+                if si == t
+                    || is_greater_as_per_lexicographic_path_ordering(si, t, compare_operators)
+                {
+                    return true;
                 }
             }
         }
     }
     false
 }
+
+/// The lexicographic path ordering as a [`TermOrdering`], see
+/// [`lexicographic_path_ordering`].
+#[allow(clippy::type_complexity)]
+pub struct LexicographicPathOrdering<LOS: RewritableLanguageOperatorSymbol> {
+    compare_operators: Box<dyn Fn(&LOS, &LOS) -> Ordering>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> LexicographicPathOrdering<LOS> {
+    /// `compare_operators` must be a total order on operator symbols.
+    pub fn new(compare_operators: impl Fn(&LOS, &LOS) -> Ordering + 'static) -> Self {
+        Self {
+            compare_operators: Box::new(compare_operators),
+        }
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> TermOrdering<LOS> for LexicographicPathOrdering<LOS> {
+    fn compare_terms(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> Ordering {
+        lexicographic_path_ordering(s, t, self.compare_operators.as_ref())
+    }
+
+    fn is_greater(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> bool {
+        is_greater_as_per_lexicographic_path_ordering(s, t, self.compare_operators.as_ref())
+    }
+}
@@ -14,5 +14,10 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+pub mod acrpo;
 pub mod assoc;
+pub mod kbo;
 pub mod lpo;
+pub mod ordering;
+pub mod precedence;
+pub mod rpo;
@@ -0,0 +1,61 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::cmp::Ordering;
+
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol};
+
+/// A total order on ground terms, e.g. a simplification ordering such as
+/// [`LexicographicPathOrdering`](super::lpo::LexicographicPathOrdering),
+/// [`KnuthBendixOrdering`](super::kbo::KnuthBendixOrdering),
+/// [`RecursivePathOrdering`](super::rpo::RecursivePathOrdering) or
+/// [`ACRecursivePathOrdering`](super::acrpo::ACRecursivePathOrdering).
+///
+/// The reordering rules, such as
+/// [`CommuteReorderRule`](crate::rules::primitives::reorder_pc::CommuteReorderRule),
+/// accept any implementation in place of their default LPO.
+pub trait TermOrdering<LOS: RewritableLanguageOperatorSymbol> {
+    /// Returns `Equal` iff `s` and `t` are equivalent under the ordering;
+    /// for most orderings, iff `s == t`.
+    fn compare_terms(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> Ordering;
+
+    /// Returns `true` iff `s` is greater than `t`.
+    fn is_greater(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> bool {
+        self.compare_terms(s, t) == Ordering::Greater
+    }
+}
+
+/// Orders terms by a measure, e.g. one of the
+/// [built-in metrics](crate::metrics::builtin): `s` is greater than `t` iff
+/// its measure is greater, and terms of equal measure are equivalent.
+#[allow(clippy::type_complexity)]
+pub struct MeasureOrdering<LOS: RewritableLanguageOperatorSymbol> {
+    measure: Box<dyn Fn(&LanguageTerm<LOS>) -> usize>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> MeasureOrdering<LOS> {
+    pub fn new(measure: impl Fn(&LanguageTerm<LOS>) -> usize + 'static) -> Self {
+        Self {
+            measure: Box::new(measure),
+        }
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> TermOrdering<LOS> for MeasureOrdering<LOS> {
+    fn compare_terms(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> Ordering {
+        (self.measure)(s).cmp(&(self.measure)(t))
+    }
+}
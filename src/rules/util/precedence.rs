@@ -0,0 +1,430 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::rules::util::kbo::{KnuthBendixAdmissibilityError, KnuthBendixOrdering};
+use crate::rules::util::lpo::LexicographicPathOrdering;
+use crate::rules::util::ordering::TermOrdering;
+use crate::rules::util::rpo::{OperatorStatus, RecursivePathOrdering};
+use crate::term::syntax::{LanguageOperatorArity, LanguageTerm, RewritableLanguageOperatorSymbol};
+
+/// Why [`PrecedenceInference::infer`] found no precedence.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PrecedenceInferenceError<LOS: RewritableLanguageOperatorSymbol> {
+    /// No precedence orients all these pairs (given by their indices in the
+    /// input), while one orients the pairs of any strict subset of them.
+    ConflictingPairs(Vec<usize>),
+    /// The search made more than the allowed number of precedence decisions.
+    SearchLimitReached,
+    /// The weights given to [`PrecedenceInference::for_knuth_bendix_ordering`]
+    /// are admissible under no precedence.
+    InadmissibleWeights(KnuthBendixAdmissibilityError<LOS>),
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> fmt::Display for PrecedenceInferenceError<LOS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrecedenceInferenceError::ConflictingPairs(indices) => {
+                write!(f, "no precedence orients the pairs {:?}", indices)
+            }
+            PrecedenceInferenceError::SearchLimitReached => {
+                write!(f, "precedence search limit reached")
+            }
+            PrecedenceInferenceError::InadmissibleWeights(error) => {
+                write!(f, "inadmissible weights: {}", error)
+            }
+        }
+    }
+}
+
+/// A total order on the operator symbols of a signature and of a set of
+/// rewrite pairs, as found by [`PrecedenceInference::infer`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InferredPrecedence<LOS: RewritableLanguageOperatorSymbol> {
+    ranking: Vec<LOS>,
+    /// The index of each symbol in `ranking`.
+    ranks: HashMap<LOS, usize>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> InferredPrecedence<LOS> {
+    fn from_ranking(ranking: Vec<LOS>) -> Self {
+        let ranks = ranking
+            .iter()
+            .enumerate()
+            .map(|(rank, op)| (op.clone(), rank))
+            .collect();
+        Self { ranking, ranks }
+    }
+
+    /// The symbols, greatest first.
+    pub fn get_ranking(&self) -> &[LOS] {
+        &self.ranking
+    }
+
+    /// The index of `op` in [`get_ranking`](Self::get_ranking), `0` for the
+    /// greatest symbol, or `None` if `op` is not ranked.
+    pub fn get_rank(&self, op: &LOS) -> Option<usize> {
+        self.ranks.get(op).copied()
+    }
+
+    /// Compares two symbols under this precedence.
+    ///
+    /// Symbols that are not ranked are below every ranked symbol and
+    /// `Equal` to each other, so that orderings built on this precedence are
+    /// only total on terms over the ranked symbols: pass every symbol that
+    /// the ordering may meet to [`PrecedenceInference::infer`].
+    pub fn compare_operators(&self, a: &LOS, b: &LOS) -> Ordering {
+        match (self.get_rank(a), self.get_rank(b)) {
+            (Some(a), Some(b)) => b.cmp(&a),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    /// This precedence as a `compare_operators` function, to pass e.g. to
+    /// [`LexicographicPathOrdering::new`].
+    pub fn into_compare_operators(self) -> impl Fn(&LOS, &LOS) -> Ordering + 'static {
+        move |a, b| self.compare_operators(a, b)
+    }
+}
+
+/// A partial precedence being extended by the search, queried through the
+/// `compare_operators` function handed to the ordering.
+struct PartialPrecedence<LOS: RewritableLanguageOperatorSymbol> {
+    /// Transitively closed set of `(greater, smaller)` symbol pairs.
+    greater: RefCell<HashSet<(LOS, LOS)>>,
+    /// The first pair of symbols the ordering compared without the partial
+    /// precedence deciding it, in the last evaluation.
+    undecided: RefCell<Option<(LOS, LOS)>>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> PartialPrecedence<LOS> {
+    fn compare(&self, a: &LOS, b: &LOS) -> Ordering {
+        if a == b {
+            return Ordering::Equal;
+        }
+        let greater = self.greater.borrow();
+        if greater.contains(&(a.clone(), b.clone())) {
+            Ordering::Greater
+        } else if greater.contains(&(b.clone(), a.clone())) {
+            Ordering::Less
+        } else {
+            self.undecided
+                .borrow_mut()
+                .get_or_insert_with(|| (a.clone(), b.clone()));
+            // arbitrary, and safe for every ordering: the result is discarded
+            Ordering::Less
+        }
+    }
+
+    /// Restarts from the `required` pairs, skipping those that contradict
+    /// earlier ones.
+    fn reset(&self, required: &[(LOS, LOS)]) {
+        self.greater.borrow_mut().clear();
+        for (a, b) in required {
+            self.try_add(a, b);
+        }
+    }
+
+    /// Adds `a > b` and what follows by transitivity; returns `false`, leaving
+    /// the precedence unchanged, if `b > a` already holds.
+    fn try_add(&self, a: &LOS, b: &LOS) -> bool {
+        let mut greater = self.greater.borrow_mut();
+        if greater.contains(&(b.clone(), a.clone())) {
+            return false;
+        }
+        let mut above_a = vec![a.clone()];
+        let mut below_b = vec![b.clone()];
+        for (x, y) in greater.iter() {
+            if y == a {
+                above_a.push(x.clone());
+            }
+            if x == b {
+                below_b.push(y.clone());
+            }
+        }
+        for x in &above_a {
+            for y in &below_b {
+                greater.insert((x.clone(), y.clone()));
+            }
+        }
+        true
+    }
+}
+
+/// Searches for a precedence (a total order on operator symbols) under which
+/// a term ordering orients every given rewrite pair `(lhs, rhs)` as
+/// decreasing, i.e. `lhs > rhs`.
+///
+/// The ordering is built from the precedence by the function given to
+/// [`Self::new`], or by one of the `for_*` constructors.
+///
+/// # Search
+///
+/// The precedence is built lazily: the pairs are evaluated under a partial
+/// precedence, and whenever the ordering compares two symbols that it does
+/// not order yet, the search branches on both ways to order them.  A partial
+/// precedence under which every pair is decreasing is then extended to a
+/// total one.  This is exponential in the worst case, see
+/// [`Self::with_max_decisions`].
+///
+/// When no precedence exists, the pairs are dropped one by one as long as the
+/// remaining ones still conflict, which yields a minimal conflicting subset.
+#[allow(clippy::type_complexity)]
+pub struct PrecedenceInference<LOS: RewritableLanguageOperatorSymbol> {
+    make_ordering: Box<dyn Fn(Box<dyn Fn(&LOS, &LOS) -> Ordering>) -> Box<dyn TermOrdering<LOS>>>,
+    max_decisions: Option<usize>,
+    /// The weights of the Knuth–Bendix ordering, whose admissibility
+    /// constrains the precedence.
+    knuth_bendix_weight: Option<Rc<dyn Fn(&LOS) -> usize>>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol + 'static> PrecedenceInference<LOS> {
+    /// `make_ordering` builds the term ordering from a `compare_operators`
+    /// function.
+    pub fn new(
+        make_ordering: impl Fn(Box<dyn Fn(&LOS, &LOS) -> Ordering>) -> Box<dyn TermOrdering<LOS>>
+            + 'static,
+    ) -> Self {
+        Self {
+            make_ordering: Box::new(make_ordering),
+            max_decisions: None,
+            knuth_bendix_weight: None,
+        }
+    }
+
+    /// Searches for a precedence for the [`LexicographicPathOrdering`].
+    pub fn for_lexicographic_path_ordering() -> Self {
+        Self::new(|compare_operators| Box::new(LexicographicPathOrdering::new(compare_operators)))
+    }
+
+    /// Searches for a precedence for the [`RecursivePathOrdering`] with the
+    /// given operator statuses.
+    pub fn for_recursive_path_ordering(
+        get_status: impl Fn(&LOS) -> OperatorStatus + 'static,
+    ) -> Self {
+        let get_status = Rc::new(get_status);
+        Self::new(move |compare_operators| {
+            let get_status = get_status.clone();
+            Box::new(
+                RecursivePathOrdering::new(compare_operators)
+                    .with_operator_status(move |op| get_status(op)),
+            )
+        })
+    }
+
+    /// Searches for a precedence for the [`KnuthBendixOrdering`] with the
+    /// given operator weights, under which they are admissible (see
+    /// [`KnuthBendixOrdering::check_admissibility`]): a unary or variadic
+    /// symbol of weight 0 is ranked above every other symbol.
+    pub fn for_knuth_bendix_ordering(weight: impl Fn(&LOS) -> usize + 'static) -> Self {
+        let weight: Rc<dyn Fn(&LOS) -> usize> = Rc::new(weight);
+        let mut inference = {
+            let weight = weight.clone();
+            Self::new(move |compare_operators| {
+                let weight = weight.clone();
+                Box::new(KnuthBendixOrdering::new(
+                    move |op| weight(op),
+                    compare_operators,
+                ))
+            })
+        };
+        inference.knuth_bendix_weight = Some(weight);
+        inference
+    }
+
+    /// Gives up with [`PrecedenceInferenceError::SearchLimitReached`] after
+    /// deciding the order of `max_decisions` pairs of symbols, over the whole
+    /// search (including the computation of a minimal conflicting subset).
+    pub fn with_max_decisions(mut self, max_decisions: usize) -> Self {
+        self.max_decisions = Some(max_decisions);
+        self
+    }
+
+    /// Returns a precedence under which every `lhs > rhs`, ranking every
+    /// symbol of `symbols`, typically the whole signature, and every operator
+    /// symbol that occurs in `pairs`.
+    pub fn infer(
+        &self,
+        symbols: &[LOS],
+        pairs: &[(LanguageTerm<LOS>, LanguageTerm<LOS>)],
+    ) -> Result<InferredPrecedence<LOS>, PrecedenceInferenceError<LOS>> {
+        let precedence = Rc::new(PartialPrecedence {
+            greater: RefCell::new(HashSet::new()),
+            undecided: RefCell::new(None),
+        });
+        let ordering = {
+            let precedence = precedence.clone();
+            (self.make_ordering)(Box::new(move |a, b| precedence.compare(a, b)))
+        };
+        // every symbol to rank, in order of first occurrence
+        let symbols = extend_to_total_order(symbols, pairs, &HashSet::new());
+        let required = self.get_required_precedence(&symbols);
+        precedence.reset(&required);
+        if let Some(weight) = &self.knuth_bendix_weight {
+            // Every precedence found extends the required pairs, so it is
+            // admissible iff their own extension is.
+            let weight = weight.clone();
+            let seeded = InferredPrecedence::from_ranking(extend_to_total_order(
+                &symbols,
+                &[],
+                &precedence.greater.borrow(),
+            ));
+            KnuthBendixOrdering::new(
+                move |op| weight(op),
+                move |a, b| seeded.compare_operators(a, b),
+            )
+            .check_admissibility(&symbols)
+            .map_err(PrecedenceInferenceError::InadmissibleWeights)?;
+        }
+        let mut decisions = 0;
+        let all: Vec<usize> = (0..pairs.len()).collect();
+        if self.solve(pairs, &all, ordering.as_ref(), &precedence, &mut decisions)? {
+            return Ok(InferredPrecedence::from_ranking(extend_to_total_order(
+                &symbols,
+                pairs,
+                &precedence.greater.borrow(),
+            )));
+        }
+        // shrink to a minimal conflicting subset
+        let mut conflicting = all;
+        let mut i = 0;
+        while i < conflicting.len() {
+            let mut without: Vec<usize> = conflicting.clone();
+            without.remove(i);
+            precedence.reset(&required);
+            if self.solve(
+                pairs,
+                &without,
+                ordering.as_ref(),
+                &precedence,
+                &mut decisions,
+            )? {
+                i += 1;
+            } else {
+                conflicting = without;
+            }
+        }
+        Err(PrecedenceInferenceError::ConflictingPairs(conflicting))
+    }
+
+    /// The pairs `(a, b)` of `symbols` such that `a > b` in any precedence
+    /// under which the Knuth–Bendix weights are admissible.
+    fn get_required_precedence(&self, symbols: &[LOS]) -> Vec<(LOS, LOS)> {
+        let Some(weight) = &self.knuth_bendix_weight else {
+            return vec![];
+        };
+        let mut required = Vec::new();
+        for symbol in symbols {
+            let unary = matches!(
+                symbol.arity(),
+                LanguageOperatorArity::Fixed(1) | LanguageOperatorArity::Variadic
+            );
+            if unary && weight(symbol) == 0 {
+                for other in symbols.iter().filter(|other| *other != symbol) {
+                    required.push((symbol.clone(), other.clone()));
+                }
+            }
+        }
+        required
+    }
+
+    /// Extends `precedence` so that the pairs at `indices` are decreasing;
+    /// returns `false`, leaving `precedence` unchanged, if that is impossible.
+    fn solve(
+        &self,
+        pairs: &[(LanguageTerm<LOS>, LanguageTerm<LOS>)],
+        indices: &[usize],
+        ordering: &dyn TermOrdering<LOS>,
+        precedence: &PartialPrecedence<LOS>,
+        decisions: &mut usize,
+    ) -> Result<bool, PrecedenceInferenceError<LOS>> {
+        for index in indices {
+            let (lhs, rhs) = &pairs[*index];
+            *precedence.undecided.borrow_mut() = None;
+            let is_decreasing = ordering.is_greater(lhs, rhs);
+            let undecided = precedence.undecided.borrow_mut().take();
+            match undecided {
+                None if is_decreasing => continue,
+                None => return Ok(false),
+                Some((a, b)) => {
+                    for (x, y) in [(&a, &b), (&b, &a)] {
+                        *decisions += 1;
+                        if self.max_decisions.is_some_and(|max| *decisions > max) {
+                            return Err(PrecedenceInferenceError::SearchLimitReached);
+                        }
+                        let saved = precedence.greater.borrow().clone();
+                        if precedence.try_add(x, y)
+                            && self.solve(pairs, indices, ordering, precedence, decisions)?
+                        {
+                            return Ok(true);
+                        }
+                        *precedence.greater.borrow_mut() = saved;
+                    }
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Ranks `symbols` and the symbols of `pairs`, greatest first, consistently
+/// with the partial precedence `greater`, and otherwise in order of first
+/// occurrence.
+fn extend_to_total_order<LOS: RewritableLanguageOperatorSymbol>(
+    symbols: &[LOS],
+    pairs: &[(LanguageTerm<LOS>, LanguageTerm<LOS>)],
+    greater: &HashSet<(LOS, LOS)>,
+) -> Vec<LOS> {
+    let mut remaining: Vec<LOS> = Vec::new();
+    for symbol in symbols {
+        if !remaining.contains(symbol) {
+            remaining.push(symbol.clone());
+        }
+    }
+    let mut stack: Vec<&LanguageTerm<LOS>> = pairs
+        .iter()
+        .rev()
+        .flat_map(|(lhs, rhs)| [rhs, lhs])
+        .collect();
+    while let Some(term) = stack.pop() {
+        if !remaining.contains(&term.operator) {
+            remaining.push(term.operator.clone());
+        }
+        stack.extend(term.sub_terms.iter().rev());
+    }
+    let mut ranking = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        // the partial precedence is a strict order, so a maximal symbol exists
+        let maximal = remaining
+            .iter()
+            .position(|x| {
+                !remaining
+                    .iter()
+                    .any(|y| greater.contains(&(y.clone(), x.clone())))
+            })
+            .unwrap();
+        ranking.push(remaining.remove(maximal));
+    }
+    ranking
+}
@@ -0,0 +1,287 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::cmp::Ordering;
+
+use crate::rules::util::ordering::TermOrdering;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol};
+
+/// How the recursive path ordering compares the sub-terms of two terms that
+/// have the same root operator.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum OperatorStatus {
+    /// Left to right, as in the lexicographic path ordering.
+    Lexicographic,
+    /// As multisets: the order of the sub-terms is irrelevant, so that
+    /// `op(a, b)` and `op(b, a)` are equivalent.
+    Multiset,
+}
+
+/// Returns `true` iff `s ~_rpo t`, i.e. `s` and `t` are equal up to
+/// permutations of the sub-terms of [`OperatorStatus::Multiset`] operators.
+pub fn are_equivalent_as_per_recursive_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    get_status: &dyn Fn(&LOS) -> OperatorStatus,
+) -> bool {
+    are_equivalent_as_per_path_ordering(s, t, get_status, &direct_sub_terms)
+}
+
+/// Returns the [`Ordering`] between two terms under the recursive path
+/// ordering induced by the given total order on operator symbols and the
+/// given operator statuses.
+///
+/// Returns `Equal` iff `s ~_rpo t`
+/// (see [`are_equivalent_as_per_recursive_path_ordering`]), `Greater` iff
+/// `s >_rpo t` and `Less` otherwise (asserting in debug builds that
+/// `t >_rpo s` holds).
+pub fn recursive_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+    get_status: &dyn Fn(&LOS) -> OperatorStatus,
+) -> Ordering {
+    path_ordering(s, t, compare_operators, get_status, &direct_sub_terms)
+}
+
+/// Returns `true` iff `s >_rpo t` under the recursive path ordering (RPO)
+/// with status induced by the given total order on operator symbols.
+///
+/// # Definition
+///
+/// On `s = f(s₁,…,sₙ)` and `t = g(t₁,…,tₘ)` (Baader & Nipkow, *Term
+/// Rewriting and All That*, §5.4), `s >_rpo t` iff:
+///
+/// - some child `sᵢ` of `s` satisfies `sᵢ ~_rpo t` or `sᵢ >_rpo t`, or
+/// - `f > g` and `s >_rpo tⱼ` for every child `tⱼ` of `t`, or
+/// - `f = g` and:
+///   - for [`OperatorStatus::Lexicographic`], `(s₁,…,sₙ)` is greater than
+///     `(t₁,…,tₘ)` in the lexicographic extension of `>_rpo` (modulo
+///     `~_rpo`) and `s >_rpo tⱼ` for every child `tⱼ` of `t`;
+///   - for [`OperatorStatus::Multiset`], `{s₁,…,sₙ}` is greater than
+///     `{t₁,…,tₘ}` in the multiset extension of `>_rpo` (modulo `~_rpo`).
+///
+/// With every operator of lexicographic status, this coincides with
+/// [`is_greater_as_per_lexicographic_path_ordering`](super::lpo::is_greater_as_per_lexicographic_path_ordering).
+///
+/// # Properties
+///
+/// When `compare_operators` is a total order, the RPO is a simplification
+/// ordering, total on ground terms up to `~_rpo`.  Being insensitive to the
+/// order of the sub-terms of multiset-status operators, it is compatible with
+/// their commutativity.
+pub fn is_greater_as_per_recursive_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+    get_status: &dyn Fn(&LOS) -> OperatorStatus,
+) -> bool {
+    is_greater_as_per_path_ordering(s, t, compare_operators, get_status, &direct_sub_terms)
+}
+
+fn direct_sub_terms<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+) -> Vec<LanguageTerm<LOS>> {
+    term.sub_terms.clone()
+}
+
+// The functions below implement the path ordering with status over the
+// sub-terms returned by `get_sub_terms`, which are either the actual
+// sub-terms (RPO) or the flattened associative chains (AC-RPO).
+
+pub(crate) fn are_equivalent_as_per_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    get_status: &dyn Fn(&LOS) -> OperatorStatus,
+    get_sub_terms: &dyn Fn(&LanguageTerm<LOS>) -> Vec<LanguageTerm<LOS>>,
+) -> bool {
+    if s == t {
+        return true;
+    }
+    if s.operator != t.operator {
+        return false;
+    }
+    let s_sub_terms = get_sub_terms(s);
+    let t_sub_terms = get_sub_terms(t);
+    if s_sub_terms.len() != t_sub_terms.len() {
+        return false;
+    }
+    match get_status(&s.operator) {
+        OperatorStatus::Lexicographic => s_sub_terms
+            .iter()
+            .zip(t_sub_terms.iter())
+            .all(|(si, ti)| are_equivalent_as_per_path_ordering(si, ti, get_status, get_sub_terms)),
+        OperatorStatus::Multiset => {
+            let (remaining_s, remaining_t) =
+                remove_equivalent_sub_terms(s_sub_terms, t_sub_terms, get_status, get_sub_terms);
+            remaining_s.is_empty() && remaining_t.is_empty()
+        }
+    }
+}
+
+pub(crate) fn path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+    get_status: &dyn Fn(&LOS) -> OperatorStatus,
+    get_sub_terms: &dyn Fn(&LanguageTerm<LOS>) -> Vec<LanguageTerm<LOS>>,
+) -> Ordering {
+    if are_equivalent_as_per_path_ordering(s, t, get_status, get_sub_terms) {
+        Ordering::Equal
+    } else if is_greater_as_per_path_ordering(s, t, compare_operators, get_status, get_sub_terms) {
+        Ordering::Greater
+    } else {
+        debug_assert!(is_greater_as_per_path_ordering(
+            t,
+            s,
+            compare_operators,
+            get_status,
+            get_sub_terms
+        ));
+        Ordering::Less
+    }
+}
+
+pub(crate) fn is_greater_as_per_path_ordering<LOS: RewritableLanguageOperatorSymbol>(
+    s: &LanguageTerm<LOS>,
+    t: &LanguageTerm<LOS>,
+    compare_operators: &dyn Fn(&LOS, &LOS) -> Ordering,
+    get_status: &dyn Fn(&LOS) -> OperatorStatus,
+    get_sub_terms: &dyn Fn(&LanguageTerm<LOS>) -> Vec<LanguageTerm<LOS>>,
+) -> bool {
+    let is_greater = |x: &LanguageTerm<LOS>, y: &LanguageTerm<LOS>| {
+        is_greater_as_per_path_ordering(x, y, compare_operators, get_status, get_sub_terms)
+    };
+    let s_sub_terms = get_sub_terms(s);
+    // some child of s dominates t
+    if s_sub_terms.iter().any(|si| {
+        are_equivalent_as_per_path_ordering(si, t, get_status, get_sub_terms) || is_greater(si, t)
+    }) {
+        return true;
+    }
+    let t_sub_terms = get_sub_terms(t);
+    match compare_operators(&s.operator, &t.operator) {
+        Ordering::Greater => t_sub_terms.iter().all(|tj| is_greater(s, tj)),
+        Ordering::Less => false,
+        Ordering::Equal => match get_status(&s.operator) {
+            OperatorStatus::Lexicographic => {
+                for (i, (si, ti)) in s_sub_terms.iter().zip(t_sub_terms.iter()).enumerate() {
+                    if are_equivalent_as_per_path_ordering(si, ti, get_status, get_sub_terms) {
+                        continue;
+                    }
+                    // i is the critical index; s must also dominate the remaining children of t
+                    return is_greater(si, ti)
+                        && t_sub_terms[(i + 1)..].iter().all(|tj| is_greater(s, tj));
+                }
+                // all shared positions are equivalent: the longer tuple is greater
+                s_sub_terms.len() > t_sub_terms.len()
+            }
+            OperatorStatus::Multiset => {
+                let (remaining_s, remaining_t) = remove_equivalent_sub_terms(
+                    s_sub_terms,
+                    t_sub_terms,
+                    get_status,
+                    get_sub_terms,
+                );
+                !remaining_s.is_empty()
+                    && remaining_t
+                        .iter()
+                        .all(|tj| remaining_s.iter().any(|si| is_greater(si, tj)))
+            }
+        },
+    }
+}
+
+/// Removes from both lists the sub-terms they have in common modulo the
+/// equivalence, so that greedy matching is enough.
+#[allow(clippy::type_complexity)]
+fn remove_equivalent_sub_terms<LOS: RewritableLanguageOperatorSymbol>(
+    s_sub_terms: Vec<LanguageTerm<LOS>>,
+    mut t_sub_terms: Vec<LanguageTerm<LOS>>,
+    get_status: &dyn Fn(&LOS) -> OperatorStatus,
+    get_sub_terms: &dyn Fn(&LanguageTerm<LOS>) -> Vec<LanguageTerm<LOS>>,
+) -> (Vec<LanguageTerm<LOS>>, Vec<LanguageTerm<LOS>>) {
+    let mut remaining_s = Vec::new();
+    for si in s_sub_terms {
+        let matched = t_sub_terms
+            .iter()
+            .position(|tj| are_equivalent_as_per_path_ordering(&si, tj, get_status, get_sub_terms));
+        match matched {
+            Some(j) => {
+                t_sub_terms.swap_remove(j);
+            }
+            None => remaining_s.push(si),
+        }
+    }
+    (remaining_s, t_sub_terms)
+}
+
+/// The recursive path ordering as a [`TermOrdering`], see
+/// [`recursive_path_ordering`].
+///
+/// Every operator has [`OperatorStatus::Lexicographic`] status unless
+/// specified otherwise with [`Self::with_operator_status`].
+#[allow(clippy::type_complexity)]
+pub struct RecursivePathOrdering<LOS: RewritableLanguageOperatorSymbol> {
+    compare_operators: Box<dyn Fn(&LOS, &LOS) -> Ordering>,
+    get_status: Box<dyn Fn(&LOS) -> OperatorStatus>,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> RecursivePathOrdering<LOS> {
+    /// `compare_operators` must be a total order on operator symbols.
+    pub fn new(compare_operators: impl Fn(&LOS, &LOS) -> Ordering + 'static) -> Self {
+        Self {
+            compare_operators: Box::new(compare_operators),
+            get_status: Box::new(|_| OperatorStatus::Lexicographic),
+        }
+    }
+
+    /// Sets the status of each operator, typically
+    /// [`OperatorStatus::Multiset`] for commutative ones.
+    pub fn with_operator_status(
+        mut self,
+        get_status: impl Fn(&LOS) -> OperatorStatus + 'static,
+    ) -> Self {
+        self.get_status = Box::new(get_status);
+        self
+    }
+
+    /// Returns `true` iff `s ~_rpo t`, see
+    /// [`are_equivalent_as_per_recursive_path_ordering`].
+    pub fn are_equivalent(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> bool {
+        are_equivalent_as_per_recursive_path_ordering(s, t, self.get_status.as_ref())
+    }
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> TermOrdering<LOS> for RecursivePathOrdering<LOS> {
+    fn compare_terms(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> Ordering {
+        recursive_path_ordering(
+            s,
+            t,
+            self.compare_operators.as_ref(),
+            self.get_status.as_ref(),
+        )
+    }
+
+    fn is_greater(&self, s: &LanguageTerm<LOS>, t: &LanguageTerm<LOS>) -> bool {
+        is_greater_as_per_recursive_path_ordering(
+            s,
+            t,
+            self.compare_operators.as_ref(),
+            self.get_status.as_ref(),
+        )
+    }
+}
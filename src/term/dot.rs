@@ -0,0 +1,155 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+
+use crate::position::PositionInLanguageTerm;
+use crate::term::syntax::{LanguageTerm, RewritableLanguageOperatorSymbol};
+
+/// How a term is laid out by [`term_to_dot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TermDotLayout {
+    /// One node per position: shared sub-terms are drawn as many times as
+    /// they occur.  The node count is
+    /// [`tree_size`](crate::metrics::builtin::tree_size).
+    Tree,
+    /// One node per distinct sub-term, i.e. the maximally shared DAG that
+    /// hash-consing stores.  The node count is
+    /// [`dag_size`](crate::metrics::builtin::dag_size).
+    SharedDag,
+}
+
+/// Fill colour of highlighted nodes.
+const HIGHLIGHT_FILL_COLOR: &str = "#ffd27f";
+
+/// Renders `term` as a Graphviz DOT digraph.
+///
+/// Nodes are labelled with the operator's `Display`; edges are labelled with
+/// the child index.  Nodes at the `highlighted` positions (e.g. the redex of a
+/// rewrite step) are filled; in [`TermDotLayout::SharedDag`] a shared node is
+/// filled if any of its positions is highlighted.  Positions that do not exist
+/// in `term` are ignored.
+pub fn term_to_dot<LOS: RewritableLanguageOperatorSymbol + fmt::Display>(
+    term: &LanguageTerm<LOS>,
+    layout: TermDotLayout,
+    highlighted: &[PositionInLanguageTerm],
+) -> String {
+    let mut dot = String::new();
+    writeln!(dot, "digraph term {{").unwrap();
+    writeln!(dot, "  node [shape=ellipse];").unwrap();
+    match layout {
+        TermDotLayout::Tree => {
+            let highlighted: HashSet<&PositionInLanguageTerm> = highlighted.iter().collect();
+            let mut next_id = 0;
+            write_tree_rec(
+                term,
+                &PositionInLanguageTerm::get_root_position(),
+                &highlighted,
+                &mut next_id,
+                &mut dot,
+            );
+        }
+        TermDotLayout::SharedDag => {
+            let highlighted: HashSet<&LanguageTerm<LOS>> = highlighted
+                .iter()
+                .filter_map(|pos| get_handle_at_position(term, pos))
+                .collect();
+            let mut ids = HashMap::new();
+            write_dag_rec(term, &highlighted, &mut ids, &mut dot);
+        }
+    }
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+fn write_tree_rec<LOS: RewritableLanguageOperatorSymbol + fmt::Display>(
+    term: &LanguageTerm<LOS>,
+    position: &PositionInLanguageTerm,
+    highlighted: &HashSet<&PositionInLanguageTerm>,
+    next_id: &mut usize,
+    dot: &mut String,
+) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+    write_node(dot, id, &term.operator, highlighted.contains(position));
+    for (n, sub) in term.sub_terms.iter().enumerate() {
+        let child_pos = position.get_position_of_nth_child(n);
+        let child_id = write_tree_rec(sub, &child_pos, highlighted, next_id, dot);
+        writeln!(dot, "  n{} -> n{} [label=\"{}\"];", id, child_id, n).unwrap();
+    }
+    id
+}
+
+fn write_dag_rec<LOS: RewritableLanguageOperatorSymbol + fmt::Display>(
+    term: &LanguageTerm<LOS>,
+    highlighted: &HashSet<&LanguageTerm<LOS>>,
+    ids: &mut HashMap<LanguageTerm<LOS>, usize>,
+    dot: &mut String,
+) -> usize {
+    if let Some(id) = ids.get(term) {
+        return *id;
+    }
+    let id = ids.len();
+    ids.insert(term.clone(), id);
+    write_node(dot, id, &term.operator, highlighted.contains(term));
+    for (n, sub) in term.sub_terms.iter().enumerate() {
+        let child_id = write_dag_rec(sub, highlighted, ids, dot);
+        writeln!(dot, "  n{} -> n{} [label=\"{}\"];", id, child_id, n).unwrap();
+    }
+    id
+}
+
+fn write_node<LOS: fmt::Display>(dot: &mut String, id: usize, operator: &LOS, highlighted: bool) {
+    let label = escape_dot_string(&operator.to_string());
+    if highlighted {
+        writeln!(
+            dot,
+            "  n{} [label=\"{}\", style=filled, fillcolor=\"{}\", penwidth=2];",
+            id, label, HIGHLIGHT_FILL_COLOR
+        )
+        .unwrap();
+    } else {
+        writeln!(dot, "  n{} [label=\"{}\"];", id, label).unwrap();
+    }
+}
+
+/// Returns the hash-consed handle of the sub-term at `position`.
+fn get_handle_at_position<'a, LOS: RewritableLanguageOperatorSymbol>(
+    term: &'a LanguageTerm<LOS>,
+    position: &PositionInLanguageTerm,
+) -> Option<&'a LanguageTerm<LOS>> {
+    let mut current = term;
+    for n in position.get_absolute_coordinates_from_root() {
+        current = current.sub_terms.get(*n)?;
+    }
+    Some(current)
+}
+
+/// Escapes `"`, `\` and line breaks so that `s` can be used inside a
+/// double-quoted DOT string.
+pub(crate) fn escape_dot_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
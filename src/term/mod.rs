@@ -15,4 +15,7 @@ limitations under the License.
 */
 
 pub mod conversion;
+pub mod dot;
+pub mod parser;
+pub mod pattern;
 pub mod syntax;
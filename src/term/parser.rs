@@ -0,0 +1,272 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::term::syntax::{
+    LanguageOperatorArity, LanguageTerm, LanguageTermNode, RewritableLanguageOperatorSymbol,
+    TermFactory,
+};
+
+/// Turns the textual token of an operator back into an operator symbol.
+///
+/// This is the user-supplied half of [`parse_term`]: the parser takes care of
+/// parentheses, commas and whitespace, and hands every operator token (a maximal
+/// run of characters other than `(`, `)`, `,` and whitespace) to this trait.
+///
+/// To parse what [`LanguageTermNode`]'s `Display` prints, accept the tokens
+/// produced by the operator's own `Display`.
+pub trait LanguageOperatorTokenParser<LOS: RewritableLanguageOperatorSymbol> {
+    /// Returns the operator denoted by `token`, or `None` if it is unknown.
+    fn parse_operator_token(&self, token: &str) -> Option<LOS>;
+}
+
+/// What went wrong while parsing a term.  See [`TermParsingError`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TermParsingErrorKind {
+    /// The input ended while a term or a closing parenthesis was expected.
+    UnexpectedEndOfInput,
+    /// A character that cannot appear at this point of the input.
+    UnexpectedCharacter(char),
+    /// The token was rejected by the [`LanguageOperatorTokenParser`].
+    UnknownOperator(String),
+    /// The operator was given a number of arguments that contradicts its
+    /// [`arity`](RewritableLanguageOperatorSymbol::arity).
+    ArityMismatch {
+        operator: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// Error returned by [`parse_term`], located at a 1-based line and column
+/// (columns count characters, not bytes).
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TermParsingError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: TermParsingErrorKind,
+}
+
+impl fmt::Display for TermParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            TermParsingErrorKind::UnexpectedEndOfInput => write!(f, "unexpected end of input"),
+            TermParsingErrorKind::UnexpectedCharacter(c) => {
+                write!(f, "unexpected character '{}'", c)
+            }
+            TermParsingErrorKind::UnknownOperator(token) => {
+                write!(f, "unknown operator '{}'", token)
+            }
+            TermParsingErrorKind::ArityMismatch {
+                operator,
+                expected,
+                found,
+            } => write!(
+                f,
+                "operator '{}' expects {} argument(s) but was given {}",
+                operator, expected, found
+            ),
+        }
+    }
+}
+
+/// Parses a term written as `op(t1, …, tn)`, the format printed by
+/// [`LanguageTermNode`]'s `Display`, and interns it in `factory`.
+///
+/// Leaves are written as a bare operator token (`a`); `a()` is also accepted.
+/// Whitespace (including newlines) is allowed between tokens.
+/// The number of arguments of every operator is checked against its
+/// [`arity`](RewritableLanguageOperatorSymbol::arity).
+///
+/// # Example
+///
+/// ```rust
+/// use simple_term_rewriter::term::parser::{parse_term, LanguageOperatorTokenParser};
+/// use simple_term_rewriter::term::syntax::{TermFactory, RewritableLanguageOperatorSymbol};
+/// use hashconsing::HConsign;
+///
+/// #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// enum Op { Not, True }
+/// impl RewritableLanguageOperatorSymbol for Op {
+///     fn arity(&self) -> simple_term_rewriter::term::syntax::LanguageOperatorArity {
+///         use simple_term_rewriter::term::syntax::LanguageOperatorArity::Fixed;
+///         match self { Op::Not => Fixed(1), _ => Fixed(0) }
+///     }
+/// }
+///
+/// struct OpParser;
+/// impl LanguageOperatorTokenParser<Op> for OpParser {
+///     fn parse_operator_token(&self, token: &str) -> Option<Op> {
+///         match token { "not" => Some(Op::Not), "true" => Some(Op::True), _ => None }
+///     }
+/// }
+///
+/// let mut f: TermFactory<Op> = HConsign::empty();
+/// let t = parse_term("not(not(true))", &OpParser, &mut f).unwrap();
+/// assert_eq!(t.operator, Op::Not);
+/// assert!(parse_term("not(true, true)", &OpParser, &mut f).is_err());
+/// ```
+pub fn parse_term<LOS: RewritableLanguageOperatorSymbol>(
+    input: &str,
+    parser: &dyn LanguageOperatorTokenParser<LOS>,
+    factory: &mut TermFactory<LOS>,
+) -> Result<LanguageTerm<LOS>, TermParsingError> {
+    let mut cursor = TextCursor::new(input);
+    let term = parse_term_rec(&mut cursor, parser, factory)?;
+    cursor.skip_whitespace();
+    match cursor.peek() {
+        None => Ok(term),
+        Some(c) => Err(cursor.error(TermParsingErrorKind::UnexpectedCharacter(c))),
+    }
+}
+
+fn parse_term_rec<LOS: RewritableLanguageOperatorSymbol>(
+    cursor: &mut TextCursor,
+    parser: &dyn LanguageOperatorTokenParser<LOS>,
+    factory: &mut TermFactory<LOS>,
+) -> Result<LanguageTerm<LOS>, TermParsingError> {
+    cursor.skip_whitespace();
+    let (line, column) = cursor.location();
+    let token = cursor.take_token();
+    if token.is_empty() {
+        return Err(match cursor.peek() {
+            None => cursor.error(TermParsingErrorKind::UnexpectedEndOfInput),
+            Some(c) => cursor.error(TermParsingErrorKind::UnexpectedCharacter(c)),
+        });
+    }
+    let operator = match parser.parse_operator_token(&token) {
+        Some(op) => op,
+        None => {
+            return Err(TermParsingError {
+                line,
+                column,
+                kind: TermParsingErrorKind::UnknownOperator(token),
+            })
+        }
+    };
+
+    let mut sub_terms = vec![];
+    cursor.skip_whitespace();
+    if cursor.peek() == Some('(') {
+        cursor.next();
+        cursor.skip_whitespace();
+        if cursor.peek() == Some(')') {
+            cursor.next();
+        } else {
+            loop {
+                sub_terms.push(parse_term_rec(cursor, parser, factory)?);
+                cursor.skip_whitespace();
+                match cursor.peek() {
+                    Some(',') => {
+                        cursor.next();
+                    }
+                    Some(')') => {
+                        cursor.next();
+                        break;
+                    }
+                    Some(c) => {
+                        return Err(cursor.error(TermParsingErrorKind::UnexpectedCharacter(c)))
+                    }
+                    None => return Err(cursor.error(TermParsingErrorKind::UnexpectedEndOfInput)),
+                }
+            }
+        }
+    }
+
+    if let LanguageOperatorArity::Fixed(expected) = operator.arity() {
+        if expected != sub_terms.len() {
+            return Err(TermParsingError {
+                line,
+                column,
+                kind: TermParsingErrorKind::ArityMismatch {
+                    operator: token,
+                    expected,
+                    found: sub_terms.len(),
+                },
+            });
+        }
+    }
+    Ok(LanguageTermNode::build(operator, sub_terms, factory))
+}
+
+/// A character cursor over some input text that keeps track of the current
+/// 1-based line and column.
+pub(crate) struct TextCursor<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> TextCursor<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub(crate) fn location(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+
+    pub(crate) fn error(&self, kind: TermParsingErrorKind) -> TermParsingError {
+        TermParsingError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+
+    pub(crate) fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    pub(crate) fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    pub(crate) fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    /// Consumes a maximal run of characters that are neither whitespace nor
+    /// one of `(`, `)` and `,`.
+    pub(crate) fn take_token(&mut self) -> String {
+        let mut token = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' || c == ',' {
+                break;
+            }
+            token.push(c);
+            self.next();
+        }
+        token
+    }
+}
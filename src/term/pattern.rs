@@ -0,0 +1,206 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::term::syntax::{
+    LanguageTerm, LanguageTermNode, RewritableLanguageOperatorSymbol, TermFactory,
+};
+
+/// A mapping from variable names to the ground terms they are bound to.
+pub type Substitution<LOS> = HashMap<String, LanguageTerm<LOS>>;
+
+/// A first-order pattern: a term tree whose leaves may be named variables.
+///
+/// Patterns are matched syntactically against ground [`LanguageTerm`]s via
+/// [`match_term`](Self::match_term), and turned back into ground terms via
+/// [`instantiate`](Self::instantiate).
+///
+/// A variable may occur several times in a pattern (non-linear pattern); all
+/// its occurrences must then be bound to the same term for the match to succeed.
+/// Thanks to hash-consing, this check is O(1).
+///
+/// # Example
+///
+/// ```rust
+/// use simple_term_rewriter::term::pattern::Pattern;
+/// use simple_term_rewriter::term::syntax::{LanguageTermNode, TermFactory, RewritableLanguageOperatorSymbol};
+/// use hashconsing::HConsign;
+///
+/// #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// enum Op { Not, True }
+/// impl RewritableLanguageOperatorSymbol for Op {
+///     fn arity(&self) -> simple_term_rewriter::term::syntax::LanguageOperatorArity {
+///         use simple_term_rewriter::term::syntax::LanguageOperatorArity::Fixed;
+///         match self { Op::Not => Fixed(1), _ => Fixed(0) }
+///     }
+/// }
+///
+/// let mut f: TermFactory<Op> = HConsign::empty();
+/// let t = LanguageTermNode::build(Op::True, vec![], &mut f);
+/// let nt = LanguageTermNode::build(Op::Not, vec![t.clone()], &mut f);
+/// let nnt = LanguageTermNode::build(Op::Not, vec![nt], &mut f);
+///
+/// // NOT(NOT(x))
+/// let p = Pattern::op(Op::Not, vec![Pattern::op(Op::Not, vec![Pattern::var("x")])]);
+/// let subst = p.match_term(&nnt).unwrap();
+/// assert_eq!(subst["x"], t);
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Pattern<LOS: RewritableLanguageOperatorSymbol> {
+    /// A named variable, matching any term.
+    Variable(String),
+    /// An operator node whose sub-patterns must match the sub-terms in order.
+    Operator(LOS, Vec<Pattern<LOS>>),
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> Pattern<LOS> {
+    /// Creates a variable pattern.
+    pub fn var(name: impl Into<String>) -> Self {
+        Pattern::Variable(name.into())
+    }
+
+    /// Creates an operator pattern with the given sub-patterns.
+    pub fn op(operator: LOS, sub_patterns: Vec<Self>) -> Self {
+        Pattern::Operator(operator, sub_patterns)
+    }
+
+    /// Creates an operator pattern without sub-patterns.
+    pub fn leaf(operator: LOS) -> Self {
+        Pattern::Operator(operator, vec![])
+    }
+
+    /// Converts a ground term into the pattern that matches exactly that term.
+    pub fn from_term(term: &LanguageTerm<LOS>) -> Self {
+        Pattern::Operator(
+            term.operator.clone(),
+            term.sub_terms.iter().map(Self::from_term).collect(),
+        )
+    }
+
+    /// Returns the set of variable names occurring in the pattern.
+    pub fn get_variables(&self) -> HashSet<&str> {
+        let mut vars = HashSet::new();
+        self.collect_variables(&mut vars);
+        vars
+    }
+
+    fn collect_variables<'a>(&'a self, vars: &mut HashSet<&'a str>) {
+        match self {
+            Pattern::Variable(name) => {
+                vars.insert(name.as_str());
+            }
+            Pattern::Operator(_, sub_patterns) => {
+                for sub in sub_patterns {
+                    sub.collect_variables(vars);
+                }
+            }
+        }
+    }
+
+    /// Syntactically matches the pattern against `term`.
+    ///
+    /// Returns the substitution `σ` such that `σ(self) = term`, or `None` if
+    /// there is no such substitution.
+    pub fn match_term(&self, term: &LanguageTerm<LOS>) -> Option<Substitution<LOS>> {
+        let mut substitution = HashMap::new();
+        if self.match_term_extending(term, &mut substitution) {
+            Some(substitution)
+        } else {
+            None
+        }
+    }
+
+    /// Matches the pattern against `term` while extending an existing
+    /// substitution.
+    ///
+    /// Variables already bound in `substitution` must be bound to the same
+    /// term for the match to succeed.  On failure `substitution` may contain
+    /// partial bindings and should be discarded.
+    pub fn match_term_extending(
+        &self,
+        term: &LanguageTerm<LOS>,
+        substitution: &mut Substitution<LOS>,
+    ) -> bool {
+        match self {
+            Pattern::Variable(name) => match substitution.get(name) {
+                Some(bound) => bound == term,
+                None => {
+                    substitution.insert(name.clone(), term.clone());
+                    true
+                }
+            },
+            Pattern::Operator(operator, sub_patterns) => {
+                *operator == term.operator
+                    && sub_patterns.len() == term.sub_terms.len()
+                    && sub_patterns
+                        .iter()
+                        .zip(term.sub_terms.iter())
+                        .all(|(p, t)| p.match_term_extending(t, substitution))
+            }
+        }
+    }
+
+    /// Builds the ground term `σ(self)` in `factory`.
+    ///
+    /// Returns `None` if the pattern contains a variable that is not bound
+    /// in `substitution`.
+    pub fn instantiate(
+        &self,
+        substitution: &Substitution<LOS>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Option<LanguageTerm<LOS>> {
+        match self {
+            Pattern::Variable(name) => substitution.get(name).cloned(),
+            Pattern::Operator(operator, sub_patterns) => {
+                let mut sub_terms = Vec::with_capacity(sub_patterns.len());
+                for sub in sub_patterns {
+                    sub_terms.push(sub.instantiate(substitution, factory)?);
+                }
+                Some(LanguageTermNode::build(
+                    operator.clone(),
+                    sub_terms,
+                    factory,
+                ))
+            }
+        }
+    }
+}
+
+/// Variables are printed with a leading `?`, operators as for
+/// [`LanguageTermNode`]: `op(?x, b)`.
+impl<LOS: RewritableLanguageOperatorSymbol + fmt::Display> fmt::Display for Pattern<LOS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Variable(name) => write!(f, "?{}", name),
+            Pattern::Operator(operator, sub_patterns) => {
+                if sub_patterns.is_empty() {
+                    write!(f, "{}", operator)
+                } else {
+                    write!(f, "{}(", operator)?;
+                    for (i, sub) in sub_patterns.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", sub)?;
+                    }
+                    write!(f, ")")
+                }
+            }
+        }
+    }
+}
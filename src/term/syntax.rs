@@ -43,6 +43,32 @@ pub trait RewritableLanguageOperatorSymbol:
     fn arity(&self) -> LanguageOperatorArity;
 }
 
+/// A node whose number of sub-terms contradicts the
+/// [`arity`](RewritableLanguageOperatorSymbol::arity) of its operator.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ArityMismatch<LOS: RewritableLanguageOperatorSymbol> {
+    pub operator: LOS,
+    pub expected: usize,
+    pub found: usize,
+}
+
+impl<LOS: RewritableLanguageOperatorSymbol> fmt::Display for ArityMismatch<LOS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "operator {:?} expects {} sub-term(s) but has {}",
+            self.operator, self.expected, self.found
+        )
+    }
+}
+
+/// An [`ArityMismatch`] found by [`validate_term`], together with its position.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct IllFormedPosition<LOS: RewritableLanguageOperatorSymbol> {
+    pub position: PositionInLanguageTerm,
+    pub mismatch: ArityMismatch<LOS>,
+}
+
 /// The internal node of a hash-consed term tree.
 ///
 /// Previously named `LanguageTerm`; renamed so the type alias below can
@@ -85,6 +111,27 @@ impl<LOS: RewritableLanguageOperatorSymbol> LanguageTermNode<LOS> {
         })
     }
 
+    /// Like [`build`](Self::build), but first checks the number of sub-terms
+    /// against the operator's [`arity`](RewritableLanguageOperatorSymbol::arity).
+    ///
+    /// Nothing is interned when the check fails.
+    pub fn try_build(
+        operator: LOS,
+        sub_terms: Vec<LanguageTerm<LOS>>,
+        factory: &mut TermFactory<LOS>,
+    ) -> Result<LanguageTerm<LOS>, ArityMismatch<LOS>> {
+        if let LanguageOperatorArity::Fixed(expected) = operator.arity() {
+            if expected != sub_terms.len() {
+                return Err(ArityMismatch {
+                    operator,
+                    expected,
+                    found: sub_terms.len(),
+                });
+            }
+        }
+        Ok(Self::build(operator, sub_terms, factory))
+    }
+
     /// Returns a reference to the sub-node at `pos`, or `None` if the path is
     /// out of bounds.
     ///
@@ -116,6 +163,49 @@ impl<LOS: RewritableLanguageOperatorSymbol> LanguageTermNode<LOS> {
     }
 }
 
+/// Checks every node of `term` against the
+/// [`arity`](RewritableLanguageOperatorSymbol::arity) of its operator.
+///
+/// Returns `Err` with one entry per ill-formed position, in pre-order.
+/// A shared sub-term is reported at each position where it occurs.
+pub fn validate_term<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+) -> Result<(), Vec<IllFormedPosition<LOS>>> {
+    let mut ill_formed = vec![];
+    validate_term_rec(
+        term,
+        &PositionInLanguageTerm::get_root_position(),
+        &mut ill_formed,
+    );
+    if ill_formed.is_empty() {
+        Ok(())
+    } else {
+        Err(ill_formed)
+    }
+}
+
+fn validate_term_rec<LOS: RewritableLanguageOperatorSymbol>(
+    term: &LanguageTerm<LOS>,
+    position: &PositionInLanguageTerm,
+    ill_formed: &mut Vec<IllFormedPosition<LOS>>,
+) {
+    if let LanguageOperatorArity::Fixed(expected) = term.operator.arity() {
+        if expected != term.sub_terms.len() {
+            ill_formed.push(IllFormedPosition {
+                position: position.clone(),
+                mismatch: ArityMismatch {
+                    operator: term.operator.clone(),
+                    expected,
+                    found: term.sub_terms.len(),
+                },
+            });
+        }
+    }
+    for (n, sub) in term.sub_terms.iter().enumerate() {
+        validate_term_rec(sub, &position.get_position_of_nth_child(n), ill_formed);
+    }
+}
+
 impl<LOS: RewritableLanguageOperatorSymbol + fmt::Display> fmt::Display for LanguageTermNode<LOS> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.sub_terms.is_empty() {
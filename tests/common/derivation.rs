@@ -0,0 +1,46 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use simple_term_rewriter::process::search::{DerivationDirection, DerivationStep};
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::process::trace::replay_rule_chain;
+use simple_term_rewriter::term::syntax::{
+    LanguageTerm, RewritableLanguageOperatorSymbol, TermFactory,
+};
+
+/// Checks that `derivation` goes from `source` to `target`, and that every
+/// step replays with `strategy` in its direction: from its source to its
+/// target if forward, the other way round if backward.
+pub fn check_derivation<LOS: RewritableLanguageOperatorSymbol>(
+    strategy: &RewriteProcess<LOS>,
+    derivation: &[DerivationStep<LOS>],
+    source: &LanguageTerm<LOS>,
+    target: &LanguageTerm<LOS>,
+    f: &mut TermFactory<LOS>,
+) {
+    let mut current = source.clone();
+    for step in derivation {
+        assert_eq!(step.source, current);
+        let (from, to) = match step.direction {
+            DerivationDirection::Forward => (&step.source, &step.target),
+            DerivationDirection::Backward => (&step.target, &step.source),
+        };
+        let (_, replayed) = replay_rule_chain(strategy, from, &step.rule_chain, f).unwrap();
+        assert_eq!(&replayed, to);
+        current = step.target.clone();
+    }
+    assert_eq!(&current, target);
+}
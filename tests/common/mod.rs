@@ -17,4 +17,5 @@ limitations under the License.
 #![allow(dead_code)]
 
 pub mod arith;
+pub mod derivation;
 pub mod regex;
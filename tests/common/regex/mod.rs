@@ -16,6 +16,8 @@ limitations under the License.
 
 pub mod constructors;
 pub mod lang;
+pub mod ordering;
+pub mod parsing;
 pub mod rules;
 pub mod util;
 
@@ -0,0 +1,86 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::cmp::Ordering;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::rules::util::ordering::TermOrdering;
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use super::generation::generate_regex_terms;
+use super::lang::RegexOp;
+
+// == precedence ================================================================
+
+/// The precedence of the ordering test suites, greatest first:
+/// Atom > Alt > Concat > Star > Epsilon > Empty, atoms by their character.
+pub fn precedence(x: &RegexOp, y: &RegexOp) -> Ordering {
+    fn rank(op: &RegexOp) -> u16 {
+        match op {
+            RegexOp::Atom(x) => 5 + (*x as u16),
+            RegexOp::Alt => 4,
+            RegexOp::Concat => 3,
+            RegexOp::Star => 2,
+            RegexOp::Epsilon => 1,
+            RegexOp::Empty => 0,
+        }
+    }
+    rank(x).cmp(&rank(y))
+}
+
+// == properties ================================================================
+
+/// Checks on `count` random terms that `compare_terms` is antisymmetric and
+/// agrees with `is_greater`, that `is_greater` is transitive, and that terms
+/// comparing `Equal` compare alike to every other term.
+pub fn check_total_antisymmetric_and_transitive(
+    ordering: &dyn TermOrdering<RegexOp>,
+    count: usize,
+    seed: u64,
+) {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let terms = generate_regex_terms(count, seed, &mut f);
+    for s in &terms {
+        assert_eq!(ordering.compare_terms(s, s), Ordering::Equal);
+        for t in &terms {
+            let st = ordering.compare_terms(s, t);
+            assert_eq!(st, ordering.compare_terms(t, s).reverse());
+            assert_eq!(st == Ordering::Greater, ordering.is_greater(s, t));
+            for u in &terms {
+                if st == Ordering::Equal {
+                    assert_eq!(ordering.compare_terms(s, u), ordering.compare_terms(t, u));
+                }
+                if st == Ordering::Greater && ordering.is_greater(t, u) {
+                    assert!(ordering.is_greater(s, u));
+                }
+            }
+        }
+    }
+}
+
+/// Checks on `count` random terms that every term is greater than each of
+/// its strict sub-terms.
+pub fn check_greater_than_subterms(ordering: &dyn TermOrdering<RegexOp>, count: usize, seed: u64) {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    for t in generate_regex_terms(count, seed, &mut f) {
+        let mut stack: Vec<_> = t.sub_terms.clone();
+        while let Some(sub_term) = stack.pop() {
+            assert!(ordering.is_greater(&t, &sub_term));
+            stack.extend(sub_term.sub_terms.iter().cloned());
+        }
+    }
+}
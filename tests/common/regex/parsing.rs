@@ -0,0 +1,53 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use simple_term_rewriter::term::parser::{
+    parse_term, LanguageOperatorTokenParser, TermParsingError,
+};
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use super::lang::RegexOp;
+
+/// Accepts both the symbols printed by `RegexOp`'s `Display` (`+`, `·`, `*`,
+/// `ε`, `∅`, single-character atoms) and the variant names (`Alt`, `Concat`,
+/// `Star`, `Epsilon`, `Empty`).
+pub struct RegexOpTokenParser;
+
+impl LanguageOperatorTokenParser<RegexOp> for RegexOpTokenParser {
+    fn parse_operator_token(&self, token: &str) -> Option<RegexOp> {
+        match token {
+            "+" | "Alt" => Some(RegexOp::Alt),
+            "·" | "Concat" => Some(RegexOp::Concat),
+            "*" | "Star" => Some(RegexOp::Star),
+            "ε" | "Epsilon" => Some(RegexOp::Epsilon),
+            "∅" | "Empty" => Some(RegexOp::Empty),
+            _ => {
+                let mut chars = token.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_alphanumeric() => Some(RegexOp::Atom(c as u8)),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+pub fn parse_regex(
+    input: &str,
+    f: &mut TermFactory<RegexOp>,
+) -> Result<LanguageTerm<RegexOp>, TermParsingError> {
+    parse_term(input, &RegexOpTokenParser, f)
+}
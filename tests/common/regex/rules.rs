@@ -19,6 +19,7 @@ use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
 use simple_term_rewriter::rule::{ClosureRewriteRule, RewriteRule};
 use simple_term_rewriter::term::syntax::{LanguageTerm, LanguageTermNode, TermFactory};
 
+use super::constructors::alt;
 use super::lang::RegexOp;
 
 // == test utilities ============================================================
@@ -219,3 +220,27 @@ pub fn one_step_outermost() -> RewriteProcess<RegexOp> {
 pub fn normalization_strategy() -> RewriteProcess<RegexOp> {
     RewriteProcess::Repeat(Box::new(one_step_outermost()))
 }
+
+/// `Alt(x, y) → Alt(y, x)`: repeating it never terminates.
+pub fn swap() -> RewriteProcess<RegexOp> {
+    rule_as_process(ClosureRewriteRule::new("swap", |t, _, _, f| {
+        if t.operator != RegexOp::Alt {
+            return None;
+        }
+        Some(alt(t.sub_terms[1].clone(), t.sub_terms[0].clone(), f))
+    }))
+}
+
+/// Rewrites the atom `from` into the atom `to`.
+pub fn atom_rule(from: u8, to: u8) -> RewriteProcess<RegexOp> {
+    rule_as_process(ClosureRewriteRule::new(
+        format!("{}→{}", from as char, to as char),
+        move |t, _, _, f| {
+            if t.operator == RegexOp::Atom(from) {
+                Some(LanguageTermNode::build(RegexOp::Atom(to), vec![], f))
+            } else {
+                None
+            }
+        },
+    ))
+}
@@ -0,0 +1,173 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Test suite for the AC-compatible recursive path ordering.
+//!
+//! Operator alphabet (greatest first): Atom > Alt > Concat > Star > Epsilon > Empty.
+//! `Alt` is the only associative-commutative operator.
+
+mod common;
+
+use std::cmp::Ordering;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::rules::util::acrpo::{
+    ac_recursive_path_ordering, are_equivalent_as_per_ac_recursive_path_ordering,
+    find_ac_compatibility_violation, ACRecursivePathOrdering,
+};
+use simple_term_rewriter::rules::util::lpo::LexicographicPathOrdering;
+use simple_term_rewriter::rules::util::ordering::TermOrdering;
+use simple_term_rewriter::rules::util::rpo::{
+    recursive_path_ordering, OperatorStatus, RecursivePathOrdering,
+};
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::ordering::*;
+
+// == AC-RPO helpers ============================================================
+
+fn is_alt(op: &RegexOp) -> bool {
+    *op == RegexOp::Alt
+}
+
+fn alt_multiset(op: &RegexOp) -> OperatorStatus {
+    if is_alt(op) {
+        OperatorStatus::Multiset
+    } else {
+        OperatorStatus::Lexicographic
+    }
+}
+
+fn acrpo() -> ACRecursivePathOrdering<RegexOp> {
+    ACRecursivePathOrdering::new(precedence, is_alt)
+}
+
+// == AC-equality ===============================================================
+
+#[test]
+fn ac_equal_terms_compare_equal() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let sa = star(a.clone(), &mut f);
+    // |(a, |(b, *(a)))  and  |(|(*(a), a), b)
+    let right = alt(b.clone(), sa.clone(), &mut f);
+    let s = alt(a.clone(), right, &mut f);
+    let left = alt(sa.clone(), a.clone(), &mut f);
+    let t = alt(left, b.clone(), &mut f);
+    let ordering = acrpo();
+    assert!(ordering.are_equivalent(&s, &t));
+    assert_eq!(ordering.compare_terms(&s, &t), Ordering::Equal);
+    assert!(are_equivalent_as_per_ac_recursive_path_ordering(
+        &s, &t, &is_alt
+    ));
+    // Concat is not commutative.
+    let ab = concat(a.clone(), b.clone(), &mut f);
+    let ba = concat(b, a, &mut f);
+    assert!(!ordering.are_equivalent(&ab, &ba));
+    assert_eq!(ordering.compare_terms(&ab, &ba), Ordering::Less);
+}
+
+#[test]
+fn flattening_identifies_what_multiset_status_alone_does_not() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // |(|(a, a), b)  and  |(|(b, a), a): both flatten to {a, a, b}.
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let aa = alt(a.clone(), a.clone(), &mut f);
+    let s = alt(aa, b.clone(), &mut f);
+    let ba = alt(b, a.clone(), &mut f);
+    let t = alt(ba, a, &mut f);
+    assert_eq!(
+        ac_recursive_path_ordering(&s, &t, &precedence, &is_alt),
+        Ordering::Equal
+    );
+    // Without flattening, |(b, a) > |(a, a) decides.
+    assert_eq!(
+        recursive_path_ordering(&s, &t, &precedence, &alt_multiset),
+        Ordering::Less
+    );
+}
+
+#[test]
+fn non_ac_operators_may_have_multiset_status() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let e = epsilon(&mut f);
+    let ae = concat(a.clone(), e.clone(), &mut f);
+    let ea = concat(e, a, &mut f);
+    let ordering = acrpo().with_operator_status(|op| {
+        if *op == RegexOp::Concat {
+            OperatorStatus::Multiset
+        } else {
+            OperatorStatus::Lexicographic
+        }
+    });
+    assert!(ordering.are_equivalent(&ae, &ea));
+    // Concat chains are still not flattened.
+    let ae_e = concat(ae.clone(), epsilon(&mut f), &mut f);
+    let e_ae = concat(epsilon(&mut f), ae.clone(), &mut f);
+    let flat = concat(ea.clone(), epsilon(&mut f), &mut f);
+    assert!(ordering.are_equivalent(&ae_e, &e_ae));
+    assert!(ordering.are_equivalent(&ae_e, &flat));
+    let e = epsilon(&mut f);
+    let nested = concat(e.clone(), concat(e, ae, &mut f), &mut f);
+    assert!(!ordering.are_equivalent(&ae_e, &nested));
+}
+
+// == compatibility check =======================================================
+
+#[test]
+fn compatibility_check_passes_for_the_ac_rpo() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let terms = generate_regex_terms(40, 113, &mut f);
+    assert_eq!(
+        find_ac_compatibility_violation(&acrpo(), &terms, &is_alt, &mut f),
+        None
+    );
+}
+
+#[test]
+fn compatibility_check_reports_the_lpo_and_the_rpo() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let terms = generate_regex_terms(40, 113, &mut f);
+    let lpo = LexicographicPathOrdering::new(precedence);
+    let rpo = RecursivePathOrdering::new(precedence).with_operator_status(alt_multiset);
+    for ordering in [
+        &lpo as &dyn TermOrdering<RegexOp>,
+        &rpo as &dyn TermOrdering<RegexOp>,
+    ] {
+        let (term, variant) =
+            find_ac_compatibility_violation(ordering, &terms, &is_alt, &mut f).unwrap();
+        assert!(acrpo().are_equivalent(&term, &variant));
+    }
+}
+
+// == properties ================================================================
+
+#[test]
+fn total_antisymmetric_and_transitive_on_random_terms() {
+    check_total_antisymmetric_and_transitive(&acrpo(), 30, 127);
+}
+
+#[test]
+fn terms_are_greater_than_their_subterms() {
+    check_greater_than_subterms(&acrpo(), 100, 131);
+}
@@ -0,0 +1,309 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Test suite for the Knuth–Bendix ordering and the [`TermOrdering`] trait.
+//!
+//! Operator alphabet (greatest first): Atom > Alt > Concat > Star > Epsilon > Empty,
+//! every operator weighing 1 unless stated otherwise.
+
+mod common;
+
+use std::cmp::Ordering;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::PositionInLanguageTerm;
+use simple_term_rewriter::rule::RewriteRule;
+use simple_term_rewriter::rules::primitives::reorder_apc::{
+    ModuloAssociativePartialReorderer, PartialACReorderRule,
+};
+use simple_term_rewriter::rules::primitives::reorder_pc::{
+    CommutativeCheckerAndOrderer, CommuteReorderRule,
+};
+use simple_term_rewriter::rules::util::kbo::{
+    is_greater_as_per_knuth_bendix_ordering, knuth_bendix_ordering, KnuthBendixAdmissibilityError,
+    KnuthBendixOrdering,
+};
+use simple_term_rewriter::rules::util::lpo::{
+    is_greater_as_per_lexicographic_path_ordering, LexicographicPathOrdering,
+};
+use simple_term_rewriter::rules::util::ordering::TermOrdering;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::ordering::*;
+
+// == KBO helpers ===============================================================
+
+fn unit_weight(_: &RegexOp) -> usize {
+    1
+}
+
+fn kbo() -> KnuthBendixOrdering<RegexOp> {
+    KnuthBendixOrdering::new(unit_weight, precedence)
+}
+
+fn kbo_gt(s: &LanguageTerm<RegexOp>, t: &LanguageTerm<RegexOp>) -> bool {
+    is_greater_as_per_knuth_bendix_ordering(s, t, &unit_weight, &precedence)
+}
+
+// == definition ================================================================
+
+#[test]
+fn heavier_terms_are_greater() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // Star is below Concat in the precedence, but weighs more here.
+    let e = empty(&mut f);
+    let s = star(star(star(e.clone(), &mut f), &mut f), &mut f);
+    let t = concat(e.clone(), e, &mut f);
+    assert!(kbo_gt(&s, &t));
+    assert!(!kbo_gt(&t, &s));
+    assert_eq!(kbo().get_term_weight(&s), 4);
+}
+
+#[test]
+fn precedence_breaks_weight_ties() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let e = empty(&mut f);
+    let eps = epsilon(&mut f);
+    let s = concat(e.clone(), e.clone(), &mut f);
+    let t = star(star(e.clone(), &mut f), &mut f);
+    assert!(kbo_gt(&s, &t));
+    assert!(kbo_gt(&eps, &e));
+    // Weights decide first: Atom is greatest, but lighter.
+    let heavy = star(e, &mut f);
+    assert!(kbo_gt(&heavy, &atom(b'a', &mut f)));
+}
+
+#[test]
+fn same_root_compares_children_lexicographically() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let e = empty(&mut f);
+    let eps = epsilon(&mut f);
+    let s = alt(eps.clone(), e.clone(), &mut f);
+    let t = alt(e.clone(), eps.clone(), &mut f);
+    assert!(kbo_gt(&s, &t));
+    // Equal first children: the second one decides.
+    let s = alt(e.clone(), eps.clone(), &mut f);
+    let t = alt(e.clone(), e, &mut f);
+    assert!(kbo_gt(&s, &t));
+    assert_eq!(
+        knuth_bendix_ordering(&s, &s, &unit_weight, &precedence),
+        Ordering::Equal
+    );
+}
+
+#[test]
+fn weights_saturate() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let e = empty(&mut f);
+    let heavy = KnuthBendixOrdering::new(|_: &RegexOp| usize::MAX / 2 + 1, precedence);
+    let s = star(star(e.clone(), &mut f), &mut f);
+    let t = concat(e.clone(), e.clone(), &mut f);
+    assert_eq!(heavy.get_term_weight(&s), usize::MAX);
+    // Both weights saturate: the precedence decides.
+    assert_eq!(heavy.compare_terms(&t, &s), Ordering::Greater);
+    assert_eq!(heavy.compare_terms(&s, &e), Ordering::Greater);
+}
+
+// == properties ================================================================
+
+#[test]
+fn total_antisymmetric_and_transitive_on_random_terms() {
+    check_total_antisymmetric_and_transitive(&kbo(), 40, 83);
+}
+
+#[test]
+fn only_equal_terms_are_equivalent() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let terms = generate_regex_terms(40, 83, &mut f);
+    let ordering = kbo();
+    for s in &terms {
+        for t in &terms {
+            assert_eq!(ordering.compare_terms(s, t) == Ordering::Equal, s == t);
+        }
+    }
+}
+
+#[test]
+fn terms_are_greater_than_their_subterms() {
+    // Star weighs 0 and is maximal, which keeps the weights admissible.
+    let ordering = KnuthBendixOrdering::new(
+        |op: &RegexOp| usize::from(*op != RegexOp::Star),
+        |x: &RegexOp, y: &RegexOp| match (x, y) {
+            (RegexOp::Star, RegexOp::Star) => Ordering::Equal,
+            (RegexOp::Star, _) => Ordering::Greater,
+            (_, RegexOp::Star) => Ordering::Less,
+            _ => precedence(x, y),
+        },
+    );
+    check_greater_than_subterms(&ordering, 100, 89);
+}
+
+#[test]
+fn admissibility() {
+    let symbols = [
+        RegexOp::Empty,
+        RegexOp::Epsilon,
+        RegexOp::Atom(b'a'),
+        RegexOp::Alt,
+        RegexOp::Concat,
+        RegexOp::Star,
+    ];
+    assert_eq!(kbo().check_admissibility(&symbols), Ok(()));
+    let zero_epsilon = KnuthBendixOrdering::new(
+        |op: &RegexOp| usize::from(*op != RegexOp::Epsilon),
+        precedence,
+    );
+    assert_eq!(
+        zero_epsilon.check_admissibility(&symbols),
+        Err(KnuthBendixAdmissibilityError::ZeroWeightConstant(
+            RegexOp::Epsilon
+        ))
+    );
+    // Star weighs 0 but Atom, Alt and Concat are above it.
+    let zero_star =
+        KnuthBendixOrdering::new(|op: &RegexOp| usize::from(*op != RegexOp::Star), precedence);
+    let error = zero_star.check_admissibility(&symbols).unwrap_err();
+    assert_eq!(
+        error,
+        KnuthBendixAdmissibilityError::ZeroWeightSymbolNotMaximal {
+            symbol: RegexOp::Star,
+            other: RegexOp::Atom(b'a'),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "operator Star has weight 0 but is not greater than Atom(97) in the precedence"
+    );
+    // Binary operators may weigh 0.
+    let zero_alt =
+        KnuthBendixOrdering::new(|op: &RegexOp| usize::from(*op != RegexOp::Alt), precedence);
+    assert_eq!(zero_alt.check_admissibility(&symbols), Ok(()));
+}
+
+// == comparison with the LPO ===================================================
+
+#[test]
+fn orients_rules_the_lpo_cannot() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // Star > Concat: ·(*(a), *(a)) → *(·(a, a)) shrinks the term, but the
+    // LPO sees a greater root on the right.
+    let star_first = |x: &RegexOp, y: &RegexOp| match (x, y) {
+        (RegexOp::Star, RegexOp::Concat) => Ordering::Greater,
+        (RegexOp::Concat, RegexOp::Star) => Ordering::Less,
+        _ => precedence(x, y),
+    };
+    let a = atom(b'a', &mut f);
+    let sa = star(a.clone(), &mut f);
+    let lhs = concat(sa.clone(), sa, &mut f);
+    let aa = concat(a.clone(), a, &mut f);
+    let rhs = star(aa, &mut f);
+    assert!(!is_greater_as_per_lexicographic_path_ordering(
+        &lhs,
+        &rhs,
+        &star_first
+    ));
+    assert!(is_greater_as_per_knuth_bendix_ordering(
+        &lhs,
+        &rhs,
+        &unit_weight,
+        &star_first
+    ));
+    let orderings: Vec<Box<dyn TermOrdering<RegexOp>>> = vec![
+        Box::new(LexicographicPathOrdering::new(star_first)),
+        Box::new(KnuthBendixOrdering::new(unit_weight, star_first)),
+    ];
+    let oriented: Vec<bool> = orderings
+        .iter()
+        .map(|ordering| ordering.is_greater(&lhs, &rhs))
+        .collect();
+    assert_eq!(oriented, vec![false, true]);
+}
+
+// == reordering rules ==========================================================
+
+struct AltCommutes;
+
+impl CommutativeCheckerAndOrderer<RegexOp> for AltCommutes {
+    fn is_a_binary_commutative_operator(&self, op: &RegexOp) -> bool {
+        *op == RegexOp::Alt
+    }
+    fn may_commute_under(
+        &self,
+        _: &RegexOp,
+        _: &LanguageTerm<RegexOp>,
+        _: &LanguageTerm<RegexOp>,
+    ) -> bool {
+        true
+    }
+    fn compare_operators(&self, a: &RegexOp, b: &RegexOp) -> Ordering {
+        precedence(a, b)
+    }
+}
+
+impl ModuloAssociativePartialReorderer<RegexOp> for AltCommutes {
+    fn is_an_associative_partially_commutative_binary_operator_we_may_consider(
+        &self,
+        op: &RegexOp,
+    ) -> bool {
+        *op == RegexOp::Alt
+    }
+    fn may_commute_under(
+        &self,
+        _: &RegexOp,
+        _: &LanguageTerm<RegexOp>,
+        _: &LanguageTerm<RegexOp>,
+    ) -> bool {
+        true
+    }
+    fn compare_operators(&self, a: &RegexOp, b: &RegexOp) -> Ordering {
+        precedence(a, b)
+    }
+}
+
+#[test]
+fn reordering_rules_accept_the_kbo() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let root = PositionInLanguageTerm::get_root_position();
+    // The LPO puts ·(ε, ε) above *(*(*(ε))); the KBO puts it below.
+    let eps = epsilon(&mut f);
+    let heavy = star(star(star(eps.clone(), &mut f), &mut f), &mut f);
+    let light = concat(eps.clone(), eps, &mut f);
+    let t = alt(heavy.clone(), light.clone(), &mut f);
+    let swapped = alt(light.clone(), heavy.clone(), &mut f);
+    let lpo_rule = CommuteReorderRule::new("lpo", AltCommutes);
+    let kbo_rule = CommuteReorderRule::new("kbo", AltCommutes).with_term_ordering(kbo());
+    assert_eq!(lpo_rule.try_apply(&t, &t, &root, &mut f), None);
+    assert_eq!(
+        kbo_rule.try_apply(&t, &t, &root, &mut f),
+        Some(swapped.clone())
+    );
+    assert_eq!(
+        lpo_rule.try_apply(&swapped, &swapped, &root, &mut f),
+        Some(t.clone())
+    );
+    // Same with the flattened reordering: |(|(heavy, light), heavy).
+    let chain = alt(t.clone(), heavy.clone(), &mut f);
+    let ac_rule = PartialACReorderRule::new("kbo ac", AltCommutes).with_term_ordering(kbo());
+    let sorted = alt(light, alt(heavy.clone(), heavy, &mut f), &mut f);
+    assert_eq!(
+        ac_rule.try_apply(&chain, &chain, &root, &mut f),
+        Some(sorted)
+    );
+}
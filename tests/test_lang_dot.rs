@@ -0,0 +1,125 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`term_to_dot`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::metrics::builtin::{dag_size, tree_size};
+use simple_term_rewriter::position::PositionInLanguageTerm;
+use simple_term_rewriter::term::dot::{term_to_dot, TermDotLayout};
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+
+fn count_nodes(dot: &str) -> usize {
+    dot.lines()
+        .filter(|l| l.contains("[label=") && !l.contains("->"))
+        .count()
+}
+
+fn count_edges(dot: &str) -> usize {
+    dot.lines().filter(|l| l.contains("->")).count()
+}
+
+fn count_highlighted(dot: &str) -> usize {
+    dot.lines().filter(|l| l.contains("style=filled")).count()
+}
+
+fn pos(coords: &[usize]) -> PositionInLanguageTerm {
+    PositionInLanguageTerm::from_absolute_coordinates(coords.to_vec())
+}
+
+#[test]
+fn tree_layout_of_leaf() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let dot = term_to_dot(&atom(b'a', &mut f), TermDotLayout::Tree, &[]);
+    assert_eq!(
+        dot,
+        "digraph term {\n  node [shape=ellipse];\n  n0 [label=\"a\"];\n}\n"
+    );
+}
+
+#[test]
+fn tree_layout_duplicates_shared_sub_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // +(*(a), *(a)): the shared Star(a) is drawn twice.
+    let sa = star(atom(b'a', &mut f), &mut f);
+    let t = alt(sa.clone(), sa, &mut f);
+    let dot = term_to_dot(&t, TermDotLayout::Tree, &[]);
+    assert_eq!(count_nodes(&dot), 5);
+    assert_eq!(count_edges(&dot), 4);
+    assert!(dot.contains("n0 -> n1 [label=\"0\"];"));
+    assert!(dot.contains("n0 -> n3 [label=\"1\"];"));
+}
+
+#[test]
+fn dag_layout_shares_sub_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let sa = star(atom(b'a', &mut f), &mut f);
+    let t = alt(sa.clone(), sa, &mut f);
+    let dot = term_to_dot(&t, TermDotLayout::SharedDag, &[]);
+    assert_eq!(count_nodes(&dot), 3);
+    // Both edges of the root point to the same node.
+    assert!(dot.contains("n0 -> n1 [label=\"0\"];"));
+    assert!(dot.contains("n0 -> n1 [label=\"1\"];"));
+}
+
+#[test]
+fn node_counts_match_metrics_on_random_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    for t in generate_regex_terms(50, 7, &mut f) {
+        let tree = term_to_dot(&t, TermDotLayout::Tree, &[]);
+        let dag = term_to_dot(&t, TermDotLayout::SharedDag, &[]);
+        assert_eq!(count_nodes(&tree), tree_size(&t));
+        assert_eq!(count_edges(&tree), tree_size(&t) - 1);
+        assert_eq!(count_nodes(&dag), dag_size(&t));
+    }
+}
+
+#[test]
+fn tree_layout_highlights_exact_positions() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let sa = star(atom(b'a', &mut f), &mut f);
+    let t = alt(sa.clone(), sa, &mut f);
+    let dot = term_to_dot(&t, TermDotLayout::Tree, &[pos(&[1])]);
+    assert_eq!(count_highlighted(&dot), 1);
+    assert!(dot.contains("n3 [label=\"*\", style=filled"));
+}
+
+#[test]
+fn dag_layout_highlights_shared_node_once() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let sa = star(atom(b'a', &mut f), &mut f);
+    let t = alt(sa.clone(), sa, &mut f);
+    let dot = term_to_dot(&t, TermDotLayout::SharedDag, &[pos(&[0]), pos(&[1])]);
+    assert_eq!(count_highlighted(&dot), 1);
+    assert!(dot.contains("n1 [label=\"*\", style=filled"));
+}
+
+#[test]
+fn invalid_highlight_positions_are_ignored() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(atom(b'a', &mut f), &mut f);
+    for layout in [TermDotLayout::Tree, TermDotLayout::SharedDag] {
+        let dot = term_to_dot(&t, layout, &[pos(&[3, 1])]);
+        assert_eq!(count_highlighted(&dot), 0);
+    }
+}
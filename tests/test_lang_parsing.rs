@@ -0,0 +1,142 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`parse_term`], the inverse of `LanguageTerm`'s `Display`.
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::term::parser::{TermParsingError, TermParsingErrorKind};
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::parsing::parse_regex;
+
+// == well-formed input =========================================================
+
+#[test]
+fn parse_leaf() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    assert_eq!(parse_regex("a", &mut f), Ok(atom(b'a', &mut f)));
+    assert_eq!(parse_regex("ε", &mut f), Ok(epsilon(&mut f)));
+    assert_eq!(parse_regex("a()", &mut f), Ok(atom(b'a', &mut f)));
+}
+
+#[test]
+fn parse_named_operators() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let expected = alt(star(atom(b'a', &mut f), &mut f), atom(b'b', &mut f), &mut f);
+    assert_eq!(parse_regex("Alt(Star(a), b)", &mut f), Ok(expected));
+}
+
+#[test]
+fn parse_tolerates_whitespace_and_newlines() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let expected = concat(atom(b'a', &mut f), empty(&mut f), &mut f);
+    assert_eq!(
+        parse_regex("  Concat (\n  a ,\n  ∅ )\n", &mut f),
+        Ok(expected)
+    );
+}
+
+#[test]
+fn parse_is_inverse_of_display_on_random_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    for t in generate_regex_terms(200, 42, &mut f) {
+        let printed = format!("{}", t);
+        assert_eq!(parse_regex(&printed, &mut f), Ok(t), "on {printed}");
+    }
+}
+
+// == errors ====================================================================
+
+fn err(kind: TermParsingErrorKind, line: usize, column: usize) -> TermParsingError {
+    TermParsingError { line, column, kind }
+}
+
+#[test]
+fn error_unknown_operator_is_located() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    assert_eq!(
+        parse_regex("Alt(a, Foo)", &mut f),
+        Err(err(
+            TermParsingErrorKind::UnknownOperator("Foo".to_string()),
+            1,
+            8
+        ))
+    );
+}
+
+#[test]
+fn error_arity_mismatch() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    assert_eq!(
+        parse_regex("Alt(a,\n  Star(a, b))", &mut f),
+        Err(err(
+            TermParsingErrorKind::ArityMismatch {
+                operator: "Star".to_string(),
+                expected: 1,
+                found: 2
+            },
+            2,
+            3
+        ))
+    );
+    // A binary operator written as a leaf.
+    assert!(matches!(
+        parse_regex("Alt", &mut f),
+        Err(TermParsingError {
+            kind: TermParsingErrorKind::ArityMismatch { found: 0, .. },
+            ..
+        })
+    ));
+}
+
+#[test]
+fn error_unexpected_end_of_input() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    assert_eq!(
+        parse_regex("Star(a", &mut f),
+        Err(err(TermParsingErrorKind::UnexpectedEndOfInput, 1, 7))
+    );
+    assert_eq!(
+        parse_regex("   ", &mut f),
+        Err(err(TermParsingErrorKind::UnexpectedEndOfInput, 1, 4))
+    );
+}
+
+#[test]
+fn error_unexpected_character() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    assert_eq!(
+        parse_regex("Alt(,a)", &mut f),
+        Err(err(TermParsingErrorKind::UnexpectedCharacter(','), 1, 5))
+    );
+    assert_eq!(
+        parse_regex("Star(a))", &mut f),
+        Err(err(TermParsingErrorKind::UnexpectedCharacter(')'), 1, 8))
+    );
+}
+
+#[test]
+fn error_display() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let e = parse_regex("Star(Foo)", &mut f).unwrap_err();
+    assert_eq!(format!("{e}"), "line 1, column 6: unknown operator 'Foo'");
+}
@@ -507,3 +507,28 @@ fn lpo_ordering_wrapper_less() {
         Ordering::Less
     );
 }
+
+#[test]
+fn lpo_equal_root_subterm_dominates() {
+    // Concat(Epsilon, Concat(Star(Empty), Empty)) > Concat(Star(Empty), Empty)
+    //
+    // The second child of s is t itself, so s > t by the subterm property,
+    // even though the first children compare the other way (Epsilon < Star(Empty)).
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let s = term!(&mut f, Concat; term!(&mut f, Epsilon), term!(&mut f, Concat; term!(&mut f, Star; term!(&mut f, Empty)), term!(&mut f, Empty)));
+    let t = term!(&mut f, Concat; term!(&mut f, Star; term!(&mut f, Empty)), term!(&mut f, Empty));
+    assert_gt(
+        "Concat(Epsilon,Concat(Star(Empty),Empty)) > Concat(Star(Empty),Empty)",
+        &s,
+        &t,
+    );
+    assert_not_gt(
+        "Concat(Star(Empty),Empty) ≯ Concat(Epsilon,Concat(Star(Empty),Empty))",
+        &t,
+        &s,
+    );
+    assert_eq!(
+        lexicographic_path_ordering(&s, &t, &lpo_cmp),
+        Ordering::Greater
+    );
+}
@@ -0,0 +1,330 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Test suite for precedence inference.
+//!
+//! Pairs are written `lhs → rhs` over the regex alphabet; the inference must
+//! find a total order on the operators under which every `lhs > rhs`.
+
+mod common;
+
+use std::cmp::Ordering;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::rules::util::kbo::{KnuthBendixAdmissibilityError, KnuthBendixOrdering};
+use simple_term_rewriter::rules::util::lpo::{
+    is_greater_as_per_lexicographic_path_ordering, LexicographicPathOrdering,
+};
+use simple_term_rewriter::rules::util::ordering::TermOrdering;
+use simple_term_rewriter::rules::util::precedence::{
+    PrecedenceInference, PrecedenceInferenceError,
+};
+use simple_term_rewriter::rules::util::rpo::{OperatorStatus, RecursivePathOrdering};
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::lang::RegexOp;
+
+// == pair helpers ==============================================================
+
+/// The operators of the pairs below.
+fn signature() -> Vec<RegexOp> {
+    vec![
+        RegexOp::Empty,
+        RegexOp::Epsilon,
+        RegexOp::Atom(b'a'),
+        RegexOp::Atom(b'b'),
+        RegexOp::Alt,
+        RegexOp::Concat,
+        RegexOp::Star,
+    ]
+}
+
+/// `·(a, |(b, ε)) → |(·(a, b), ·(a, ε))`: requires `Concat > Alt`.
+fn distribute(f: &mut TermFactory<RegexOp>) -> (LanguageTerm<RegexOp>, LanguageTerm<RegexOp>) {
+    let a = atom(b'a', f);
+    let b = atom(b'b', f);
+    let e = epsilon(f);
+    let b_or_e = alt(b.clone(), e.clone(), f);
+    let lhs = concat(a.clone(), b_or_e, f);
+    let ab = concat(a.clone(), b, f);
+    let ae = concat(a, e, f);
+    (lhs, alt(ab, ae, f))
+}
+
+/// The reverse of [`distribute`]: requires `Alt > Concat`.
+fn factorize(f: &mut TermFactory<RegexOp>) -> (LanguageTerm<RegexOp>, LanguageTerm<RegexOp>) {
+    let (lhs, rhs) = distribute(f);
+    (rhs, lhs)
+}
+
+/// `*(∅) → ε`: requires `Star > Epsilon` or `Empty > Epsilon`.
+fn star_empty(f: &mut TermFactory<RegexOp>) -> (LanguageTerm<RegexOp>, LanguageTerm<RegexOp>) {
+    let e = empty(f);
+    (star(e, f), epsilon(f))
+}
+
+/// `**(x) → *(x)`: decreasing under any precedence.
+fn double_star(f: &mut TermFactory<RegexOp>) -> (LanguageTerm<RegexOp>, LanguageTerm<RegexOp>) {
+    let a = atom(b'a', f);
+    let sa = star(a, f);
+    (star(sa.clone(), f), sa)
+}
+
+// == inference =================================================================
+
+#[test]
+fn inferred_precedence_orients_every_pair() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let pairs = vec![distribute(&mut f), star_empty(&mut f), double_star(&mut f)];
+    let precedence = PrecedenceInference::for_lexicographic_path_ordering()
+        .infer(&signature(), &pairs)
+        .unwrap();
+    for (lhs, rhs) in &pairs {
+        assert!(is_greater_as_per_lexicographic_path_ordering(
+            lhs,
+            rhs,
+            &|a, b| precedence.compare_operators(a, b)
+        ));
+    }
+    let ranking = precedence.get_ranking();
+    let rank = |op: &RegexOp| ranking.iter().position(|x| x == op).unwrap();
+    assert!(rank(&RegexOp::Concat) < rank(&RegexOp::Alt));
+    // Every symbol of the pairs is ranked exactly once.
+    assert_eq!(ranking.len(), 7);
+}
+
+#[test]
+fn unconstrained_symbols_keep_their_order_of_occurrence() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let pairs = vec![double_star(&mut f)];
+    let precedence = PrecedenceInference::for_lexicographic_path_ordering()
+        .infer(&[RegexOp::Concat, RegexOp::Alt], &pairs)
+        .unwrap();
+    // The given symbols first, then those of the pairs.
+    assert_eq!(
+        precedence.get_ranking(),
+        &[
+            RegexOp::Concat,
+            RegexOp::Alt,
+            RegexOp::Star,
+            RegexOp::Atom(b'a')
+        ]
+    );
+}
+
+#[test]
+fn inferred_precedence_is_total_on_symbols_absent_from_the_pairs() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let pairs = vec![double_star(&mut f)];
+    let precedence = PrecedenceInference::for_lexicographic_path_ordering()
+        .infer(&signature(), &pairs)
+        .unwrap();
+    let lpo = LexicographicPathOrdering::new(precedence.into_compare_operators());
+    // Neither Alt nor Epsilon occurs in the pairs, and their arities differ.
+    let b = atom(b'b', &mut f);
+    let bb = alt(b.clone(), b, &mut f);
+    let e = epsilon(&mut f);
+    let ordering = lpo.compare_terms(&bb, &e);
+    assert!(ordering.is_ne());
+    assert_eq!(lpo.compare_terms(&e, &bb), ordering.reverse());
+}
+
+#[test]
+fn unranked_symbols_are_below_the_ranked_ones() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let precedence = PrecedenceInference::for_lexicographic_path_ordering()
+        .infer(&[], &[double_star(&mut f)])
+        .unwrap();
+    assert_eq!(precedence.get_rank(&RegexOp::Star), Some(0));
+    assert_eq!(precedence.get_rank(&RegexOp::Alt), None);
+    assert_eq!(
+        precedence.compare_operators(&RegexOp::Alt, &RegexOp::Star),
+        Ordering::Less
+    );
+    assert_eq!(
+        precedence.compare_operators(&RegexOp::Alt, &RegexOp::Concat),
+        Ordering::Equal
+    );
+}
+
+#[test]
+fn conflicting_pairs_are_reduced_to_a_minimal_subset() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let pairs = vec![
+        star_empty(&mut f),
+        distribute(&mut f),
+        double_star(&mut f),
+        factorize(&mut f),
+    ];
+    let inference = PrecedenceInference::for_lexicographic_path_ordering();
+    assert_eq!(
+        inference.infer(&signature(), &pairs),
+        Err(PrecedenceInferenceError::ConflictingPairs(vec![1, 3]))
+    );
+    // Dropping either pair of the conflict makes the rest orientable.
+    assert!(inference.infer(&signature(), &pairs[..3]).is_ok());
+    assert!(inference
+        .infer(
+            &signature(),
+            &[pairs[0].clone(), pairs[2].clone(), pairs[3].clone()]
+        )
+        .is_ok());
+}
+
+#[test]
+fn pairs_no_precedence_orients_conflict_alone() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let (lhs, rhs) = double_star(&mut f);
+    let inference = PrecedenceInference::for_lexicographic_path_ordering();
+    // **(a) is a sub-term of the right-hand side.
+    let pairs = vec![distribute(&mut f), (rhs, lhs.clone())];
+    let error = inference.infer(&signature(), &pairs).unwrap_err();
+    assert_eq!(error, PrecedenceInferenceError::ConflictingPairs(vec![1]));
+    assert_eq!(error.to_string(), "no precedence orients the pairs [1]");
+    assert_eq!(
+        inference.infer(&signature(), &[(lhs.clone(), lhs)]),
+        Err(PrecedenceInferenceError::ConflictingPairs(vec![0]))
+    );
+}
+
+// == other orderings ===========================================================
+
+#[test]
+fn multiset_status_orients_what_no_lpo_precedence_can() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // |(a, a) → *(a) needs Alt > Star.  Then, |(a, *(b)) → |(b, a) needs
+    // a > b lexicographically, and |(b, *(a)) → |(a, b) needs b > a.
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let sa = star(a.clone(), &mut f);
+    let sb = star(b.clone(), &mut f);
+    let pairs = vec![
+        (
+            alt(a.clone(), sb, &mut f),
+            alt(b.clone(), a.clone(), &mut f),
+        ),
+        (
+            alt(b.clone(), sa.clone(), &mut f),
+            alt(a.clone(), b, &mut f),
+        ),
+        (alt(a.clone(), a, &mut f), sa),
+    ];
+    assert_eq!(
+        PrecedenceInference::for_lexicographic_path_ordering().infer(&signature(), &pairs),
+        Err(PrecedenceInferenceError::ConflictingPairs(vec![0, 1, 2]))
+    );
+    let get_status = |op: &RegexOp| {
+        if *op == RegexOp::Alt {
+            OperatorStatus::Multiset
+        } else {
+            OperatorStatus::Lexicographic
+        }
+    };
+    let precedence = PrecedenceInference::for_recursive_path_ordering(get_status)
+        .infer(&signature(), &pairs)
+        .unwrap();
+    let rpo = RecursivePathOrdering::new(precedence.into_compare_operators())
+        .with_operator_status(get_status);
+    assert!(pairs.iter().all(|(lhs, rhs)| rpo.is_greater(lhs, rhs)));
+}
+
+#[test]
+fn knuth_bendix_inference_only_decides_weight_ties() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    // |(a, b) → ·(a, b): equal weights, requires Alt > Concat.
+    let tie = (
+        alt(a.clone(), b.clone(), &mut f),
+        concat(a.clone(), b, &mut f),
+    );
+    // *(a) → ·(a, a): heavier right-hand side, never decreasing under unit weights.
+    let heavier = (star(a.clone(), &mut f), concat(a.clone(), a, &mut f));
+    let inference = PrecedenceInference::for_knuth_bendix_ordering(|_: &RegexOp| 1);
+    let pairs = vec![tie, heavier.clone()];
+    let precedence = inference.infer(&signature(), &pairs[..1]).unwrap();
+    assert!(precedence
+        .compare_operators(&RegexOp::Alt, &RegexOp::Concat)
+        .is_gt());
+    assert_eq!(
+        inference.infer(&signature(), &pairs),
+        Err(PrecedenceInferenceError::ConflictingPairs(vec![1]))
+    );
+    // The LPO orients it with Star > Concat.
+    assert!(PrecedenceInference::for_lexicographic_path_ordering()
+        .infer(&signature(), &[heavier])
+        .is_ok());
+}
+
+#[test]
+fn knuth_bendix_inference_keeps_the_weights_admissible() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    // |(a, b) → *(·(a, b)): equal weights, requires Alt > Star, which the
+    // zero weight of Star forbids.
+    let ab = concat(a.clone(), b.clone(), &mut f);
+    let pairs = vec![double_star(&mut f), (alt(a, b, &mut f), star(ab, &mut f))];
+    let weight = |op: &RegexOp| usize::from(*op != RegexOp::Star);
+    let inference = PrecedenceInference::for_knuth_bendix_ordering(weight);
+    assert_eq!(
+        inference.infer(&signature(), &pairs),
+        Err(PrecedenceInferenceError::ConflictingPairs(vec![1]))
+    );
+    let precedence = inference.infer(&signature(), &pairs[..1]).unwrap();
+    assert_eq!(precedence.get_ranking()[0], RegexOp::Star);
+    let kbo = KnuthBendixOrdering::new(weight, precedence.into_compare_operators());
+    assert_eq!(kbo.check_admissibility(&signature()), Ok(()));
+}
+
+#[test]
+fn inadmissible_weights_are_reported() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let pairs = vec![double_star(&mut f)];
+    let error = PrecedenceInference::for_knuth_bendix_ordering(|op: &RegexOp| {
+        usize::from(*op != RegexOp::Empty)
+    })
+    .infer(&signature(), &pairs)
+    .unwrap_err();
+    assert_eq!(
+        error,
+        PrecedenceInferenceError::InadmissibleWeights(
+            KnuthBendixAdmissibilityError::ZeroWeightConstant(RegexOp::Empty)
+        )
+    );
+    assert_eq!(
+        error.to_string(),
+        "inadmissible weights: constant Empty has weight 0"
+    );
+}
+
+#[test]
+fn search_limit() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let pairs = vec![distribute(&mut f), star_empty(&mut f)];
+    assert_eq!(
+        PrecedenceInference::for_lexicographic_path_ordering()
+            .with_max_decisions(1)
+            .infer(&signature(), &pairs),
+        Err(PrecedenceInferenceError::SearchLimitReached)
+    );
+    assert!(PrecedenceInference::for_lexicographic_path_ordering()
+        .with_max_decisions(16)
+        .infer(&signature(), &pairs)
+        .is_ok());
+}
@@ -0,0 +1,207 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`BestFirstSearch`].
+
+mod common;
+
+use std::rc::Rc;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::metrics::builtin::tree_size;
+use simple_term_rewriter::process::best_first::{BestFirstSearch, BestFirstTermination};
+use simple_term_rewriter::process::budget::{BudgetExhaustion, RewriteBudget};
+use simple_term_rewriter::process::control::RewriteExecutionError;
+use simple_term_rewriter::process::environment::StrategyEnvironment;
+use simple_term_rewriter::process::normalizer::Normalizer;
+use simple_term_rewriter::process::search::{ReachabilityOutcome, ReachabilitySearch};
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::derivation::check_derivation;
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+/// Any rule, anywhere in the term, with every alternative kept.
+fn any_step() -> RewriteProcess<RegexOp> {
+    let mut alternatives: Vec<_> = all_rules().into_iter().map(RewriteProcess::Rule).collect();
+    alternatives.push(RewriteProcess::OneChild(Box::new(RewriteProcess::Call(
+        "step".to_string(),
+    ))));
+    let environment = StrategyEnvironment::new(vec![(
+        "step".to_string(),
+        RewriteProcess::TryAllPaths(alternatives),
+    )])
+    .unwrap();
+    RewriteProcess::let_in(
+        Rc::new(environment),
+        RewriteProcess::Call("step".to_string()),
+    )
+    .unwrap()
+}
+
+fn smallest() -> BestFirstSearch<RegexOp> {
+    BestFirstSearch::new(any_step(), tree_size)
+}
+
+/// `·(*(*(∅)), |(∅, *(*(a))))`, whose normal form is `*(a)`.
+fn example(f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let e = empty(f);
+    let sse = star(star(e.clone(), f), f);
+    let ssa = star(star(atom(b'a', f), f), f);
+    concat(sse, alt(e, ssa, f), f)
+}
+
+// == greedy best-first =========================================================
+
+#[test]
+fn greedy_search_finds_the_smallest_term() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = example(&mut f);
+    let result = smallest().run(&t, &mut f).unwrap();
+    let expected = star(atom(b'a', &mut f), &mut f);
+    assert_eq!(result.best_term, expected);
+    assert_eq!(result.best_cost, 2);
+    assert_eq!(result.termination, BestFirstTermination::Exhausted);
+    check_derivation(&any_step(), &result.derivation, &t, &expected, &mut f);
+}
+
+#[test]
+fn smallest_terms_are_the_normal_forms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let mut normalizer = Normalizer::new(all_rules());
+    // Every rule shrinks the term, and the normal forms are unique.
+    for t in generate_regex_terms(50, 73, &mut f) {
+        let normal_form = normalizer.normalize(&t, &mut f);
+        let result = smallest().run(&t, &mut f).unwrap();
+        assert_eq!(result.best_term, normal_form);
+        assert_eq!(result.best_cost, tree_size(&normal_form));
+        check_derivation(&any_step(), &result.derivation, &t, &normal_form, &mut f);
+    }
+}
+
+// == beam search ===============================================================
+
+#[test]
+fn beam_of_width_one_climbs_down() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = example(&mut f);
+    let full = smallest().run(&t, &mut f).unwrap();
+    let beam = smallest().with_beam_width(1).run(&t, &mut f).unwrap();
+    assert_eq!(beam.best_term, full.best_term);
+    assert_eq!(beam.termination, BestFirstTermination::Exhausted);
+    // One term per layer, the last one being the normal form.
+    assert_eq!(beam.expanded_terms, beam.derivation.len() + 1);
+    assert!(beam.expanded_terms < full.expanded_terms);
+    check_derivation(&any_step(), &beam.derivation, &t, &beam.best_term, &mut f);
+}
+
+// == goal-directed search ======================================================
+
+#[test]
+fn goal_search_finds_shortest_derivations() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let mut normalizer = Normalizer::new(all_rules());
+    for t in generate_regex_terms(50, 79, &mut f) {
+        let normal_form = normalizer.normalize(&t, &mut f);
+        let shortest = match ReachabilitySearch::new(any_step())
+            .search(&t, &normal_form, &mut f)
+            .unwrap()
+        {
+            ReachabilityOutcome::Found(derivation) => derivation.len(),
+            other => panic!("normal form not reached: {:?}", other),
+        };
+        // Admissible: at least one step is left unless the goal is reached.
+        let goal = normal_form.clone();
+        let heuristic = move |u: &LanguageTerm<RegexOp>| usize::from(*u != goal);
+        let goal = normal_form.clone();
+        for search in [
+            BestFirstSearch::new(any_step(), heuristic.clone()),
+            BestFirstSearch::new(any_step(), heuristic).with_beam_width(usize::MAX),
+        ] {
+            let goal = goal.clone();
+            let result = search
+                .with_goal(move |u| *u == goal)
+                .run(&t, &mut f)
+                .unwrap();
+            assert_eq!(result.termination, BestFirstTermination::GoalReached);
+            assert_eq!(result.derivation.len(), shortest);
+            check_derivation(&any_step(), &result.derivation, &t, &normal_form, &mut f);
+        }
+    }
+}
+
+#[test]
+fn unreachable_goal() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = example(&mut f);
+    let result = smallest()
+        .with_goal(|u| u.operator == RegexOp::Epsilon)
+        .run(&t, &mut f)
+        .unwrap();
+    assert_eq!(result.termination, BestFirstTermination::Exhausted);
+    assert_eq!(result.best_term, star(atom(b'a', &mut f), &mut f));
+}
+
+// == limits ====================================================================
+
+#[test]
+fn expansion_and_budget_limits() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = example(&mut f);
+    let result = smallest().with_max_expansions(1).run(&t, &mut f).unwrap();
+    assert_eq!(
+        result.termination,
+        BestFirstTermination::MaxExpansionsReached
+    );
+    assert_eq!(result.expanded_terms, 1);
+    assert_eq!(result.derivation.len(), 1);
+    assert!(result.best_cost < tree_size(&t));
+    let result = smallest()
+        .with_budget(RewriteBudget::new().with_max_rule_applications(3))
+        .run(&t, &mut f)
+        .unwrap();
+    assert_eq!(
+        result.termination,
+        BestFirstTermination::BudgetExhausted(BudgetExhaustion::RuleApplications)
+    );
+    let result = smallest()
+        .with_beam_width(2)
+        .with_budget(RewriteBudget::new().with_max_frontier_size(1))
+        .run(&t, &mut f)
+        .unwrap();
+    assert_eq!(
+        result.termination,
+        BestFirstTermination::BudgetExhausted(BudgetExhaustion::FrontierSize)
+    );
+}
+
+#[test]
+fn strategy_errors_are_reported() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = atom(b'a', &mut f);
+    let result =
+        BestFirstSearch::new(RewriteProcess::Call("step".to_string()), tree_size).run(&t, &mut f);
+    assert_eq!(
+        result,
+        Err(RewriteExecutionError::UnboundStrategyName(
+            "step".to_string()
+        ))
+    );
+}
@@ -0,0 +1,250 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`RewriteBudget`] in both executors.
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::metrics::builtin::tree_size;
+use simple_term_rewriter::process::budget::{BudgetExhaustion, RewriteBudget, RewriteOutcome};
+use simple_term_rewriter::process::control::RewriteExecutionError;
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::rule::ClosureRewriteRule;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+/// `x → Concat(x, x)`: the tree size doubles at every step.
+fn duplicate() -> RewriteProcess<RegexOp> {
+    rule_as_process(ClosureRewriteRule::new("duplicate", |t, _, _, f| {
+        Some(concat(t.clone(), t.clone(), f))
+    }))
+}
+
+fn alt_ab(f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let a = atom(b'a', f);
+    let b = atom(b'b', f);
+    alt(a, b, f)
+}
+
+fn exhaustion<LOS: simple_term_rewriter::term::syntax::RewritableLanguageOperatorSymbol>(
+    outcome: &RewriteOutcome<LOS>,
+) -> Option<BudgetExhaustion> {
+    match outcome {
+        RewriteOutcome::Finished(_) => None,
+        RewriteOutcome::BudgetExhausted { exhaustion, .. } => Some(*exhaustion),
+    }
+}
+
+// == untraced executor =========================================================
+
+#[test]
+fn untraced_finishes_within_budget() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(empty(&mut f), &mut f), &mut f);
+    let outcome = RewriteProcessUntracedExecutor::new()
+        .with_budget(RewriteBudget::new().with_max_rule_applications(10))
+        .run_within_budget(&normalization_strategy(), &t, &mut f)
+        .unwrap();
+    assert_eq!(outcome, RewriteOutcome::Finished(vec![epsilon(&mut f)]));
+}
+
+#[test]
+fn untraced_rule_applications_stop_non_terminating_repeat() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let executor = RewriteProcessUntracedExecutor::new()
+        .with_budget(RewriteBudget::new().with_max_rule_applications(7));
+    let strategy = RewriteProcess::Repeat(Box::new(swap()));
+    let outcome = executor.run_within_budget(&strategy, &t, &mut f).unwrap();
+    // After 7 swaps, the Repeat returns the term it had reached.
+    let b = atom(b'b', &mut f);
+    let a = atom(b'a', &mut f);
+    assert_eq!(
+        outcome,
+        RewriteOutcome::BudgetExhausted {
+            exhaustion: BudgetExhaustion::RuleApplications,
+            partial_results: vec![alt(b, a, &mut f)],
+        }
+    );
+    assert_eq!(
+        executor.execute(&strategy, &t, &mut f),
+        Err(RewriteExecutionError::BudgetExhausted(
+            BudgetExhaustion::RuleApplications
+        ))
+    );
+}
+
+#[test]
+fn untraced_term_size_limit() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = atom(b'a', &mut f);
+    let outcome = RewriteProcessUntracedExecutor::new()
+        .with_budget(RewriteBudget::new().with_max_term_size(100))
+        .run_within_budget(&RewriteProcess::Repeat(Box::new(duplicate())), &t, &mut f)
+        .unwrap();
+    assert_eq!(exhaustion(&outcome), Some(BudgetExhaustion::TermSize));
+    // 1, 3, 7, 15, 31, 63 nodes: the next step would have 127.
+    assert_eq!(outcome.get_terms().len(), 1);
+    assert_eq!(tree_size(&outcome.get_terms()[0]), 63);
+}
+
+#[test]
+fn untraced_frontier_size_truncates_results() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let strategy = RewriteProcess::TryAllPaths(vec![swap(), swap(), swap(), swap(), swap()]);
+    let outcome = RewriteProcessUntracedExecutor::new()
+        .with_budget(RewriteBudget::new().with_max_frontier_size(3))
+        .run_within_budget(&strategy, &t, &mut f)
+        .unwrap();
+    assert_eq!(exhaustion(&outcome), Some(BudgetExhaustion::FrontierSize));
+    assert_eq!(outcome.get_terms().len(), 3);
+}
+
+#[test]
+fn untraced_deadline_stops_repeat_without_rule_applications() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = atom(b'a', &mut f);
+    // The inner Repeat never fails, so the outer one never stops by itself.
+    let strategy = RewriteProcess::Repeat(Box::new(RewriteProcess::Repeat(Box::new(
+        rule_as_process(rule_star_empty()),
+    ))));
+    let outcome = RewriteProcessUntracedExecutor::new()
+        .with_budget(RewriteBudget::new().with_timeout(Duration::from_millis(20)))
+        .run_within_budget(&strategy, &t, &mut f)
+        .unwrap();
+    assert_eq!(
+        exhaustion(&outcome),
+        Some(BudgetExhaustion::DeadlineReached)
+    );
+    assert_eq!(outcome.into_terms(), vec![t]);
+}
+
+#[test]
+fn untraced_cancellation_from_another_thread() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let flag = Arc::new(AtomicBool::new(false));
+    let canceller = {
+        let flag = flag.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            flag.store(true, Ordering::Relaxed);
+        })
+    };
+    let outcome = RewriteProcessUntracedExecutor::new()
+        .with_budget(RewriteBudget::new().with_cancellation_flag(flag))
+        .run_within_budget(&RewriteProcess::Repeat(Box::new(swap())), &t, &mut f)
+        .unwrap();
+    canceller.join().unwrap();
+    assert_eq!(exhaustion(&outcome), Some(BudgetExhaustion::Cancelled));
+    assert_eq!(outcome.get_terms().len(), 1);
+}
+
+// == traced executor ===========================================================
+
+#[test]
+fn traced_finishes_within_budget() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(empty(&mut f), &mut f), &mut f);
+    let eps = epsilon(&mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(normalization_strategy(), t, f)
+        .with_budget(RewriteBudget::new().with_max_rule_applications(2));
+    assert_eq!(
+        executor.run_within_budget(),
+        Ok(RewriteOutcome::Finished(vec![eps]))
+    );
+}
+
+#[test]
+fn traced_rule_applications_are_counted_across_steps() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(swap(), t.clone(), f)
+        .with_budget(RewriteBudget::new().with_max_rule_applications(4));
+    let outcome = executor.run_within_budget().unwrap();
+    assert_eq!(
+        outcome,
+        RewriteOutcome::BudgetExhausted {
+            exhaustion: BudgetExhaustion::RuleApplications,
+            partial_results: vec![t.clone()],
+        }
+    );
+    // The executor stays interrupted.
+    assert_eq!(
+        executor.try_progress(),
+        Err(RewriteExecutionError::BudgetExhausted(
+            BudgetExhaustion::RuleApplications
+        ))
+    );
+    assert_eq!(executor.get_current_terms(), &[t]);
+}
+
+#[test]
+fn traced_frontier_size_rolls_back_the_step() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let strategy = RewriteProcess::TryAllPaths(vec![swap(), swap()]);
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, t, f)
+        .with_budget(RewriteBudget::new().with_max_frontier_size(5));
+    assert_eq!(executor.progress().len(), 2);
+    assert_eq!(executor.progress().len(), 4);
+    let before = executor.get_current_terms().to_vec();
+    assert_eq!(
+        executor.try_progress(),
+        Err(RewriteExecutionError::BudgetExhausted(
+            BudgetExhaustion::FrontierSize
+        ))
+    );
+    assert_eq!(executor.get_current_terms(), before.as_slice());
+}
+
+#[test]
+fn traced_deadline() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(swap(), t.clone(), f)
+        .with_budget(RewriteBudget::new().with_deadline(Instant::now()));
+    let outcome = executor.run_within_budget().unwrap();
+    assert_eq!(
+        exhaustion(&outcome),
+        Some(BudgetExhaustion::DeadlineReached)
+    );
+    assert_eq!(outcome.into_terms(), vec![t]);
+}
+
+#[test]
+#[should_panic(expected = "rewriting budget exhausted: cancelled")]
+fn traced_progress_panics_when_cancelled() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let flag = Arc::new(AtomicBool::new(true));
+    let mut executor = RewriteProcessTracedExecutor::new(swap(), t, f)
+        .with_budget(RewriteBudget::new().with_cancellation_flag(flag));
+    executor.progress();
+}
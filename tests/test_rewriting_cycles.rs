@@ -0,0 +1,262 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for the cycle detection of both executors.
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::PositionInLanguageTerm;
+use simple_term_rewriter::process::budget::RewriteBudget;
+use simple_term_rewriter::process::control::{
+    CycleRuleApplication, RewriteCycle, RewriteExecutionError,
+};
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn pos(coords: &[usize]) -> PositionInLanguageTerm {
+    PositionInLanguageTerm::from_absolute_coordinates(coords.to_vec())
+}
+
+fn swap_at(coords: &[usize]) -> CycleRuleApplication {
+    CycleRuleApplication {
+        rule_desc: "swap".to_string(),
+        position: pos(coords),
+    }
+}
+
+fn alt_ab(f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let a = atom(b'a', f);
+    let b = atom(b'b', f);
+    alt(a, b, f)
+}
+
+fn alt_ba(f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let b = atom(b'b', f);
+    let a = atom(b'a', f);
+    alt(b, a, f)
+}
+
+fn detecting() -> RewriteProcessUntracedExecutor<RegexOp> {
+    RewriteProcessUntracedExecutor::new().with_cycle_detection(true)
+}
+
+// == untraced executor =========================================================
+
+#[test]
+fn untraced_detects_cycle_at_root() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let t_swapped = alt_ba(&mut f);
+    let strategy = RewriteProcess::Repeat(Box::new(swap()));
+    assert_eq!(
+        detecting().execute(&strategy, &t, &mut f),
+        Err(RewriteExecutionError::CycleDetected(RewriteCycle {
+            terms: vec![t.clone(), t_swapped, t],
+            rule_chains: vec![Some(vec![swap_at(&[])]), Some(vec![swap_at(&[])])],
+        }))
+    );
+}
+
+#[test]
+fn untraced_cycle_below_root_reports_whole_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let c = atom(b'c', &mut f);
+    let inner = alt_ab(&mut f);
+    let inner_swapped = alt_ba(&mut f);
+    let t = concat(inner, c.clone(), &mut f);
+    let t_swapped = concat(inner_swapped, c, &mut f);
+    let strategy = RewriteProcess::Repeat(Box::new(RewriteProcess::AnyChild(
+        SiblingOrder::Leftmost,
+        DepthOrder::Outermost,
+        Box::new(swap()),
+    )));
+    let err = detecting().execute(&strategy, &t, &mut f).unwrap_err();
+    let RewriteExecutionError::CycleDetected(cycle) = err else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!(cycle.terms, vec![t.clone(), t_swapped, t]);
+    assert_eq!(
+        cycle.rule_chains,
+        vec![Some(vec![swap_at(&[0])]), Some(vec![swap_at(&[0])])]
+    );
+}
+
+#[test]
+fn untraced_self_loop_through_pipe() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let strategy = RewriteProcess::Repeat(Box::new(RewriteProcess::Pipe(
+        Box::new(swap()),
+        Box::new(swap()),
+    )));
+    let err = detecting().execute(&strategy, &t, &mut f).unwrap_err();
+    let RewriteExecutionError::CycleDetected(cycle) = err else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!(cycle.terms, vec![t.clone(), t]);
+    assert_eq!(
+        cycle.rule_chains,
+        vec![Some(vec![swap_at(&[]), swap_at(&[])])]
+    );
+}
+
+#[test]
+fn untraced_steps_through_a_nested_repeat_are_not_replayed() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    // The inner Repeat fires nothing, then the swap loops.
+    let strategy = RewriteProcess::Repeat(Box::new(RewriteProcess::Pipe(
+        Box::new(RewriteProcess::Repeat(Box::new(atom_rule(b'a', b'b')))),
+        Box::new(swap()),
+    )));
+    let err = detecting().execute(&strategy, &t, &mut f).unwrap_err();
+    let RewriteExecutionError::CycleDetected(cycle) = &err else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!(cycle.rule_chains, vec![None, None]);
+    assert_eq!(
+        format!("{err}"),
+        "rewriting cycle of length 2 detected: step 0: not replayed; step 1: not replayed"
+    );
+}
+
+#[test]
+fn untraced_replay_spends_the_budget() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let strategy = RewriteProcess::Repeat(Box::new(swap()));
+    // Two swaps close the cycle, leaving one for the replay.
+    let executor = detecting().with_budget(RewriteBudget::new().with_max_rule_applications(3));
+    let err = executor.execute(&strategy, &t, &mut f).unwrap_err();
+    let RewriteExecutionError::CycleDetected(cycle) = err else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!(cycle.rule_chains, vec![Some(vec![swap_at(&[])]), None]);
+}
+
+#[test]
+fn untraced_same_term_on_distinct_branches_is_not_a_cycle() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let d = atom(b'd', &mut f);
+    // a → b → d and a → c → d.
+    let strategy = RewriteProcess::Repeat(Box::new(RewriteProcess::TryAllPaths(vec![
+        atom_rule(b'a', b'b'),
+        atom_rule(b'a', b'c'),
+        atom_rule(b'b', b'd'),
+        atom_rule(b'c', b'd'),
+    ])));
+    assert_eq!(
+        detecting().execute(&strategy, &a, &mut f),
+        Ok(vec![d.clone(), d])
+    );
+}
+
+#[test]
+fn untraced_detection_does_not_change_terminating_results() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let strategy = normalization_strategy();
+    for t in generate_regex_terms(100, 11, &mut f) {
+        let expected = RewriteProcessUntracedExecutor::rewrite(&strategy, &t, &mut f);
+        assert_eq!(detecting().execute(&strategy, &t, &mut f), Ok(expected));
+    }
+}
+
+#[test]
+fn cycle_display() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let strategy = RewriteProcess::Repeat(Box::new(swap()));
+    let err = detecting().execute(&strategy, &t, &mut f).unwrap_err();
+    assert_eq!(
+        format!("{err}"),
+        "rewriting cycle of length 2 detected: step 0: 'swap' at ε; step 1: 'swap' at ε"
+    );
+}
+
+// == traced executor ===========================================================
+
+#[test]
+fn traced_detects_cycle_and_keeps_state() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let t_swapped = alt_ba(&mut f);
+    let mut executor =
+        RewriteProcessTracedExecutor::new(swap(), t.clone(), f).with_cycle_detection(true);
+    executor.progress();
+    assert_eq!(
+        executor.try_progress(),
+        Err(RewriteExecutionError::CycleDetected(RewriteCycle {
+            terms: vec![t.clone(), t_swapped.clone(), t],
+            rule_chains: vec![Some(vec![swap_at(&[])]), Some(vec![swap_at(&[])])],
+        }))
+    );
+    assert_eq!(executor.get_current_terms(), &[t_swapped]);
+}
+
+#[test]
+fn traced_same_term_on_distinct_branches_is_not_a_cycle() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let d = atom(b'd', &mut f);
+    let strategy = RewriteProcess::TryAllPaths(vec![
+        atom_rule(b'a', b'b'),
+        atom_rule(b'a', b'c'),
+        atom_rule(b'b', b'd'),
+        atom_rule(b'c', b'd'),
+    ]);
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, a, f)
+        .with_cycle_detection(true)
+        .with_frontier_deduplication(true);
+    while !executor.get_current_terms().is_empty() {
+        executor.try_progress().unwrap();
+    }
+    assert_eq!(executor.get_completed_terms(), &[d]);
+}
+
+#[test]
+fn traced_longer_cycle_names_every_rule() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let strategy = RewriteProcess::TryOnePath(vec![
+        atom_rule(b'a', b'b'),
+        atom_rule(b'b', b'c'),
+        atom_rule(b'c', b'a'),
+    ]);
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, a, f).with_cycle_detection(true);
+    executor.progress();
+    executor.progress();
+    let err = executor.try_progress().unwrap_err();
+    let RewriteExecutionError::CycleDetected(cycle) = err else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!(cycle.terms.len(), 4);
+    let descs: Vec<&str> = cycle
+        .rule_chains
+        .iter()
+        .map(|chain| chain.as_ref().unwrap()[0].rule_desc.as_str())
+        .collect();
+    assert_eq!(descs, vec!["a→b", "b→c", "c→a"]);
+}
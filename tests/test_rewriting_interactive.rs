@@ -0,0 +1,187 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`InteractiveRewriteExecutor`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use simple_term_rewriter::process::interactive::InteractiveRewriteExecutor;
+use simple_term_rewriter::process::normalizer::Normalizer;
+use simple_term_rewriter::process::redex::enumerate_redexes;
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::process::trace::render_trace;
+use simple_term_rewriter::process::traced::{AtomicRuleApplication, RewriteProcessTracedExecutor};
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn application(rule_index: usize, coordinates: Vec<usize>) -> AtomicRuleApplication {
+    AtomicRuleApplication {
+        left_id: 0,
+        rule_chain: vec![(
+            PositionInRewriteProcess::from_absolute_coordinates(vec![rule_index]),
+            PositionInLanguageTerm::from_absolute_coordinates(coordinates),
+        )],
+        right_id: 0,
+    }
+}
+
+fn all_rules_at_root() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryAllPaths(all_rules().into_iter().map(RewriteProcess::Rule).collect())
+}
+
+// == stepping ==================================================================
+
+#[test]
+fn candidates_are_the_redexes() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    for t in generate_regex_terms(50, 61, &mut f) {
+        let expected = enumerate_redexes(&all_rules(), &t, &mut f);
+        let executor = InteractiveRewriteExecutor::new(all_rules(), t.clone(), f);
+        assert_eq!(executor.get_candidates(), expected.as_slice());
+        assert_eq!(executor.is_normal_form(), expected.is_empty());
+        f = executor.into_factory();
+    }
+}
+
+#[test]
+fn apply_takes_the_chosen_step() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // ·(*(*(∅)), *(ε)): double_star at 0, star_empty at 0_0, star_epsilon at 1.
+    let e = empty(&mut f);
+    let eps = epsilon(&mut f);
+    let t = concat(
+        star(star(e, &mut f), &mut f),
+        star(eps.clone(), &mut f),
+        &mut f,
+    );
+    let mut executor = InteractiveRewriteExecutor::new(all_rules(), t, f);
+    assert_eq!(executor.get_candidates().len(), 3);
+    assert_eq!(executor.apply(1), Some(application(0, vec![0, 0])));
+    // ·(*(ε), *(ε)): star_epsilon at 0 and at 1.
+    assert_eq!(executor.apply(1), Some(application(1, vec![1])));
+    let current = executor.get_current_term().clone();
+    assert_eq!(current.sub_terms[1], eps);
+    // ·(*(ε), ε): star_epsilon at 0, concat_right_epsilon at the root.
+    assert_eq!(executor.get_candidates().len(), 2);
+    assert!(executor.apply(2).is_none());
+    assert_eq!(executor.get_current_term(), &current);
+    assert_eq!(executor.get_trace().len(), 2);
+}
+
+#[test]
+fn undo_and_redo() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let sa = star(a.clone(), &mut f);
+    let ssa = star(sa.clone(), &mut f);
+    let t = star(ssa.clone(), &mut f);
+    let mut executor = InteractiveRewriteExecutor::new(all_rules(), t.clone(), f);
+    assert!(!executor.undo());
+    executor.apply(0).unwrap();
+    executor.apply(0).unwrap();
+    assert_eq!(executor.get_current_term(), &sa);
+    assert!(executor.is_normal_form());
+    assert!(executor.undo());
+    assert!(executor.undo());
+    assert_eq!(executor.get_current_term(), &t);
+    assert_eq!(executor.get_candidates().len(), 2);
+    assert_eq!(executor.redo(), Some(application(2, vec![])));
+    assert_eq!(executor.get_current_term(), &ssa);
+    assert!(executor.can_redo());
+    // A new step forgets the undone one.
+    executor.undo();
+    executor.apply(1).unwrap();
+    assert!(!executor.can_redo());
+    assert!(executor.redo().is_none());
+    assert_eq!(executor.get_derivation(), vec![t, ssa]);
+    assert_eq!(executor.get_trace(), vec![application(2, vec![0])]);
+}
+
+// == comparison with automatic derivations =====================================
+
+#[test]
+fn trace_matches_the_traced_executor() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // Only the root is a redex: |(∅, *(*(a))) then *(*(a)).
+    let a = atom(b'a', &mut f);
+    let e = empty(&mut f);
+    let t = alt(e, star(star(a, &mut f), &mut f), &mut f);
+    let mut traced = RewriteProcessTracedExecutor::new(all_rules_at_root(), t.clone(), f);
+    let mut automatic = vec![];
+    while !traced.get_current_terms().is_empty() {
+        automatic.extend(traced.progress());
+    }
+    let completed = traced.get_completed_terms().to_vec();
+    let mut interactive = InteractiveRewriteExecutor::new(all_rules(), t, traced.into_factory());
+    while !interactive.is_normal_form() {
+        interactive.apply(0).unwrap();
+    }
+    assert_eq!(interactive.get_trace(), automatic);
+    assert_eq!(completed, vec![interactive.get_current_term().clone()]);
+}
+
+#[test]
+fn trace_renders_with_the_rules_as_alternatives() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(epsilon(&mut f), star(empty(&mut f), &mut f), &mut f);
+    let mut executor = InteractiveRewriteExecutor::new(all_rules(), t, f);
+    executor.apply(1).unwrap();
+    executor.apply(0).unwrap();
+    let derivation = executor.get_derivation();
+    let trace = executor.get_trace();
+    let mut f = executor.into_factory();
+    let rendered: String = (0..trace.len())
+        .map(|i| {
+            render_trace(
+                &all_rules_at_root(),
+                &derivation[i..=i],
+                &trace[i..=i],
+                &mut f,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rendered,
+        "0 → 0: ·(ε, *(∅)) ⇒ ·(ε, ε)\n\
+         \x20 rule star(∅)→ε at position 1: *(∅) → ε\n\
+         0 → 0: ·(ε, ε) ⇒ ε\n\
+         \x20 rule concat(ε,r)→r at position ε: ·(ε, ε) → ε\n"
+    );
+}
+
+#[test]
+fn first_candidates_reach_the_normal_forms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let mut normalizer = Normalizer::new(all_rules());
+    for t in generate_regex_terms(100, 67, &mut f) {
+        let normal_form = normalizer.normalize(&t, &mut f);
+        let mut executor = InteractiveRewriteExecutor::new(all_rules(), t, f);
+        while !executor.is_normal_form() {
+            executor.apply(0).unwrap();
+        }
+        assert_eq!(executor.get_current_term(), &normal_form);
+        let derivation = executor.get_derivation();
+        assert_eq!(derivation.len(), executor.get_trace().len() + 1);
+        f = executor.into_factory();
+    }
+}
@@ -0,0 +1,186 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`JoinabilityCheck`].
+
+mod common;
+
+use std::rc::Rc;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::process::budget::{BudgetExhaustion, RewriteBudget};
+use simple_term_rewriter::process::control::RewriteExecutionError;
+use simple_term_rewriter::process::environment::StrategyEnvironment;
+use simple_term_rewriter::process::joinability::{
+    JoinabilityCheck, JoinabilityOutcome, JoinabilityWitness,
+};
+use simple_term_rewriter::process::normalizer::Normalizer;
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::derivation::check_derivation;
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+/// Any rule, anywhere in the term, with every alternative kept.
+fn any_step() -> RewriteProcess<RegexOp> {
+    let mut alternatives: Vec<_> = all_rules().into_iter().map(RewriteProcess::Rule).collect();
+    alternatives.push(RewriteProcess::OneChild(Box::new(RewriteProcess::Call(
+        "step".to_string(),
+    ))));
+    let environment = StrategyEnvironment::new(vec![(
+        "step".to_string(),
+        RewriteProcess::TryAllPaths(alternatives),
+    )])
+    .unwrap();
+    RewriteProcess::let_in(
+        Rc::new(environment),
+        RewriteProcess::Call("step".to_string()),
+    )
+    .unwrap()
+}
+
+fn joinable(
+    left: &LanguageTerm<RegexOp>,
+    right: &LanguageTerm<RegexOp>,
+    f: &mut TermFactory<RegexOp>,
+) -> JoinabilityWitness<RegexOp> {
+    match JoinabilityCheck::new(any_step())
+        .run(left, right, f)
+        .unwrap()
+    {
+        JoinabilityOutcome::Joinable(witness) => witness,
+        other => panic!("not joinable: {:?}", other),
+    }
+}
+
+// == joinable terms ============================================================
+
+#[test]
+fn identical_terms_are_joinable() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(atom(b'a', &mut f), &mut f), &mut f);
+    let witness = joinable(&t, &t, &mut f);
+    assert_eq!(witness.common_reduct, t);
+    assert!(witness.left_derivation.is_empty());
+    assert!(witness.right_derivation.is_empty());
+}
+
+#[test]
+fn a_term_joins_its_reduct() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let sa = star(atom(b'a', &mut f), &mut f);
+    let left = alt(star(sa.clone(), &mut f), empty(&mut f), &mut f);
+    let witness = joinable(&left, &sa, &mut f);
+    assert_eq!(witness.common_reduct, sa);
+    assert_eq!(witness.left_derivation.len(), 2);
+    assert!(witness.right_derivation.is_empty());
+    check_derivation(&any_step(), &witness.left_derivation, &left, &sa, &mut f);
+    // Symmetrically.
+    let witness = joinable(&sa, &left, &mut f);
+    assert!(witness.left_derivation.is_empty());
+    assert_eq!(witness.right_derivation.len(), 2);
+}
+
+#[test]
+fn both_sides_rewrite() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let eps = epsilon(&mut f);
+    let e = empty(&mut f);
+    let left = concat(eps.clone(), star(e.clone(), &mut f), &mut f);
+    let right = alt(star(eps.clone(), &mut f), e, &mut f);
+    let witness = joinable(&left, &right, &mut f);
+    assert_eq!(witness.common_reduct, eps);
+    assert_eq!(witness.left_derivation.len(), 2);
+    assert_eq!(witness.right_derivation.len(), 2);
+    check_derivation(&any_step(), &witness.left_derivation, &left, &eps, &mut f);
+    check_derivation(&any_step(), &witness.right_derivation, &right, &eps, &mut f);
+}
+
+// == other outcomes ============================================================
+
+#[test]
+fn distinct_normal_forms_are_not_joinable() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let left = star(star(atom(b'a', &mut f), &mut f), &mut f);
+    let right = alt(atom(b'b', &mut f), empty(&mut f), &mut f);
+    let outcome = JoinabilityCheck::new(any_step())
+        .run(&left, &right, &mut f)
+        .unwrap();
+    assert_eq!(outcome, JoinabilityOutcome::NotJoinable);
+}
+
+#[test]
+fn budget_exhaustion() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let sa = star(atom(b'a', &mut f), &mut f);
+    let left = star(star(star(sa.clone(), &mut f), &mut f), &mut f);
+    let outcome = JoinabilityCheck::new(any_step())
+        .with_budget(RewriteBudget::new().with_max_rule_applications(2))
+        .run(&left, &sa, &mut f)
+        .unwrap();
+    assert_eq!(
+        outcome,
+        JoinabilityOutcome::BudgetExhausted(BudgetExhaustion::RuleApplications)
+    );
+}
+
+#[test]
+fn errors_are_reported_and_the_factory_is_given_back() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let outcome =
+        JoinabilityCheck::new(RewriteProcess::Call("step".to_string())).run(&a, &b, &mut f);
+    assert_eq!(
+        outcome,
+        Err(RewriteExecutionError::UnboundStrategyName(
+            "step".to_string()
+        ))
+    );
+    assert_eq!(atom(b'a', &mut f), a);
+}
+
+#[test]
+fn joinable_exactly_with_the_same_normal_form() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let mut normalizer = Normalizer::new(all_rules());
+    let terms = generate_regex_terms(40, 71, &mut f);
+    for pair in terms.windows(2) {
+        let (left, right) = (&pair[0], &pair[1]);
+        let same_normal_form =
+            normalizer.normalize(left, &mut f) == normalizer.normalize(right, &mut f);
+        let outcome = JoinabilityCheck::new(any_step())
+            .run(left, right, &mut f)
+            .unwrap();
+        assert_eq!(
+            matches!(outcome, JoinabilityOutcome::Joinable(_)),
+            same_normal_form
+        );
+        let normal_form = normalizer.normalize(left, &mut f);
+        let witness = joinable(left, &normal_form, &mut f);
+        check_derivation(
+            &any_step(),
+            &witness.left_derivation,
+            left,
+            &normal_form,
+            &mut f,
+        );
+    }
+}
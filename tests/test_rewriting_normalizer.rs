@@ -0,0 +1,187 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for the memoised [`Normalizer`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::process::normalizer::{Normalizer, NormalizerStatistics};
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+/// `Repeat(TryOnePath([AnyChild(Leftmost, Innermost, rules), rules]))`
+fn innermost_strategy() -> RewriteProcess<RegexOp> {
+    let rules =
+        || RewriteProcess::TryOnePath(all_rules().into_iter().map(RewriteProcess::Rule).collect());
+    RewriteProcess::Repeat(Box::new(RewriteProcess::TryOnePath(vec![
+        RewriteProcess::AnyChild(
+            SiblingOrder::Leftmost,
+            DepthOrder::Innermost,
+            Box::new(rules()),
+        ),
+        rules(),
+    ])))
+}
+
+/// `Star(Star(∅))`: normalises to `ε` in two steps.
+fn double_star_empty(f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let e = empty(f);
+    let s = star(e, f);
+    star(s, f)
+}
+
+// == normal forms ==============================================================
+
+#[test]
+fn same_normal_forms_as_innermost_strategy() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let strategy = innermost_strategy();
+    let mut normalizer = Normalizer::new(all_rules());
+    for t in generate_regex_terms(200, 3, &mut f) {
+        let expected = RewriteProcessUntracedExecutor::rewrite(&strategy, &t, &mut f);
+        assert_eq!(vec![normalizer.normalize(&t, &mut f)], expected);
+    }
+}
+
+#[test]
+fn irreducible_term_is_its_own_normal_form() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let t = alt(a, b, &mut f);
+    let mut normalizer = Normalizer::new(all_rules());
+    assert_eq!(normalizer.normalize(&t, &mut f), t);
+    assert_eq!(normalizer.get_statistics().rule_applications, 0);
+}
+
+// == caching ===================================================================
+
+#[test]
+fn shared_sub_terms_are_normalised_once() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let x = double_star_empty(&mut f);
+    let t = concat(x.clone(), x, &mut f);
+    let mut normalizer = Normalizer::new(all_rules());
+    assert_eq!(normalizer.normalize(&t, &mut f), epsilon(&mut f));
+    // Two steps for the first `Star(Star(∅))`, none for the second one, one for
+    // `Concat(ε, ε)`.
+    assert_eq!(normalizer.get_statistics().rule_applications, 3);
+    assert!(normalizer.get_statistics().cache_hits > 0);
+}
+
+#[test]
+fn repeated_calls_hit_the_cache() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let terms = generate_regex_terms(100, 5, &mut f);
+    let mut normalizer = Normalizer::new(all_rules());
+    let first: Vec<_> = terms
+        .iter()
+        .map(|t| normalizer.normalize(t, &mut f))
+        .collect();
+    normalizer.reset_statistics();
+    let second: Vec<_> = terms
+        .iter()
+        .map(|t| normalizer.normalize(t, &mut f))
+        .collect();
+    assert_eq!(first, second);
+    assert_eq!(
+        normalizer.get_statistics(),
+        NormalizerStatistics {
+            cache_hits: terms.len(),
+            cache_misses: 0,
+            rule_applications: 0,
+        }
+    );
+}
+
+#[test]
+fn cache_is_shared_across_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let x = double_star_empty(&mut f);
+    let a = atom(b'a', &mut f);
+    let t1 = concat(x.clone(), a.clone(), &mut f);
+    let t2 = alt(x.clone(), a, &mut f);
+    let mut normalizer = Normalizer::new(all_rules());
+    normalizer.normalize(&t1, &mut f);
+    assert_eq!(
+        normalizer.get_cached_normal_form(&x),
+        Some(&epsilon(&mut f))
+    );
+    let applications = normalizer.get_statistics().rule_applications;
+    normalizer.normalize(&t2, &mut f);
+    // `Alt(ε, a)` is irreducible: nothing fires for `x` again.
+    assert_eq!(normalizer.get_statistics().rule_applications, applications);
+}
+
+// == invalidation ==============================================================
+
+#[test]
+fn clear_cache_keeps_statistics() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = double_star_empty(&mut f);
+    let mut normalizer = Normalizer::new(all_rules());
+    normalizer.normalize(&t, &mut f);
+    let statistics = normalizer.get_statistics();
+    assert!(normalizer.get_cache_size() > 0);
+    normalizer.clear_cache();
+    assert_eq!(normalizer.get_cache_size(), 0);
+    assert_eq!(normalizer.get_statistics(), statistics);
+    normalizer.normalize(&t, &mut f);
+    assert_eq!(
+        normalizer.get_statistics().rule_applications,
+        2 * statistics.rule_applications
+    );
+}
+
+#[test]
+fn invalidate_removes_dependent_entries_only() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let x = double_star_empty(&mut f);
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let t1 = concat(x.clone(), a.clone(), &mut f);
+    let t2 = alt(a, b, &mut f);
+    let mut normalizer = Normalizer::new(all_rules());
+    normalizer.normalize(&t1, &mut f);
+    normalizer.normalize(&t2, &mut f);
+    let size = normalizer.get_cache_size();
+    // `x`, `Star(∅)`, `Star(ε)`, `ε` (all normalising to `ε`) and `t1`.
+    assert_eq!(normalizer.invalidate(&x), 5);
+    assert_eq!(normalizer.get_cache_size(), size - 5);
+    assert!(normalizer.get_cached_normal_form(&x).is_none());
+    assert!(normalizer.get_cached_normal_form(&t1).is_none());
+    assert_eq!(normalizer.get_cached_normal_form(&t2), Some(&t2));
+}
+
+#[test]
+fn set_rules_clears_the_cache() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = double_star_empty(&mut f);
+    let mut normalizer = Normalizer::new(all_rules());
+    assert_eq!(normalizer.normalize(&t, &mut f), epsilon(&mut f));
+    normalizer.set_rules(vec![Box::new(rule_double_star())]);
+    assert_eq!(normalizer.get_cache_size(), 0);
+    let e = empty(&mut f);
+    assert_eq!(normalizer.normalize(&t, &mut f), star(e, &mut f));
+}
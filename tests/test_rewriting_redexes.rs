@@ -0,0 +1,145 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`enumerate_redexes`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::PositionInLanguageTerm;
+use simple_term_rewriter::process::normalizer::Normalizer;
+use simple_term_rewriter::process::redex::enumerate_redexes;
+use simple_term_rewriter::rule::RewriteRule;
+use simple_term_rewriter::rules::combinators::guard::RootOnlyRewriteApplicationGuard;
+use simple_term_rewriter::rules::combinators::guarded::GuardedRule;
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn tp(coordinates: Vec<usize>) -> PositionInLanguageTerm {
+    PositionInLanguageTerm::from_absolute_coordinates(coordinates)
+}
+
+// == enumeration ===============================================================
+
+#[test]
+fn every_rule_at_every_position_in_preorder() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // ·(*(*(∅)), |(∅, ∅))
+    let e = empty(&mut f);
+    let se = star(e.clone(), &mut f);
+    let t = concat(
+        star(se.clone(), &mut f),
+        alt(e.clone(), e.clone(), &mut f),
+        &mut f,
+    );
+    let redexes = enumerate_redexes(&all_rules(), &t, &mut f);
+    let found: Vec<(usize, PositionInLanguageTerm)> = redexes
+        .iter()
+        .map(|redex| (redex.rule_index, redex.position.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (2, tp(vec![0])),
+            (0, tp(vec![0, 0])),
+            (7, tp(vec![1])),
+            (8, tp(vec![1])),
+            (9, tp(vec![1])),
+        ]
+    );
+    assert_eq!(redexes[0].contractum, se);
+    assert!(redexes[2..].iter().all(|redex| redex.contractum == e));
+}
+
+#[test]
+fn results_are_whole_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let e = empty(&mut f);
+    let a = atom(b'a', &mut f);
+    let t = concat(a.clone(), star(e, &mut f), &mut f);
+    let redexes = enumerate_redexes(&all_rules(), &t, &mut f);
+    assert_eq!(redexes.len(), 1);
+    assert_eq!(redexes[0].position, tp(vec![1]));
+    assert_eq!(redexes[0].contractum, epsilon(&mut f));
+    assert_eq!(redexes[0].result, concat(a, epsilon(&mut f), &mut f));
+}
+
+#[test]
+fn nothing_to_enumerate() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let t = star(a.clone(), &mut f);
+    assert!(enumerate_redexes(&all_rules(), &t, &mut f).is_empty());
+    let no_rules: Vec<Box<dyn RewriteRule<RegexOp>>> = vec![];
+    let t = star(star(a, &mut f), &mut f);
+    assert!(enumerate_redexes(&no_rules, &t, &mut f).is_empty());
+}
+
+// == guards ====================================================================
+
+#[test]
+fn guards_are_honored() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(star(atom(b'a', &mut f), &mut f), &mut f), &mut f);
+    let unguarded = enumerate_redexes(&all_rules(), &t, &mut f);
+    let positions: Vec<_> = unguarded
+        .iter()
+        .map(|redex| redex.position.clone())
+        .collect();
+    assert_eq!(positions, vec![tp(vec![]), tp(vec![0])]);
+    let guarded: Vec<Box<dyn RewriteRule<RegexOp>>> = vec![Box::new(GuardedRule::new(
+        rule_double_star(),
+        RootOnlyRewriteApplicationGuard,
+    ))];
+    let redexes = enumerate_redexes(&guarded, &t, &mut f);
+    assert_eq!(redexes.len(), 1);
+    assert_eq!(redexes[0].position, tp(vec![]));
+}
+
+// == agreement with strategies =================================================
+
+#[test]
+fn no_redex_exactly_on_normal_forms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let mut normalizer = Normalizer::new(all_rules());
+    for t in generate_regex_terms(200, 53, &mut f) {
+        let normal_form = normalizer.normalize(&t, &mut f);
+        assert_eq!(
+            enumerate_redexes(&all_rules(), &t, &mut f).is_empty(),
+            normal_form == t
+        );
+        assert!(enumerate_redexes(&all_rules(), &normal_form, &mut f).is_empty());
+    }
+}
+
+#[test]
+fn strategy_steps_are_among_the_redexes() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    for t in generate_regex_terms(200, 59, &mut f) {
+        let results: Vec<_> = enumerate_redexes(&all_rules(), &t, &mut f)
+            .into_iter()
+            .map(|redex| redex.result)
+            .collect();
+        let steps = rewrite(one_step_outermost(), t, &mut f);
+        assert_eq!(steps.is_empty(), results.is_empty());
+        assert!(steps.iter().all(|step| results.contains(step)));
+    }
+}
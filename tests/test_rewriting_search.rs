@@ -0,0 +1,285 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`ReachabilitySearch`].
+
+mod common;
+
+use std::rc::Rc;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::process::budget::{BudgetExhaustion, RewriteBudget};
+use simple_term_rewriter::process::control::RewriteExecutionError;
+use simple_term_rewriter::process::environment::StrategyEnvironment;
+use simple_term_rewriter::process::search::{
+    DerivationDirection, DerivationStep, ReachabilityOutcome, ReachabilitySearch,
+};
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::rules::primitives::pattern::PatternRule;
+use simple_term_rewriter::term::pattern::Pattern;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::derivation::check_derivation;
+use common::regex::constructors::*;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn var(name: &str) -> Pattern<RegexOp> {
+    Pattern::var(name)
+}
+
+fn p_alt(l: Pattern<RegexOp>, r: Pattern<RegexOp>) -> Pattern<RegexOp> {
+    Pattern::op(RegexOp::Alt, vec![l, r])
+}
+
+/// `Alt` commutativity and associativity in both directions, anywhere in the
+/// term.  The relation is symmetric, so the strategy is its own backward
+/// strategy.
+fn alt_ac_step() -> RewriteProcess<RegexOp> {
+    let rule = |desc: &str, lhs, rhs| {
+        RewriteProcess::Rule(Box::new(PatternRule::new(desc, lhs, rhs).unwrap()))
+    };
+    let environment = StrategyEnvironment::new(vec![(
+        "step".to_string(),
+        RewriteProcess::TryAllPaths(vec![
+            rule("comm", p_alt(var("x"), var("y")), p_alt(var("y"), var("x"))),
+            rule(
+                "assoc",
+                p_alt(p_alt(var("x"), var("y")), var("z")),
+                p_alt(var("x"), p_alt(var("y"), var("z"))),
+            ),
+            rule(
+                "unassoc",
+                p_alt(var("x"), p_alt(var("y"), var("z"))),
+                p_alt(p_alt(var("x"), var("y")), var("z")),
+            ),
+            RewriteProcess::OneChild(Box::new(RewriteProcess::Call("step".to_string()))),
+        ]),
+    )])
+    .unwrap();
+    RewriteProcess::let_in(
+        Rc::new(environment),
+        RewriteProcess::Call("step".to_string()),
+    )
+    .unwrap()
+}
+
+fn atoms(letters: &[u8], f: &mut TermFactory<RegexOp>) -> Vec<LanguageTerm<RegexOp>> {
+    letters.iter().map(|&c| atom(c, f)).collect()
+}
+
+fn found(outcome: ReachabilityOutcome<RegexOp>) -> Vec<DerivationStep<RegexOp>> {
+    match outcome {
+        ReachabilityOutcome::Found(derivation) => derivation,
+        other => panic!("no derivation found: {:?}", other),
+    }
+}
+
+// == forward search ============================================================
+
+#[test]
+fn a_term_reaches_itself() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(atom(b'a', &mut f), &mut f);
+    let outcome = ReachabilitySearch::new(alt_ac_step())
+        .search(&t, &t, &mut f)
+        .unwrap();
+    assert_eq!(outcome, ReachabilityOutcome::Found(vec![]));
+}
+
+#[test]
+fn shortest_derivations() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let [a, b, c] = <[_; 3]>::try_from(atoms(b"abc", &mut f)).unwrap();
+    let source = alt(a.clone(), alt(b.clone(), c.clone(), &mut f), &mut f);
+    let search = ReachabilitySearch::new(alt_ac_step());
+    // |(a, |(b, c)) → |(|(a, b), c) → |(c, |(a, b))
+    let ab = alt(a.clone(), b.clone(), &mut f);
+    for (target, length) in [
+        (alt(ab.clone(), c.clone(), &mut f), 1),
+        (alt(c.clone(), ab, &mut f), 2),
+        (alt(a, alt(c, b, &mut f), &mut f), 1),
+    ] {
+        let derivation = found(search.search(&source, &target, &mut f).unwrap());
+        assert_eq!(derivation.len(), length);
+        assert!(derivation
+            .iter()
+            .all(|step| step.direction == DerivationDirection::Forward));
+        check_derivation(&alt_ac_step(), &derivation, &source, &target, &mut f);
+    }
+}
+
+#[test]
+fn unreachable_targets() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let source = star(star(a.clone(), &mut f), &mut f);
+    let target = star(atom(b'b', &mut f), &mut f);
+    let strategy = RewriteProcess::TryAllPaths(vec![
+        RewriteProcess::outermost_step(rule_as_process(rule_double_star())),
+        rule_as_process(rule_star_empty()),
+    ]);
+    let outcome = ReachabilitySearch::new(strategy)
+        .search(&source, &target, &mut f)
+        .unwrap();
+    assert_eq!(outcome, ReachabilityOutcome::Unreachable);
+    // Letters are never exchanged with one another.
+    let [a, b, c] = <[_; 3]>::try_from(atoms(b"abc", &mut f)).unwrap();
+    let source = alt(a.clone(), alt(b.clone(), c.clone(), &mut f), &mut f);
+    let target = alt(a.clone(), alt(b, a, &mut f), &mut f);
+    let outcome = ReachabilitySearch::new(alt_ac_step())
+        .search(&source, &target, &mut f)
+        .unwrap();
+    assert_eq!(outcome, ReachabilityOutcome::Unreachable);
+}
+
+// == limits ====================================================================
+
+#[test]
+fn depth_limit() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let [a, b, c] = <[_; 3]>::try_from(atoms(b"abc", &mut f)).unwrap();
+    let source = alt(a.clone(), alt(b.clone(), c.clone(), &mut f), &mut f);
+    let target = alt(c, alt(a, b, &mut f), &mut f);
+    let outcome = ReachabilitySearch::new(alt_ac_step())
+        .with_max_depth(1)
+        .search(&source, &target, &mut f)
+        .unwrap();
+    assert_eq!(outcome, ReachabilityOutcome::DepthLimitReached);
+    let outcome = ReachabilitySearch::new(alt_ac_step())
+        .with_max_depth(2)
+        .search(&source, &target, &mut f)
+        .unwrap();
+    assert_eq!(found(outcome).len(), 2);
+}
+
+#[test]
+fn budget_limits() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let [a, b, c, d] = <[_; 4]>::try_from(atoms(b"abcd", &mut f)).unwrap();
+    let source = alt(
+        a.clone(),
+        alt(b.clone(), alt(c.clone(), d.clone(), &mut f), &mut f),
+        &mut f,
+    );
+    let target = alt(d, alt(c, alt(b, a, &mut f), &mut f), &mut f);
+    let outcome = ReachabilitySearch::new(alt_ac_step())
+        .with_budget(RewriteBudget::new().with_max_rule_applications(20))
+        .search(&source, &target, &mut f)
+        .unwrap();
+    assert_eq!(
+        outcome,
+        ReachabilityOutcome::BudgetExhausted(BudgetExhaustion::RuleApplications)
+    );
+    let outcome = ReachabilitySearch::new(alt_ac_step())
+        .with_budget(RewriteBudget::new().with_max_frontier_size(10))
+        .search(&source, &target, &mut f)
+        .unwrap();
+    assert_eq!(
+        outcome,
+        ReachabilityOutcome::BudgetExhausted(BudgetExhaustion::FrontierSize)
+    );
+}
+
+#[test]
+fn a_meeting_found_before_the_budget_runs_out_is_returned() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let [a, b, c] = <[_; 3]>::try_from(atoms(b"abc", &mut f)).unwrap();
+    let source = alt(a.clone(), alt(b.clone(), c.clone(), &mut f), &mut f);
+    // Met by expanding |(|(b, c), a), the first term of the second layer,
+    // while the budget runs out on the rest of the layer.
+    let target = alt(alt(c, b, &mut f), a, &mut f);
+    let outcome = ReachabilitySearch::new(alt_ac_step())
+        .with_budget(RewriteBudget::new().with_max_rule_applications(6))
+        .search(&source, &target, &mut f)
+        .unwrap();
+    let derivation = found(outcome);
+    assert_eq!(derivation.len(), 2);
+    check_derivation(&alt_ac_step(), &derivation, &source, &target, &mut f);
+}
+
+#[test]
+fn strategy_errors_are_reported() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = atom(b'a', &mut f);
+    let u = atom(b'b', &mut f);
+    let outcome =
+        ReachabilitySearch::new(RewriteProcess::Call("step".to_string())).search(&t, &u, &mut f);
+    assert_eq!(
+        outcome,
+        Err(RewriteExecutionError::UnboundStrategyName(
+            "step".to_string()
+        ))
+    );
+}
+
+// == bidirectional search ======================================================
+
+#[test]
+fn bidirectional_search_finds_derivations_as_short() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let [a, b, c, d] = <[_; 4]>::try_from(atoms(b"abcd", &mut f)).unwrap();
+    let source = alt(
+        a.clone(),
+        alt(b.clone(), alt(c.clone(), d.clone(), &mut f), &mut f),
+        &mut f,
+    );
+    let targets = [
+        alt(
+            d.clone(),
+            alt(c.clone(), alt(b.clone(), a.clone(), &mut f), &mut f),
+            &mut f,
+        ),
+        alt(
+            alt(b.clone(), a.clone(), &mut f),
+            alt(d.clone(), c.clone(), &mut f),
+            &mut f,
+        ),
+        alt(
+            alt(alt(c.clone(), a.clone(), &mut f), d.clone(), &mut f),
+            b.clone(),
+            &mut f,
+        ),
+    ];
+    let forward = ReachabilitySearch::new(alt_ac_step());
+    let bidirectional =
+        ReachabilitySearch::new(alt_ac_step()).with_backward_strategy(alt_ac_step());
+    let mut used_backward_steps = false;
+    for target in targets {
+        let one_way = found(forward.search(&source, &target, &mut f).unwrap());
+        let two_ways = found(bidirectional.search(&source, &target, &mut f).unwrap());
+        assert_eq!(one_way.len(), two_ways.len());
+        check_derivation(&alt_ac_step(), &two_ways, &source, &target, &mut f);
+        used_backward_steps |= two_ways
+            .iter()
+            .any(|step| step.direction == DerivationDirection::Backward);
+    }
+    assert!(used_backward_steps);
+}
+
+#[test]
+fn bidirectional_search_detects_unreachability() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let [a, b] = <[_; 2]>::try_from(atoms(b"ab", &mut f)).unwrap();
+    let source = alt(a.clone(), b.clone(), &mut f);
+    let target = alt(a.clone(), a, &mut f);
+    let outcome = ReachabilitySearch::new(alt_ac_step())
+        .with_backward_strategy(alt_ac_step())
+        .search(&source, &target, &mut f)
+        .unwrap();
+    assert_eq!(outcome, ReachabilityOutcome::Unreachable);
+}
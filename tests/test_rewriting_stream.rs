@@ -0,0 +1,198 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for the lazy
+//! [`RewriteProcessUntracedExecutor::stream`] evaluation.
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::process::budget::{BudgetExhaustion, RewriteBudget};
+use simple_term_rewriter::process::control::RewriteExecutionError;
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::rule::ClosureRewriteRule;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn any_rule() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryAllPaths(all_rules().into_iter().map(RewriteProcess::Rule).collect())
+}
+
+/// Every rule, at the root or in any child, in every order: explores all
+/// rewriting paths.
+fn exploration_strategy() -> RewriteProcess<RegexOp> {
+    RewriteProcess::Repeat(Box::new(RewriteProcess::TryAllPaths(vec![
+        any_rule(),
+        RewriteProcess::AnyChild(
+            SiblingOrder::Leftmost,
+            DepthOrder::Innermost,
+            Box::new(any_rule()),
+        ),
+        RewriteProcess::AnyChild(
+            SiblingOrder::Rightmost,
+            DepthOrder::Outermost,
+            Box::new(any_rule()),
+        ),
+    ])))
+}
+
+/// `x → Concat(x, x)`: never reaches a fixpoint.
+fn duplicate() -> RewriteProcess<RegexOp> {
+    rule_as_process(ClosureRewriteRule::new("duplicate", |t, _, _, f| {
+        Some(concat(t.clone(), t.clone(), f))
+    }))
+}
+
+fn alt_ab(f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let a = atom(b'a', f);
+    let b = atom(b'b', f);
+    alt(a, b, f)
+}
+
+// == same results as the eager evaluation ======================================
+
+#[test]
+fn stream_matches_execute_on_normalization() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let executor = RewriteProcessUntracedExecutor::new();
+    let strategy = normalization_strategy();
+    for t in generate_regex_terms(100, 11, &mut f) {
+        let expected = executor.execute(&strategy, &t, &mut f).unwrap();
+        let streamed: Vec<_> = executor.stream(&strategy, &t, &mut f).collect();
+        assert_eq!(streamed, expected);
+    }
+}
+
+#[test]
+fn stream_matches_execute_on_exploration() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let executor = RewriteProcessUntracedExecutor::new();
+    let strategy = exploration_strategy();
+    for t in generate_regex_terms(50, 7, &mut f) {
+        let expected = executor.execute(&strategy, &t, &mut f).unwrap();
+        let streamed: Vec<_> = executor.stream(&strategy, &t, &mut f).collect();
+        assert_eq!(streamed, expected);
+    }
+}
+
+#[test]
+fn stream_matches_execute_through_pipe() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let executor = RewriteProcessUntracedExecutor::new();
+    let strategy = RewriteProcess::Pipe(
+        Box::new(RewriteProcess::TryAllPaths(vec![
+            atom_rule(b'a', b'b'),
+            atom_rule(b'a', b'c'),
+        ])),
+        Box::new(RewriteProcess::TryAllPaths(vec![
+            atom_rule(b'b', b'd'),
+            atom_rule(b'b', b'e'),
+            atom_rule(b'c', b'f'),
+        ])),
+    );
+    let a = atom(b'a', &mut f);
+    let expected = executor.execute(&strategy, &a, &mut f).unwrap();
+    assert_eq!(expected.len(), 3);
+    let streamed: Vec<_> = executor.stream(&strategy, &a, &mut f).collect();
+    assert_eq!(streamed, expected);
+}
+
+// == laziness ==================================================================
+
+#[test]
+fn first_result_only_fires_what_it_needs() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let strategy = RewriteProcess::TryAllPaths(vec![swap(), swap(), swap(), swap(), swap()]);
+    let executor = RewriteProcessUntracedExecutor::new();
+    let mut stream = executor.stream(&strategy, &t, &mut f);
+    assert!(stream.next().is_some());
+    assert_eq!(stream.get_rule_applications(), 1);
+    assert_eq!(stream.by_ref().count(), 4);
+    assert_eq!(stream.get_rule_applications(), 5);
+}
+
+#[test]
+fn take_stops_before_a_diverging_alternative() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    // The second alternative never terminates.
+    let strategy = RewriteProcess::TryAllPaths(vec![
+        atom_rule(b'a', b'b'),
+        RewriteProcess::Repeat(Box::new(duplicate())),
+    ]);
+    let executor = RewriteProcessUntracedExecutor::new();
+    let first: Vec<_> = executor.stream(&strategy, &a, &mut f).take(1).collect();
+    assert_eq!(first, vec![b]);
+}
+
+#[test]
+fn find_stops_on_a_predicate() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let c = atom(b'c', &mut f);
+    let strategy = RewriteProcess::TryAllPaths(vec![
+        atom_rule(b'a', b'b'),
+        atom_rule(b'a', b'c'),
+        atom_rule(b'a', b'd'),
+    ]);
+    let executor = RewriteProcessUntracedExecutor::new();
+    let mut stream = executor.stream(&strategy, &a, &mut f);
+    assert_eq!(stream.find(|t| *t == c), Some(c));
+    assert_eq!(stream.get_rule_applications(), 2);
+}
+
+// == checks and budget =========================================================
+
+#[test]
+fn budget_ends_the_stream_with_partial_results() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let b = atom(b'b', &mut f);
+    let a = atom(b'a', &mut f);
+    let swapped = alt(b, a, &mut f);
+    let strategy = RewriteProcess::Repeat(Box::new(swap()));
+    let executor = RewriteProcessUntracedExecutor::new()
+        .with_budget(RewriteBudget::new().with_max_rule_applications(7));
+    let mut stream = executor.stream(&strategy, &t, &mut f);
+    let partial: Vec<_> = stream.by_ref().collect();
+    assert_eq!(partial, vec![swapped]);
+    assert_eq!(
+        stream.get_exhaustion(),
+        Some(BudgetExhaustion::RuleApplications)
+    );
+    assert!(stream.get_error().is_none());
+}
+
+#[test]
+fn cycle_detection_ends_the_stream_with_an_error() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt_ab(&mut f);
+    let strategy = RewriteProcess::Repeat(Box::new(swap()));
+    let executor = RewriteProcessUntracedExecutor::new().with_cycle_detection(true);
+    let expected = executor.execute(&strategy, &t, &mut f).unwrap_err();
+    assert!(matches!(expected, RewriteExecutionError::CycleDetected(_)));
+    let mut stream = executor.stream(&strategy, &t, &mut f);
+    assert_eq!(stream.next(), None);
+    assert_eq!(stream.get_error(), Some(&expected));
+}
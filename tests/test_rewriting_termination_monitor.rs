@@ -0,0 +1,276 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for the termination monitors: the `TerminationMonitoredRule` wrapper
+//! and the executors' `with_termination_monitor` option.
+
+mod common;
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::metrics::builtin::tree_size;
+use simple_term_rewriter::position::PositionInLanguageTerm;
+use simple_term_rewriter::process::budget::RewriteOutcome;
+use simple_term_rewriter::process::control::{RewriteExecutionError, TerminationViolation};
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::rule::{ClosureRewriteRule, RewriteRule};
+use simple_term_rewriter::rules::combinators::monitored::TerminationMonitoredRule;
+use simple_term_rewriter::rules::util::lpo::LexicographicPathOrdering;
+use simple_term_rewriter::rules::util::ordering::{MeasureOrdering, TermOrdering};
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn pos(coords: &[usize]) -> PositionInLanguageTerm {
+    PositionInLanguageTerm::from_absolute_coordinates(coords.to_vec())
+}
+
+/// `Star(x) → Concat(x, Star(x))`: grows the term.
+fn unfold() -> ClosureRewriteRule<RegexOp> {
+    ClosureRewriteRule::new("unfold", |t, _, _, f| {
+        if t.operator != RegexOp::Star {
+            return None;
+        }
+        Some(concat(t.sub_terms[0].clone(), t.clone(), f))
+    })
+}
+
+/// `Concat(x, Alt(y, z)) → Alt(Concat(x, y), Concat(x, z))`
+fn distribute() -> ClosureRewriteRule<RegexOp> {
+    ClosureRewriteRule::new("distribute", |t, _, _, f| {
+        if t.operator != RegexOp::Concat || t.sub_terms[1].operator != RegexOp::Alt {
+            return None;
+        }
+        let x = t.sub_terms[0].clone();
+        let (y, z) = (
+            t.sub_terms[1].sub_terms[0].clone(),
+            t.sub_terms[1].sub_terms[1].clone(),
+        );
+        let xy = concat(x.clone(), y, f);
+        let xz = concat(x, z, f);
+        Some(alt(xy, xz, f))
+    })
+}
+
+/// Precedence with `Concat > Alt` when `concat_first`, `Alt > Concat` otherwise.
+fn lpo(concat_first: bool) -> LexicographicPathOrdering<RegexOp> {
+    LexicographicPathOrdering::new(move |x: &RegexOp, y: &RegexOp| {
+        let rank = |op: &RegexOp| match op {
+            RegexOp::Atom(c) => 5 + (*c as u16),
+            RegexOp::Alt if concat_first => 3,
+            RegexOp::Alt => 4,
+            RegexOp::Concat if concat_first => 4,
+            RegexOp::Concat => 3,
+            RegexOp::Star => 2,
+            RegexOp::Epsilon => 1,
+            RegexOp::Empty => 0,
+        };
+        rank(x).cmp(&rank(y))
+    })
+}
+
+fn anywhere(rule: impl RewriteRule<RegexOp> + 'static) -> RewriteProcess<RegexOp> {
+    RewriteProcess::AnyChild(
+        SiblingOrder::Leftmost,
+        DepthOrder::Outermost,
+        Box::new(rule_as_process(rule)),
+    )
+}
+
+// == measure ordering ==========================================================
+
+#[test]
+fn measure_ordering_compares_measures() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let ordering = MeasureOrdering::new(tree_size);
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let sa = star(a.clone(), &mut f);
+    assert!(ordering.is_greater(&sa, &a));
+    assert_eq!(ordering.compare_terms(&a, &sa), Ordering::Less);
+    // Terms of equal measure are equivalent.
+    assert_eq!(ordering.compare_terms(&a, &b), Ordering::Equal);
+}
+
+// == executor option ===========================================================
+
+#[test]
+fn monitored_normalization_of_random_terms_is_unchanged() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    for t in generate_regex_terms(30, 137, &mut f) {
+        let expected = rewrite(normalization_strategy(), t.clone(), &mut f);
+        let mut executor = RewriteProcessTracedExecutor::new(one_step_outermost(), t, f)
+            .with_termination_monitor(MeasureOrdering::new(tree_size));
+        assert_eq!(
+            executor.run_within_budget(),
+            Ok(RewriteOutcome::Finished(expected))
+        );
+        f = executor.into_factory();
+    }
+}
+
+#[test]
+fn executor_reports_a_growing_step() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let sa = star(a.clone(), &mut f);
+    let t = alt(a.clone(), sa.clone(), &mut f);
+    let unfolded = concat(a, sa.clone(), &mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(anywhere(unfold()), t.clone(), f)
+        .with_termination_monitor(MeasureOrdering::new(tree_size));
+    let error = executor.try_progress().unwrap_err();
+    assert_eq!(
+        error,
+        RewriteExecutionError::NonDecreasingRuleApplication(TerminationViolation {
+            rule_desc: "unfold".to_string(),
+            position: pos(&[1]),
+            redex: sa,
+            output: unfolded,
+        })
+    );
+    assert_eq!(
+        error.to_string(),
+        "rule 'unfold' fired at position 1 without decreasing the rewritten sub-term"
+    );
+    // The executor is left as it was.
+    assert_eq!(executor.get_current_terms(), &[t]);
+}
+
+#[test]
+fn executor_monitor_depends_on_the_ordering() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let e = epsilon(&mut f);
+    let b_or_e = alt(b, e, &mut f);
+    let t = concat(a, b_or_e, &mut f);
+    let strategy = RewriteProcess::Repeat(Box::new(rule_as_process(distribute())));
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, t.clone(), f)
+        .with_termination_monitor(lpo(true));
+    assert!(executor.run_within_budget().unwrap().is_finished());
+    let strategy = RewriteProcess::Repeat(Box::new(rule_as_process(distribute())));
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, t, executor.into_factory())
+        .with_termination_monitor(lpo(false));
+    let Err(RewriteExecutionError::NonDecreasingRuleApplication(violation)) =
+        executor.run_within_budget()
+    else {
+        panic!("expected a termination violation");
+    };
+    assert_eq!(violation.rule_desc, "distribute");
+}
+
+#[test]
+fn untraced_executor_reports_a_growing_step() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let sa = star(a.clone(), &mut f);
+    let t = alt(a.clone(), sa.clone(), &mut f);
+    let unfolded = concat(a, sa.clone(), &mut f);
+    let violation = RewriteExecutionError::NonDecreasingRuleApplication(TerminationViolation {
+        rule_desc: "unfold".to_string(),
+        position: pos(&[1]),
+        redex: sa,
+        output: unfolded,
+    });
+    let executor = RewriteProcessUntracedExecutor::new()
+        .with_termination_monitor(MeasureOrdering::new(tree_size));
+    let strategy = anywhere(unfold());
+    assert_eq!(
+        executor.execute(&strategy, &t, &mut f),
+        Err(violation.clone())
+    );
+    let mut stream = executor.stream(&strategy, &t, &mut f);
+    assert_eq!(stream.next(), None);
+    assert_eq!(stream.get_error(), Some(&violation));
+    // Decreasing rules pass the monitor.
+    for t in generate_regex_terms(30, 141, &mut f) {
+        let expected = rewrite(normalization_strategy(), t.clone(), &mut f);
+        assert_eq!(
+            executor.execute(&normalization_strategy(), &t, &mut f),
+            Ok(expected)
+        );
+    }
+}
+
+// == rule wrapper ==============================================================
+
+#[test]
+fn monitored_rule_behaves_as_the_inner_rule() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let root = PositionInLanguageTerm::get_root_position();
+    let rule = TerminationMonitoredRule::new(rule_double_star(), MeasureOrdering::new(tree_size));
+    assert_eq!(rule.get_desc(), rule_double_star().get_desc());
+    for t in generate_regex_terms(50, 139, &mut f) {
+        assert_eq!(
+            rule.try_apply(&t, &t, &root, &mut f),
+            rule_double_star().try_apply(&t, &t, &root, &mut f)
+        );
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn violation_handler_receives_the_violations() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let violations: Rc<RefCell<Vec<TerminationViolation<RegexOp>>>> = Rc::default();
+    let recorded = violations.clone();
+    let rule = TerminationMonitoredRule::new(unfold(), MeasureOrdering::new(tree_size))
+        .with_violation_handler(move |violation| recorded.borrow_mut().push(violation.clone()));
+    let a = atom(b'a', &mut f);
+    let sa = star(a.clone(), &mut f);
+    let t = alt(a.clone(), sa.clone(), &mut f);
+    let unfolded = concat(a.clone(), sa.clone(), &mut f);
+    // The output is returned anyway.
+    assert_eq!(
+        rule.try_apply(&sa, &t, &pos(&[1]), &mut f),
+        Some(unfolded.clone())
+    );
+    assert_eq!(rule.try_apply(&a, &t, &pos(&[0]), &mut f), None);
+    assert_eq!(
+        *violations.borrow(),
+        vec![TerminationViolation {
+            rule_desc: "unfold".to_string(),
+            position: pos(&[1]),
+            redex: sa,
+            output: unfolded,
+        }]
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(
+    expected = "rule 'unfold' fired at position ε without decreasing the rewritten sub-term"
+)]
+fn monitored_rule_panics_by_default() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let sa = star(a, &mut f);
+    let strategy = rule_as_process(TerminationMonitoredRule::new(
+        unfold(),
+        MeasureOrdering::new(tree_size),
+    ));
+    rewrite(strategy, sa, &mut f);
+}
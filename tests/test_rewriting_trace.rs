@@ -0,0 +1,245 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for the lookup of strategy positions and the trace renderer.
+
+mod common;
+
+use std::rc::Rc;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use simple_term_rewriter::process::environment::StrategyEnvironment;
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::process::trace::{render_trace, replay_rule_chain};
+use simple_term_rewriter::process::traced::{AtomicRuleApplication, RewriteProcessTracedExecutor};
+use simple_term_rewriter::rule::ClosureRewriteRule;
+use simple_term_rewriter::rules::combinators::guard::ClosureRewriteApplicationGuard;
+use simple_term_rewriter::rules::combinators::guarded::GuardedRule;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn sp(coordinates: Vec<usize>) -> PositionInRewriteProcess {
+    PositionInRewriteProcess::from_absolute_coordinates(coordinates)
+}
+
+fn tp(coordinates: Vec<usize>) -> PositionInLanguageTerm {
+    PositionInLanguageTerm::from_absolute_coordinates(coordinates)
+}
+
+// == lookup ====================================================================
+
+#[test]
+fn rules_are_found_by_position() {
+    // Repeat(TryOnePath([TryOnePath(rules), AnyChild(TryOnePath(rules))])).
+    let strategy = normalization_strategy();
+    let desc = |coordinates| {
+        strategy
+            .get_rule_at_position(&sp(coordinates))
+            .map(|rule| rule.get_desc())
+    };
+    assert_eq!(
+        desc(vec![0, 0, 2]),
+        Some("star(star(r))→star(r)".to_string())
+    );
+    assert_eq!(desc(vec![0, 1, 0, 9]), Some("alt(r,r)→r".to_string()));
+    assert_eq!(desc(vec![0, 1]), None);
+    assert_eq!(desc(vec![0, 0, 10]), None);
+    assert_eq!(desc(vec![1]), None);
+    assert!(matches!(
+        strategy.get_process_at_position(&sp(vec![0, 1])),
+        Some(RewriteProcess::AnyChild(_, _, _))
+    ));
+}
+
+#[test]
+fn positions_go_through_calls() {
+    let environment = StrategyEnvironment::new(vec![(
+        "step".to_string(),
+        RewriteProcess::OneChild(Box::new(rule_as_process(rule_star_empty()))),
+    )])
+    .unwrap();
+    let call = RewriteProcess::Call("step".to_string());
+    let strategy = RewriteProcess::let_in(Rc::new(environment), call).unwrap();
+    assert_eq!(
+        strategy
+            .get_rule_at_position(&sp(vec![0, 0, 0]))
+            .map(|rule| rule.get_desc()),
+        Some("star(∅)→ε".to_string())
+    );
+    assert!(strategy.get_rule_at_position(&sp(vec![0, 1, 0])).is_none());
+    // Without the `Let`, the call cannot be resolved.
+    let unbound = RewriteProcess::<RegexOp>::Call("step".to_string());
+    assert!(unbound.get_process_at_position(&sp(vec![0])).is_none());
+}
+
+// == replay ====================================================================
+
+#[test]
+fn replay_recovers_redexes_of_a_pipe() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // Star(Star(∅)) → Star(∅) → ε
+    let strategy = RewriteProcess::Pipe(
+        Box::new(rule_as_process(rule_double_star())),
+        Box::new(rule_as_process(rule_star_empty())),
+    );
+    let e = empty(&mut f);
+    let se = star(e, &mut f);
+    let t = star(se.clone(), &mut f);
+    let chain = vec![(sp(vec![0]), tp(vec![])), (sp(vec![1]), tp(vec![]))];
+    let (steps, result) = replay_rule_chain(&strategy, &t, &chain, &mut f).unwrap();
+    assert_eq!(result, epsilon(&mut f));
+    assert_eq!(steps.len(), 2);
+    assert_eq!((&steps[0].redex, &steps[0].replacement), (&t, &se));
+    assert_eq!(steps[1].redex, se);
+    assert_eq!(
+        steps[1].to_string(),
+        "rule star(∅)→ε at position ε: *(∅) → ε"
+    );
+}
+
+#[test]
+fn replay_gives_siblings_the_context_of_the_congruence() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(atom(b'a', &mut f), atom(b'b', &mut f), &mut f);
+    let expected = concat(atom(b'b', &mut f), atom(b'c', &mut f), &mut f);
+    // Both children are rewritten in the context `·(a, b)`, so the guard of
+    // `b → c` holds although `a` is rewritten first.
+    let mut executor = RewriteProcessTracedExecutor::new(rewrite_after_a(), t.clone(), f);
+    let applications = executor.progress();
+    assert_eq!(
+        executor.get_current_terms().to_vec(),
+        vec![expected.clone()]
+    );
+    let chain = &applications[0].rule_chain;
+    assert_eq!(
+        chain,
+        &vec![(sp(vec![0, 0]), tp(vec![0])), (sp(vec![0, 1]), tp(vec![1]))]
+    );
+    let mut f = executor.into_factory();
+    let (steps, result) = replay_rule_chain(&rewrite_after_a(), &t, chain, &mut f).unwrap();
+    assert_eq!(result, expected);
+    assert_eq!(steps[1].to_string(), "rule b→c at position 1: b → c");
+}
+
+#[test]
+fn replay_reaches_the_traced_results() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let terms = generate_regex_terms(50, 41, &mut f);
+    let strategy = || RewriteProcess::parallel_outermost(any_rules());
+    for t in terms {
+        let mut executor = RewriteProcessTracedExecutor::new(strategy(), t, f);
+        let mut transitions = Vec::new();
+        while !executor.get_current_terms().is_empty() {
+            let previous = executor.get_current_terms().to_vec();
+            let applications = executor.progress();
+            transitions.push((
+                previous,
+                applications,
+                executor.get_current_terms().to_vec(),
+            ));
+        }
+        // Replaying in the executor's factory yields the very same terms.
+        f = executor.into_factory();
+        for (previous, applications, next) in transitions {
+            for application in applications {
+                let (steps, result) = replay_rule_chain(
+                    &strategy(),
+                    &previous[application.left_id],
+                    &application.rule_chain,
+                    &mut f,
+                )
+                .unwrap();
+                assert_eq!(steps.len(), application.rule_chain.len());
+                assert_eq!(result, next[application.right_id]);
+            }
+        }
+    }
+}
+
+#[test]
+fn replay_fails_on_foreign_chains() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let strategy = rule_as_process(rule_double_star());
+    let t = star(atom(b'a', &mut f), &mut f);
+    // No rule at strategy position 0, and the rule does not fire on `t`.
+    let chain = vec![(sp(vec![0]), tp(vec![]))];
+    assert!(replay_rule_chain(&strategy, &t, &chain, &mut f).is_none());
+    let chain = vec![(sp(vec![]), tp(vec![]))];
+    assert!(replay_rule_chain(&strategy, &t, &chain, &mut f).is_none());
+}
+
+// == rendering =================================================================
+
+#[test]
+fn render_one_progress() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let e = empty(&mut f);
+    let a = atom(b'a', &mut f);
+    let t = concat(star(e, &mut f), star(star(a, &mut f), &mut f), &mut f);
+    let strategy = RewriteProcess::AllChildren(Box::new(any_rules()));
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, t, f);
+    let previous = executor.get_current_terms().to_vec();
+    let applications = executor.progress();
+    assert_eq!(
+        executor.render_trace(&previous, &applications),
+        "0 → 0: ·(*(∅), *(*(a))) ⇒ ·(ε, *(a))\n\
+         \x20 rule star(∅)→ε at position 0: *(∅) → ε\n\
+         \x20 rule star(star(r))→star(r) at position 1: *(*(a)) → *(a)\n"
+    );
+}
+
+#[test]
+fn render_lists_unreplayable_chains() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = atom(b'a', &mut f);
+    let strategy = rule_as_process(rule_double_star());
+    let application = AtomicRuleApplication {
+        left_id: 0,
+        rule_chain: vec![(sp(vec![]), tp(vec![]))],
+        right_id: 0,
+    };
+    assert_eq!(
+        render_trace(&strategy, &[t], &[application], &mut f),
+        "0 → 0: a\n  rule star(star(r))→star(r) at position ε\n"
+    );
+}
+
+fn any_rules() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryOnePath(all_rules().into_iter().map(RewriteProcess::Rule).collect())
+}
+
+/// `AllChildren(TryOnePath([a → b, b → c]))`, `b → c` only firing with an `a`
+/// as first child of the context.
+fn rewrite_after_a() -> RewriteProcess<RegexOp> {
+    let b_to_c = GuardedRule::new(
+        ClosureRewriteRule::new("b→c", |t, _, _, f| {
+            (t.operator == RegexOp::Atom(b'b')).then(|| atom(b'c', f))
+        }),
+        ClosureRewriteApplicationGuard::new(|_, context: &LanguageTerm<RegexOp>, _| {
+            context.sub_terms[0].operator == RegexOp::Atom(b'a')
+        }),
+    );
+    RewriteProcess::AllChildren(Box::new(RewriteProcess::TryOnePath(vec![
+        atom_rule(b'a', b'b'),
+        rule_as_process(b_to_c),
+    ])))
+}
@@ -0,0 +1,193 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for
+//! [`RewriteProcessTracedExecutor::with_frontier_deduplication`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn any_rule() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryAllPaths(all_rules().into_iter().map(RewriteProcess::Rule).collect())
+}
+
+/// Rewrites the leftmost or the rightmost child, as two alternatives.
+fn both_sides() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryAllPaths(vec![
+        RewriteProcess::AnyChild(
+            SiblingOrder::Leftmost,
+            DepthOrder::Outermost,
+            Box::new(any_rule()),
+        ),
+        RewriteProcess::AnyChild(
+            SiblingOrder::Rightmost,
+            DepthOrder::Outermost,
+            Box::new(any_rule()),
+        ),
+    ])
+}
+
+/// `Alt(Star(∅), Star(ε))`: both sides rewrite to `ε`, in either order.
+fn diamond_term(f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let left = star(empty(f), f);
+    let right = star(epsilon(f), f);
+    alt(left, right, f)
+}
+
+// == within a layer ============================================================
+
+#[test]
+fn without_deduplication_duplicates_accumulate() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(both_sides(), t, f);
+    executor.progress();
+    executor.progress();
+    // Alt(ε,ε), reached from both intermediate terms by both alternatives.
+    assert_eq!(executor.get_current_terms().len(), 4);
+}
+
+#[test]
+fn duplicates_within_a_layer_share_right_id() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let eps = epsilon(&mut f);
+    let joined = alt(eps.clone(), eps, &mut f);
+    let mut executor =
+        RewriteProcessTracedExecutor::new(both_sides(), t, f).with_frontier_deduplication(true);
+    assert_eq!(executor.progress().len(), 2);
+    let applications = executor.progress();
+    assert_eq!(executor.get_current_terms(), std::slice::from_ref(&joined));
+    // Every incoming edge is still reported.
+    assert_eq!(applications.len(), 4);
+    assert!(applications.iter().all(|app| app.right_id == 0));
+    let sources: Vec<usize> = applications.iter().map(|app| app.left_id).collect();
+    assert_eq!(sources, vec![0, 0, 1, 1]);
+    assert!(executor.get_revisiting_applications().is_empty());
+}
+
+// == across layers =============================================================
+
+#[test]
+fn terms_of_earlier_layers_are_not_revisited() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let t = alt(a, b, &mut f);
+    let mut executor =
+        RewriteProcessTracedExecutor::new(swap(), t.clone(), f).with_frontier_deduplication(true);
+    assert_eq!(executor.progress().len(), 1);
+    assert!(executor.progress().is_empty());
+    assert!(executor.get_current_terms().is_empty());
+    let revisiting = executor.get_revisiting_applications();
+    assert_eq!(revisiting.len(), 1);
+    assert_eq!(revisiting[0].left_id, 0);
+    assert_eq!(revisiting[0].term, t);
+    assert_eq!(revisiting[0].rule_chain.len(), 1);
+}
+
+#[test]
+fn completed_terms_are_not_revisited() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // a → b (completed at the second step), a → c → d → b.
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let strategy = RewriteProcess::TryAllPaths(vec![
+        atom_rule(b'a', b'b'),
+        atom_rule(b'a', b'c'),
+        atom_rule(b'c', b'd'),
+        atom_rule(b'd', b'b'),
+    ]);
+    let mut executor =
+        RewriteProcessTracedExecutor::new(strategy, a, f).with_frontier_deduplication(true);
+    while !executor.get_current_terms().is_empty() {
+        executor.progress();
+        if !executor.get_revisiting_applications().is_empty() {
+            assert_eq!(executor.get_revisiting_applications()[0].term, b);
+        }
+    }
+    assert_eq!(executor.get_completed_terms(), std::slice::from_ref(&b));
+    assert_eq!(executor.get_revisiting_applications().len(), 1);
+}
+
+#[test]
+fn revisiting_applications_are_reset_by_each_step() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let t = alt(a, b, &mut f);
+    let mut executor =
+        RewriteProcessTracedExecutor::new(RewriteProcess::TryAllPaths(vec![swap(), swap()]), t, f)
+            .with_frontier_deduplication(true);
+    executor.progress();
+    assert!(executor.get_revisiting_applications().is_empty());
+    executor.progress();
+    assert_eq!(executor.get_revisiting_applications().len(), 2);
+    executor.progress();
+    assert!(executor.get_revisiting_applications().is_empty());
+}
+
+#[test]
+fn normal_forms_are_unchanged_by_deduplication() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let mut plain = RewriteProcessTracedExecutor::new(both_sides(), t.clone(), f);
+    while !plain.get_current_terms().is_empty() {
+        plain.progress();
+    }
+    let mut expected = plain.get_completed_terms().to_vec();
+    expected.dedup();
+    let f = plain.into_factory();
+    let mut deduplicated =
+        RewriteProcessTracedExecutor::new(both_sides(), t, f).with_frontier_deduplication(true);
+    while !deduplicated.get_current_terms().is_empty() {
+        deduplicated.progress();
+    }
+    assert_eq!(deduplicated.get_completed_terms(), expected.as_slice());
+}
+
+// == rewrite graph =============================================================
+
+#[test]
+fn revisiting_edges_are_recorded_in_the_rewrite_graph() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let t = alt(a, b, &mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(swap(), t, f)
+        .with_frontier_deduplication(true)
+        .with_rewrite_graph_recording(true);
+    while !executor.get_current_terms().is_empty() {
+        executor.progress();
+    }
+    let graph = executor.get_rewrite_graph().unwrap();
+    assert_eq!(graph.get_nodes().len(), 2);
+    let endpoints: Vec<(usize, usize)> = graph
+        .get_edges()
+        .iter()
+        .map(|e| (e.source, e.target))
+        .collect();
+    assert_eq!(endpoints, vec![(0, 1), (1, 0)]);
+}
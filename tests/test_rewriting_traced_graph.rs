@@ -0,0 +1,231 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for the [`RewriteGraph`] recorded by
+//! [`RewriteProcessTracedExecutor::with_rewrite_graph_recording`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::PositionInLanguageTerm;
+use simple_term_rewriter::process::rewrite_graph::RewriteGraph;
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn pos(coords: &[usize]) -> PositionInLanguageTerm {
+    PositionInLanguageTerm::from_absolute_coordinates(coords.to_vec())
+}
+
+fn any_rule() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryAllPaths(all_rules().into_iter().map(RewriteProcess::Rule).collect())
+}
+
+/// Rewrites the leftmost and the rightmost child, and tries `Alt(r,r)→r` at
+/// the root, as three alternatives.
+fn exploration_strategy() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryAllPaths(vec![
+        RewriteProcess::AnyChild(
+            SiblingOrder::Leftmost,
+            DepthOrder::Outermost,
+            Box::new(any_rule()),
+        ),
+        RewriteProcess::AnyChild(
+            SiblingOrder::Rightmost,
+            DepthOrder::Outermost,
+            Box::new(any_rule()),
+        ),
+        rule_as_process(rule_alt_idempotent()),
+    ])
+}
+
+/// `Alt(Star(∅), Star(ε))`
+fn diamond_term(f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let left = star(empty(f), f);
+    let right = star(epsilon(f), f);
+    alt(left, right, f)
+}
+
+fn run_recorded(
+    strategy: RewriteProcess<RegexOp>,
+    initial: LanguageTerm<RegexOp>,
+    f: TermFactory<RegexOp>,
+) -> RewriteProcessTracedExecutor<RegexOp> {
+    let mut executor =
+        RewriteProcessTracedExecutor::new(strategy, initial, f).with_rewrite_graph_recording(true);
+    while !executor.get_current_terms().is_empty() {
+        executor.progress();
+    }
+    executor
+}
+
+fn graph(executor: &RewriteProcessTracedExecutor<RegexOp>) -> &RewriteGraph<RegexOp> {
+    executor.get_rewrite_graph().unwrap()
+}
+
+// == recording switch ==========================================================
+
+#[test]
+fn recording_is_disabled_by_default() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(exploration_strategy(), t, f);
+    executor.progress();
+    assert!(executor.get_rewrite_graph().is_none());
+}
+
+#[test]
+fn initial_term_is_node_zero() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let executor = RewriteProcessTracedExecutor::new(exploration_strategy(), t.clone(), f)
+        .with_rewrite_graph_recording(true);
+    let graph = graph(&executor);
+    assert_eq!(graph.get_nodes().len(), 1);
+    assert_eq!(graph.get_node_id(&t), Some(0));
+    assert!(graph.get_edges().is_empty());
+}
+
+// == structure =================================================================
+
+#[test]
+fn duplicate_terms_across_branches_are_merged() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let eps = epsilon(&mut f);
+    let joined = alt(eps.clone(), eps.clone(), &mut f);
+    let executor = run_recorded(exploration_strategy(), t, f);
+    let graph = graph(&executor);
+    // Alt(Star(∅),Star(ε)), Alt(ε,Star(ε)), Alt(Star(∅),ε), Alt(ε,ε), ε
+    assert_eq!(graph.get_nodes().len(), 5);
+    let joined_id = graph.get_node_id(&joined).unwrap();
+    let incoming = graph
+        .get_edges()
+        .iter()
+        .filter(|e| e.target == joined_id)
+        .count();
+    assert_eq!(incoming, 4);
+}
+
+#[test]
+fn identical_edges_are_recorded_once() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let eps = epsilon(&mut f);
+    let joined = alt(eps.clone(), eps.clone(), &mut f);
+    let executor = run_recorded(exploration_strategy(), t, f);
+    let graph = graph(&executor);
+    // Alt(ε,ε) is in the frontier four times, but `Alt(r,r)→r` is recorded once.
+    let joined_id = graph.get_node_id(&joined).unwrap();
+    let eps_id = graph.get_node_id(&eps).unwrap();
+    let outgoing: Vec<_> = graph
+        .get_edges()
+        .iter()
+        .filter(|e| e.source == joined_id)
+        .collect();
+    assert_eq!(outgoing.len(), 1);
+    assert_eq!(outgoing[0].target, eps_id);
+    assert_eq!(graph.get_edges().len(), 7);
+}
+
+#[test]
+fn edges_carry_rule_descriptions_and_positions() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let executor = run_recorded(exploration_strategy(), t, f);
+    let graph = graph(&executor);
+    let first = &graph.get_edges()[0];
+    assert_eq!(first.source, 0);
+    assert_eq!(first.steps.len(), 1);
+    assert_eq!(first.steps[0].rule_desc, "star(∅)→ε");
+    assert_eq!(first.steps[0].strategy_position, pos(&[0, 0, 0]));
+    assert_eq!(first.steps[0].term_position, pos(&[0]));
+}
+
+#[test]
+fn pipe_edges_have_one_step_per_rule() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // Star(Star(Star(∅))) → Star(Star(∅)) → ε in a single Pipe step.
+    let t = star(star(star(empty(&mut f), &mut f), &mut f), &mut f);
+    let strategy = RewriteProcess::Pipe(
+        Box::new(rule_as_process(rule_double_star())),
+        Box::new(RewriteProcess::TryOnePath(vec![
+            rule_as_process(rule_double_star()),
+            rule_as_process(rule_star_empty()),
+        ])),
+    );
+    let executor = run_recorded(strategy, t, f);
+    let graph = graph(&executor);
+    assert_eq!(graph.get_edges().len(), 1);
+    let descs: Vec<_> = graph.get_edges()[0]
+        .steps
+        .iter()
+        .map(|s| s.rule_desc.as_str())
+        .collect();
+    assert_eq!(
+        descs,
+        vec!["star(star(r))→star(r)", "star(star(r))→star(r)"]
+    );
+}
+
+#[test]
+fn completed_terms_are_flagged() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = diamond_term(&mut f);
+    let eps = epsilon(&mut f);
+    let executor = run_recorded(exploration_strategy(), t, f);
+    let graph = graph(&executor);
+    for (id, term) in graph.get_nodes().iter().enumerate() {
+        assert_eq!(graph.is_completed(id), *term == eps, "on {term}");
+    }
+}
+
+// == export ====================================================================
+
+#[test]
+fn dot_export() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(empty(&mut f), &mut f), &mut f);
+    let executor = run_recorded(one_step_outermost(), t, f);
+    let dot = graph(&executor).to_dot();
+    assert!(dot.starts_with("digraph rewrite_graph {\n"));
+    assert!(dot.contains("  n0 [label=\"*(*(∅))\"];"));
+    assert!(dot.contains("  n2 [label=\"ε\", peripheries=2];"));
+    assert!(dot.contains("  n0 -> n1 [label=\"star(star(r))→star(r) @ ε\"];"));
+    assert!(dot.contains("  n1 -> n2 [label=\"star(∅)→ε @ ε\"];"));
+}
+
+#[test]
+fn json_export() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(star(empty(&mut f), &mut f), atom(b'a', &mut f), &mut f);
+    let executor = run_recorded(one_step_outermost(), t, f);
+    let json = graph(&executor).to_json();
+    let expected = "{\n  \"nodes\": [\n    \
+        {\"id\": 0, \"term\": \"·(*(∅), a)\", \"completed\": false},\n    \
+        {\"id\": 1, \"term\": \"·(ε, a)\", \"completed\": false},\n    \
+        {\"id\": 2, \"term\": \"a\", \"completed\": true}\n  ],\n  \
+        \"edges\": [\n    \
+        {\"source\": 0, \"target\": 1, \"steps\": [{\"rule\": \"star(∅)→ε\", \"strategy_position\": [1, 0, 0], \"term_position\": [0]}]},\n    \
+        {\"source\": 1, \"target\": 2, \"steps\": [{\"rule\": \"concat(ε,r)→r\", \"strategy_position\": [0, 5], \"term_position\": []}]}\n  \
+        ]\n}\n";
+    assert_eq!(json, expected);
+}
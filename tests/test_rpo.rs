@@ -0,0 +1,169 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Test suite for the recursive path ordering with status.
+//!
+//! Operator alphabet (greatest first): Atom > Alt > Concat > Star > Epsilon > Empty.
+//! Unless stated otherwise, `Alt` has multiset status and every other operator
+//! lexicographic status.
+
+mod common;
+
+use std::cmp::Ordering;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::rules::util::lpo::lexicographic_path_ordering;
+use simple_term_rewriter::rules::util::ordering::TermOrdering;
+use simple_term_rewriter::rules::util::rpo::{
+    is_greater_as_per_recursive_path_ordering, recursive_path_ordering, OperatorStatus,
+    RecursivePathOrdering,
+};
+use simple_term_rewriter::term::syntax::{LanguageTerm, TermFactory};
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::ordering::*;
+
+// == RPO helpers ===============================================================
+
+fn alt_multiset(op: &RegexOp) -> OperatorStatus {
+    if *op == RegexOp::Alt {
+        OperatorStatus::Multiset
+    } else {
+        OperatorStatus::Lexicographic
+    }
+}
+
+fn rpo() -> RecursivePathOrdering<RegexOp> {
+    RecursivePathOrdering::new(precedence).with_operator_status(alt_multiset)
+}
+
+/// Swaps the two sub-terms of every `Alt` node.
+fn mirror_alts(t: &LanguageTerm<RegexOp>, f: &mut TermFactory<RegexOp>) -> LanguageTerm<RegexOp> {
+    let mut sub_terms: Vec<_> = t.sub_terms.iter().map(|x| mirror_alts(x, f)).collect();
+    match t.operator {
+        RegexOp::Alt => alt(sub_terms.pop().unwrap(), sub_terms.pop().unwrap(), f),
+        RegexOp::Concat => {
+            let right = sub_terms.pop().unwrap();
+            concat(sub_terms.pop().unwrap(), right, f)
+        }
+        RegexOp::Star => star(sub_terms.pop().unwrap(), f),
+        _ => t.clone(),
+    }
+}
+
+// == status ====================================================================
+
+#[test]
+fn lexicographic_status_coincides_with_the_lpo() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let terms = generate_regex_terms(40, 97, &mut f);
+    let all_lexicographic = |_: &RegexOp| OperatorStatus::Lexicographic;
+    for s in &terms {
+        for t in &terms {
+            assert_eq!(
+                recursive_path_ordering(s, t, &precedence, &all_lexicographic),
+                lexicographic_path_ordering(s, t, &precedence)
+            );
+        }
+    }
+}
+
+#[test]
+fn multiset_status_ignores_argument_order() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let sb = star(atom(b'b', &mut f), &mut f);
+    let ab = alt(a.clone(), sb.clone(), &mut f);
+    let ba = alt(sb.clone(), a.clone(), &mut f);
+    let ordering = rpo();
+    assert_ne!(ab, ba);
+    assert!(ordering.are_equivalent(&ab, &ba));
+    assert_eq!(ordering.compare_terms(&ab, &ba), Ordering::Equal);
+    assert!(!ordering.is_greater(&ab, &ba));
+    assert!(!ordering.is_greater(&ba, &ab));
+    // Concat keeps lexicographic status.
+    let ab = concat(a.clone(), sb.clone(), &mut f);
+    let ba = concat(sb, a, &mut f);
+    assert!(!ordering.are_equivalent(&ab, &ba));
+    assert_eq!(ordering.compare_terms(&ba, &ab), Ordering::Greater);
+}
+
+#[test]
+fn multiset_extension_compares_the_largest_differing_sub_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // |(a, *(b)) against |(b, a): a cancels out and *(b) > b.
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let s = alt(a.clone(), star(b.clone(), &mut f), &mut f);
+    let t = alt(b, a, &mut f);
+    assert!(is_greater_as_per_recursive_path_ordering(
+        &s,
+        &t,
+        &precedence,
+        &alt_multiset
+    ));
+    // Lexicographically, b > a decides the other way.
+    assert_eq!(
+        lexicographic_path_ordering(&s, &t, &precedence),
+        Ordering::Less
+    );
+}
+
+// == properties ================================================================
+
+#[test]
+fn equivalent_terms_compare_identically() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let terms = generate_regex_terms(40, 101, &mut f);
+    let mirrored: Vec<_> = terms.iter().map(|t| mirror_alts(t, &mut f)).collect();
+    let ordering = rpo();
+    for (s, s_mirrored) in terms.iter().zip(mirrored.iter()) {
+        assert!(ordering.are_equivalent(s, s_mirrored));
+        for t in &terms {
+            assert_eq!(
+                ordering.compare_terms(s, t),
+                ordering.compare_terms(s_mirrored, t)
+            );
+        }
+    }
+}
+
+#[test]
+fn total_antisymmetric_and_transitive_on_random_terms() {
+    check_total_antisymmetric_and_transitive(&rpo(), 30, 103);
+}
+
+#[test]
+fn terms_are_greater_than_their_subterms() {
+    check_greater_than_subterms(&rpo(), 100, 107);
+}
+
+#[test]
+fn terms_are_greater_than_their_strict_embeddings() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // Monotonicity: replacing a sub-term by a smaller one yields a smaller term.
+    let ordering = rpo();
+    let e = empty(&mut f);
+    for t in generate_regex_terms(60, 109, &mut f) {
+        let smaller = star(t.clone(), &mut f);
+        let context = alt(concat(smaller, e.clone(), &mut f), e.clone(), &mut f);
+        let context_smaller = alt(concat(t.clone(), e.clone(), &mut f), e.clone(), &mut f);
+        assert!(ordering.is_greater(&context, &context_smaller));
+    }
+}
@@ -0,0 +1,215 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`Pattern`] matching / instantiation and for [`PatternRule`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::PositionInLanguageTerm;
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::rule::RewriteRule;
+use simple_term_rewriter::rules::primitives::pattern::{PatternRule, PatternRuleError};
+use simple_term_rewriter::term::pattern::Pattern;
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::lang::RegexOp;
+use common::regex::rules::rewrite;
+
+fn var(name: &str) -> Pattern<RegexOp> {
+    Pattern::var(name)
+}
+
+fn p_star(p: Pattern<RegexOp>) -> Pattern<RegexOp> {
+    Pattern::op(RegexOp::Star, vec![p])
+}
+
+fn p_alt(l: Pattern<RegexOp>, r: Pattern<RegexOp>) -> Pattern<RegexOp> {
+    Pattern::op(RegexOp::Alt, vec![l, r])
+}
+
+fn p_concat(l: Pattern<RegexOp>, r: Pattern<RegexOp>) -> Pattern<RegexOp> {
+    Pattern::op(RegexOp::Concat, vec![l, r])
+}
+
+fn root_pos() -> PositionInLanguageTerm {
+    PositionInLanguageTerm::get_root_position()
+}
+
+// == matching ==================================================================
+
+#[test]
+fn variable_matches_any_term() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(atom(b'a', &mut f), epsilon(&mut f), &mut f);
+    let subst = var("x").match_term(&t).unwrap();
+    assert_eq!(subst.len(), 1);
+    assert_eq!(subst["x"], t);
+}
+
+#[test]
+fn operator_mismatch_fails() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(atom(b'a', &mut f), atom(b'b', &mut f), &mut f);
+    assert!(p_alt(var("x"), var("y")).match_term(&t).is_none());
+}
+
+#[test]
+fn nested_pattern_binds_all_variables() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let t = alt(star(a.clone(), &mut f), b.clone(), &mut f);
+    let subst = p_alt(p_star(var("x")), var("y")).match_term(&t).unwrap();
+    assert_eq!(subst["x"], a);
+    assert_eq!(subst["y"], b);
+}
+
+#[test]
+fn non_linear_pattern_requires_equal_bindings() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let same = alt(a.clone(), a.clone(), &mut f);
+    let diff = alt(a, b, &mut f);
+    let p = p_alt(var("x"), var("x"));
+    assert!(p.match_term(&same).is_some());
+    assert!(p.match_term(&diff).is_none());
+}
+
+#[test]
+fn ground_pattern_from_term_matches_only_itself() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(atom(b'a', &mut f), &mut f);
+    let other = star(atom(b'b', &mut f), &mut f);
+    let p = Pattern::from_term(&t);
+    assert!(p.get_variables().is_empty());
+    assert_eq!(p.match_term(&t).unwrap().len(), 0);
+    assert!(p.match_term(&other).is_none());
+}
+
+#[test]
+fn instantiate_builds_term_in_factory() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let t = concat(a.clone(), star(a.clone(), &mut f), &mut f);
+    let subst = p_concat(var("x"), p_star(var("x"))).match_term(&t).unwrap();
+    let rebuilt = p_star(p_concat(var("x"), var("x"))).instantiate(&subst, &mut f);
+    let expected = star(concat(a.clone(), a, &mut f), &mut f);
+    assert_eq!(rebuilt, Some(expected));
+}
+
+#[test]
+fn instantiate_with_unbound_variable_is_none() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let subst = Default::default();
+    assert!(p_star(var("x")).instantiate(&subst, &mut f).is_none());
+}
+
+// == PatternRule construction ==================================================
+
+#[test]
+fn rule_rejects_variable_lhs() {
+    let res = PatternRule::new("bad", var("x"), p_star(var("x")));
+    assert_eq!(res.err(), Some(PatternRuleError::LeftHandSideIsAVariable));
+}
+
+#[test]
+fn rule_rejects_unbound_rhs_variable() {
+    let res = PatternRule::new("bad", p_star(var("x")), p_alt(var("x"), var("y")));
+    assert_eq!(
+        res.err(),
+        Some(PatternRuleError::UnboundRightHandSideVariable(
+            "y".to_string()
+        ))
+    );
+}
+
+// == PatternRule application ===================================================
+
+fn double_star() -> PatternRule<RegexOp> {
+    PatternRule::new(
+        "star(star(x))→star(x)",
+        p_star(p_star(var("x"))),
+        p_star(var("x")),
+    )
+    .unwrap()
+}
+
+fn alt_idempotent() -> PatternRule<RegexOp> {
+    PatternRule::new("alt(x,x)→x", p_alt(var("x"), var("x")), var("x")).unwrap()
+}
+
+#[test]
+fn rule_fires_on_match() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(atom(b'a', &mut f), &mut f), &mut f);
+    let result = double_star().try_apply(&t, &t, &root_pos(), &mut f);
+    assert_eq!(result, Some(star(atom(b'a', &mut f), &mut f)));
+}
+
+#[test]
+fn rule_does_not_fire_without_match() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(atom(b'a', &mut f), &mut f);
+    assert!(double_star()
+        .try_apply(&t, &t, &root_pos(), &mut f)
+        .is_none());
+}
+
+#[test]
+fn non_linear_rule_fires_only_on_equal_children() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let b = atom(b'b', &mut f);
+    let same = alt(a.clone(), a.clone(), &mut f);
+    let diff = alt(a.clone(), b, &mut f);
+    assert_eq!(
+        alt_idempotent().try_apply(&same, &same, &root_pos(), &mut f),
+        Some(a)
+    );
+    assert!(alt_idempotent()
+        .try_apply(&diff, &diff, &root_pos(), &mut f)
+        .is_none());
+}
+
+#[test]
+fn rule_plugs_into_strategies() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // Alt(Star(Star(Star(a))), Star(a)) normalizes to Star(a).
+    let a = atom(b'a', &mut f);
+    let sa = star(a, &mut f);
+    let sssa = star(star(sa.clone(), &mut f), &mut f);
+    let t = alt(sssa, sa.clone(), &mut f);
+    let rules = || {
+        RewriteProcess::TryOnePath(vec![
+            RewriteProcess::Rule(Box::new(double_star())),
+            RewriteProcess::Rule(Box::new(alt_idempotent())),
+        ])
+    };
+    let step = RewriteProcess::TryOnePath(vec![
+        rules(),
+        RewriteProcess::AnyChild(
+            SiblingOrder::Leftmost,
+            DepthOrder::Outermost,
+            Box::new(rules()),
+        ),
+    ]);
+    let result = rewrite(RewriteProcess::Repeat(Box::new(step)), t, &mut f);
+    assert_eq!(result, vec![sa]);
+}
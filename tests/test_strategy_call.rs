@@ -0,0 +1,260 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`RewriteProcess::Call`] and the [`StrategyEnvironment`].
+
+mod common;
+
+use std::rc::Rc;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use simple_term_rewriter::process::control::RewriteExecutionError;
+use simple_term_rewriter::process::environment::{StrategyEnvironment, StrategyEnvironmentError};
+use simple_term_rewriter::process::normalizer::Normalizer;
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn call(name: &str) -> RewriteProcess<RegexOp> {
+    RewriteProcess::Call(name.to_string())
+}
+
+fn any_rule() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryOnePath(all_rules().into_iter().map(RewriteProcess::Rule).collect())
+}
+
+/// `simplify = Pipe(AllChildren(simplify), Repeat(Pipe(rules, simplify)))`
+fn simplify() -> RewriteProcess<RegexOp> {
+    let environment = StrategyEnvironment::new(vec![(
+        "simplify".to_string(),
+        RewriteProcess::Pipe(
+            Box::new(RewriteProcess::AllChildren(Box::new(call("simplify")))),
+            Box::new(RewriteProcess::Repeat(Box::new(RewriteProcess::Pipe(
+                Box::new(any_rule()),
+                Box::new(call("simplify")),
+            )))),
+        ),
+    )])
+    .unwrap();
+    RewriteProcess::let_in(Rc::new(environment), call("simplify")).unwrap()
+}
+
+// == evaluation ================================================================
+
+#[test]
+fn recursive_simplification_normalizes() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let strategy = simplify();
+    let mut normalizer = Normalizer::new(all_rules());
+    for t in generate_regex_terms(200, 29, &mut f) {
+        let expected = vec![normalizer.normalize(&t, &mut f)];
+        assert_eq!(
+            RewriteProcessUntracedExecutor::rewrite(&strategy, &t, &mut f),
+            expected
+        );
+    }
+}
+
+#[test]
+fn mutually_recursive_definitions() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // `even` rewrites the stars at even depths, `odd` the other ones.
+    let environment = StrategyEnvironment::new(vec![
+        (
+            "even".to_string(),
+            RewriteProcess::Pipe(
+                Box::new(RewriteProcess::Repeat(Box::new(rule_as_process(
+                    rule_double_star(),
+                )))),
+                Box::new(RewriteProcess::AllChildren(Box::new(call("odd")))),
+            ),
+        ),
+        (
+            "odd".to_string(),
+            RewriteProcess::AllChildren(Box::new(call("even"))),
+        ),
+    ])
+    .unwrap();
+    let strategy = RewriteProcess::let_in(Rc::new(environment), call("even")).unwrap();
+    // Concat(Star(Star(a)), Star(Star(Star(b)))): only the second double star
+    // is at an even depth.
+    let a = atom(b'a', &mut f);
+    let sa = star(a, &mut f);
+    let ssa = star(sa, &mut f);
+    let b = atom(b'b', &mut f);
+    let sb = star(b, &mut f);
+    let ssb = star(sb.clone(), &mut f);
+    let sssb = star(ssb.clone(), &mut f);
+    let t = concat(ssa.clone(), sssb, &mut f);
+    assert_eq!(
+        RewriteProcessUntracedExecutor::rewrite(&strategy, &t, &mut f),
+        vec![concat(ssa, ssb, &mut f)]
+    );
+}
+
+#[test]
+fn call_outside_let_is_an_error() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = atom(b'a', &mut f);
+    let result = RewriteProcessUntracedExecutor::new().execute(&call("simplify"), &t, &mut f);
+    assert_eq!(
+        result,
+        Err(RewriteExecutionError::UnboundStrategyName(
+            "simplify".to_string()
+        ))
+    );
+}
+
+// == environment errors ========================================================
+
+#[test]
+fn unbound_names_are_reported() {
+    let error = StrategyEnvironment::<RegexOp>::new(vec![("a".to_string(), call("b"))]);
+    assert_eq!(
+        error.err(),
+        Some(StrategyEnvironmentError::UnboundName {
+            in_definition: Some("a".to_string()),
+            name: "b".to_string(),
+        })
+    );
+    let environment = Rc::new(StrategyEnvironment::new(vec![]).unwrap());
+    assert_eq!(
+        RewriteProcess::<RegexOp>::let_in(environment, call("b")).err(),
+        Some(StrategyEnvironmentError::UnboundName {
+            in_definition: None,
+            name: "b".to_string(),
+        })
+    );
+}
+
+#[test]
+fn duplicate_definitions_are_reported() {
+    let error = StrategyEnvironment::<RegexOp>::new(vec![
+        ("a".to_string(), rule_as_process(rule_double_star())),
+        ("a".to_string(), rule_as_process(rule_star_empty())),
+    ]);
+    assert_eq!(
+        error.err(),
+        Some(StrategyEnvironmentError::DuplicateDefinition(
+            "a".to_string()
+        ))
+    );
+}
+
+#[test]
+fn unguarded_recursion_is_reported() {
+    let self_recursive = StrategyEnvironment::<RegexOp>::new(vec![(
+        "a".to_string(),
+        RewriteProcess::TryOnePath(vec![rule_as_process(rule_double_star()), call("a")]),
+    )]);
+    assert_eq!(
+        self_recursive.err(),
+        Some(StrategyEnvironmentError::UnguardedRecursion(vec![
+            "a".to_string()
+        ]))
+    );
+    // `Repeat` may succeed without firing a rule, so it does not guard `b`.
+    let mutually_recursive = StrategyEnvironment::<RegexOp>::new(vec![
+        ("a".to_string(), RewriteProcess::topdown(call("b"))),
+        (
+            "b".to_string(),
+            RewriteProcess::Pipe(
+                Box::new(RewriteProcess::Repeat(Box::new(any_rule()))),
+                Box::new(call("a")),
+            ),
+        ),
+    ]);
+    assert_eq!(
+        mutually_recursive.err(),
+        Some(StrategyEnvironmentError::UnguardedRecursion(vec![
+            "a".to_string(),
+            "b".to_string()
+        ]))
+    );
+}
+
+#[test]
+fn rules_and_children_guard_recursion() {
+    for definition in [
+        RewriteProcess::Pipe(Box::new(any_rule()), Box::new(call("a"))),
+        RewriteProcess::OneChild(Box::new(call("a"))),
+        RewriteProcess::innermost_step(RewriteProcess::Pipe(
+            Box::new(any_rule()),
+            Box::new(call("a")),
+        )),
+    ] {
+        assert!(StrategyEnvironment::new(vec![("a".to_string(), definition)]).is_ok());
+    }
+}
+
+// == traced and streamed evaluation ============================================
+
+#[test]
+fn traced_positions_go_through_calls() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let environment = StrategyEnvironment::new(vec![(
+        "double".to_string(),
+        rule_as_process(rule_double_star()),
+    )])
+    .unwrap();
+    let strategy = RewriteProcess::let_in(
+        Rc::new(environment),
+        RewriteProcess::AllChildren(Box::new(call("double"))),
+    )
+    .unwrap();
+    let a = atom(b'a', &mut f);
+    let sa = star(a, &mut f);
+    let ssa = star(sa, &mut f);
+    let t = concat(ssa.clone(), ssa, &mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, t, f);
+    let applications = executor.progress();
+    assert_eq!(applications.len(), 1);
+    // Let → AllChildren → Call → Rule.
+    let rule_sp = PositionInRewriteProcess::from_absolute_coordinates(vec![0, 0, 0]);
+    assert_eq!(
+        applications[0].rule_chain,
+        vec![
+            (
+                rule_sp.clone(),
+                PositionInLanguageTerm::from_absolute_coordinates(vec![0])
+            ),
+            (
+                rule_sp,
+                PositionInLanguageTerm::from_absolute_coordinates(vec![1])
+            ),
+        ]
+    );
+}
+
+#[test]
+fn streamed_results_match_eager_results() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let strategy = simplify();
+    let executor = RewriteProcessUntracedExecutor::new();
+    for t in generate_regex_terms(50, 31, &mut f) {
+        let expected = executor.execute(&strategy, &t, &mut f).unwrap();
+        let streamed: Vec<_> = executor.stream(&strategy, &t, &mut f).collect();
+        assert_eq!(streamed, expected);
+    }
+}
@@ -0,0 +1,222 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for [`RewriteProcess::AllChildren`], [`RewriteProcess::OneChild`]
+//! and [`RewriteProcess::SomeChildren`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn rule_double() -> RewriteProcess<RegexOp> {
+    rule_as_process(rule_double_star())
+}
+
+/// `a → b` and `a → c`, as two alternatives.
+fn a_to_b_or_c() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryAllPaths(vec![atom_rule(b'a', b'b'), atom_rule(b'a', b'c')])
+}
+
+// == AllChildren ===============================================================
+
+#[test]
+fn all_children_on_leaf_succeeds_unchanged() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = atom(b'a', &mut f);
+    let result = rewrite(
+        RewriteProcess::AllChildren(Box::new(rule_double())),
+        t.clone(),
+        &mut f,
+    );
+    assert_eq!(result, vec![t]);
+}
+
+#[test]
+fn all_children_fails_if_one_child_fails() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(
+        star(star(atom(b'a', &mut f), &mut f), &mut f),
+        atom(b'b', &mut f),
+        &mut f,
+    );
+    let result = rewrite(
+        RewriteProcess::AllChildren(Box::new(rule_double())),
+        t,
+        &mut f,
+    );
+    assert!(result.is_empty());
+}
+
+#[test]
+fn all_children_rewrites_every_child() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(
+        star(star(atom(b'a', &mut f), &mut f), &mut f),
+        star(star(atom(b'b', &mut f), &mut f), &mut f),
+        &mut f,
+    );
+    let result = rewrite(
+        RewriteProcess::AllChildren(Box::new(rule_double())),
+        t,
+        &mut f,
+    );
+    assert_eq!(
+        result,
+        vec![alt(
+            star(atom(b'a', &mut f), &mut f),
+            star(atom(b'b', &mut f), &mut f),
+            &mut f
+        )]
+    );
+}
+
+#[test]
+fn all_children_combines_alternatives() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(atom(b'a', &mut f), atom(b'a', &mut f), &mut f);
+    let result = rewrite(
+        RewriteProcess::AllChildren(Box::new(a_to_b_or_c())),
+        t,
+        &mut f,
+    );
+    let expected: Vec<_> = [(b'b', b'b'), (b'b', b'c'), (b'c', b'b'), (b'c', b'c')]
+        .into_iter()
+        .map(|(l, r)| concat(atom(l, &mut f), atom(r, &mut f), &mut f))
+        .collect();
+    assert_eq!(result, expected);
+}
+
+// == OneChild ==================================================================
+
+#[test]
+fn one_child_on_leaf_fails() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = atom(b'a', &mut f);
+    let result = rewrite(RewriteProcess::OneChild(Box::new(a_to_b_or_c())), t, &mut f);
+    assert!(result.is_empty());
+}
+
+#[test]
+fn one_child_keeps_every_alternative() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(atom(b'a', &mut f), atom(b'a', &mut f), &mut f);
+    let result = rewrite(RewriteProcess::OneChild(Box::new(a_to_b_or_c())), t, &mut f);
+    let expected: Vec<_> = [(b'b', b'a'), (b'c', b'a'), (b'a', b'b'), (b'a', b'c')]
+        .into_iter()
+        .map(|(l, r)| concat(atom(l, &mut f), atom(r, &mut f), &mut f))
+        .collect();
+    assert_eq!(result, expected);
+}
+
+// == SomeChildren ==============================================================
+
+#[test]
+fn some_children_leaves_failing_children_unchanged() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(
+        star(star(atom(b'a', &mut f), &mut f), &mut f),
+        atom(b'b', &mut f),
+        &mut f,
+    );
+    let result = rewrite(
+        RewriteProcess::SomeChildren(Box::new(rule_double())),
+        t,
+        &mut f,
+    );
+    assert_eq!(
+        result,
+        vec![concat(
+            star(atom(b'a', &mut f), &mut f),
+            atom(b'b', &mut f),
+            &mut f
+        )]
+    );
+}
+
+#[test]
+fn some_children_fails_if_every_child_fails() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(atom(b'a', &mut f), atom(b'b', &mut f), &mut f);
+    let result = rewrite(
+        RewriteProcess::SomeChildren(Box::new(rule_double())),
+        t,
+        &mut f,
+    );
+    assert!(result.is_empty());
+}
+
+// == traced and streamed evaluation ============================================
+
+#[test]
+fn traced_chain_lists_every_child_rewrite() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(
+        star(star(atom(b'a', &mut f), &mut f), &mut f),
+        star(star(atom(b'b', &mut f), &mut f), &mut f),
+        &mut f,
+    );
+    let strategy = RewriteProcess::AllChildren(Box::new(rule_double()));
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, t, f);
+    let applications = executor.progress();
+    assert_eq!(applications.len(), 1);
+    let inner = PositionInRewriteProcess::from_absolute_coordinates(vec![0]);
+    assert_eq!(
+        applications[0].rule_chain,
+        vec![
+            (
+                inner.clone(),
+                PositionInLanguageTerm::from_absolute_coordinates(vec![0])
+            ),
+            (
+                inner,
+                PositionInLanguageTerm::from_absolute_coordinates(vec![1])
+            ),
+        ]
+    );
+}
+
+#[test]
+fn streamed_results_match_eager_results() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(
+        atom(b'a', &mut f),
+        alt(atom(b'a', &mut f), atom(b'b', &mut f), &mut f),
+        &mut f,
+    );
+    let executor = RewriteProcessUntracedExecutor::new();
+    for strategy in [
+        RewriteProcess::AllChildren(Box::new(a_to_b_or_c())),
+        RewriteProcess::OneChild(Box::new(RewriteProcess::OneChild(Box::new(a_to_b_or_c())))),
+        RewriteProcess::SomeChildren(Box::new(a_to_b_or_c())),
+        RewriteProcess::AllChildren(Box::new(RewriteProcess::SomeChildren(Box::new(
+            a_to_b_or_c(),
+        )))),
+    ] {
+        let expected = executor.execute(&strategy, &t, &mut f).unwrap();
+        let streamed: Vec<_> = executor.stream(&strategy, &t, &mut f).collect();
+        assert_eq!(streamed, expected);
+    }
+}
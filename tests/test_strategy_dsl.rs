@@ -0,0 +1,256 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for the strategy syntax of [`parse_process`] and [`print_process`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::process::dsl::{
+    parse_process, print_annotated_process, print_process, RewriteRuleRegistry,
+    StrategyParsingError, StrategyParsingErrorKind,
+};
+use simple_term_rewriter::process::environment::StrategyEnvironmentError;
+use simple_term_rewriter::process::strategy::RewriteProcess;
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::rule::RewriteRule;
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+/// The regex rules, by short name and by description.
+struct RegexRules;
+
+impl RewriteRuleRegistry<RegexOp> for RegexRules {
+    fn make_rule(&self, name: &str) -> Option<Box<dyn RewriteRule<RegexOp>>> {
+        match name {
+            "star_empty" => Some(Box::new(rule_star_empty())),
+            "star_epsilon" => Some(Box::new(rule_star_epsilon())),
+            "double_star" => Some(Box::new(rule_double_star())),
+            _ => all_rules().into_iter().find(|rule| rule.get_desc() == name),
+        }
+    }
+}
+
+fn parse(input: &str) -> Result<RewriteProcess<RegexOp>, StrategyParsingError> {
+    parse_process(input, &RegexRules)
+}
+
+fn err(kind: StrategyParsingErrorKind, line: usize, column: usize) -> StrategyParsingError {
+    StrategyParsingError { line, column, kind }
+}
+
+// == parsing ===================================================================
+
+#[test]
+fn parsed_strategy_behaves_as_built_one() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let rules: Vec<String> = all_rules()
+        .iter()
+        .map(|rule| format!("rule:\"{}\"", rule.get_desc()))
+        .collect();
+    let rules = rules.join(", ");
+    let input = format!(
+        "repeat(try_one(try_one({}), any_child(lm, out, try_one({}))))",
+        rules, rules
+    );
+    let parsed = parse(&input).unwrap();
+    let built = normalization_strategy();
+    for t in generate_regex_terms(100, 37, &mut f) {
+        assert_eq!(
+            RewriteProcessUntracedExecutor::rewrite(&parsed, &t, &mut f),
+            RewriteProcessUntracedExecutor::rewrite(&built, &t, &mut f)
+        );
+    }
+}
+
+#[test]
+fn parse_tolerates_comments_and_newlines() {
+    let input = "# simplify stars\nrepeat( # to fixpoint\n  try_one(rule:star_empty,\n    rule:star_epsilon))\n";
+    let process = parse(input).unwrap();
+    assert_eq!(
+        print_process(&process),
+        "repeat(try_one(rule:\"star(∅)→ε\", rule:\"star(ε)→ε\"))"
+    );
+}
+
+#[test]
+fn pipe_is_right_nested() {
+    let process = parse("pipe(rule:star_empty, rule:star_epsilon, rule:double_star)").unwrap();
+    assert_eq!(
+        print_process(&process),
+        "pipe(rule:\"star(∅)→ε\", pipe(rule:\"star(ε)→ε\", rule:\"star(star(r))→star(r)\"))"
+    );
+}
+
+#[test]
+fn parse_let_with_recursive_definition() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let process = parse(
+        "let(simplify = pipe(all_children(call:simplify), \
+                             repeat(pipe(rule:double_star, call:simplify))); \
+             call:simplify)",
+    )
+    .unwrap();
+    let a = atom(b'a', &mut f);
+    let t = star(star(star(a.clone(), &mut f), &mut f), &mut f);
+    assert_eq!(
+        RewriteProcessUntracedExecutor::rewrite(&process, &t, &mut f),
+        vec![star(a, &mut f)]
+    );
+}
+
+// == printing ==================================================================
+
+#[test]
+fn print_then_parse_is_identity() {
+    for input in [
+        "any_child(rm, inn, rule:\"star(∅)→ε\")",
+        "try_all(all_children(rule:\"alt(r,r)→r\"), one_child(rule:\"alt(r,r)→r\"), try_one())",
+        "some_children(topdown(bottomup(parallel_outermost_step(rule:\"concat(ε,r)→r\"))))",
+        "let(a = one_child(call:a), \"b c\" = call:a; pipe(call:\"b c\", call:a))",
+        "let(; rule:\"concat(r,ε)→r\")",
+    ] {
+        let printed = print_process(&parse(input).unwrap());
+        assert_eq!(printed, input);
+        let annotated = print_annotated_process(&parse(input).unwrap());
+        assert_eq!(print_process(&parse(&annotated).unwrap()), input);
+    }
+}
+
+#[test]
+fn single_steps_are_printed_as_their_encodings() {
+    for (input, printed) in [
+        (
+            "outermost_step(rule:star_empty)",
+            "try_one(rule:\"star(∅)→ε\", any_child(lm, out, rule:\"star(∅)→ε\"))",
+        ),
+        (
+            "innermost_step(rule:star_empty)",
+            "try_one(any_child(lm, inn, rule:\"star(∅)→ε\"), rule:\"star(∅)→ε\")",
+        ),
+    ] {
+        assert_eq!(print_process(&parse(input).unwrap()), printed);
+    }
+}
+
+#[test]
+fn annotated_labels_are_traced_positions() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let process =
+        parse("repeat(try_one(rule:star_empty, any_child(lm, out, rule:double_star)))").unwrap();
+    assert_eq!(
+        print_annotated_process(&process),
+        "repeat(                                # ε\n\
+         \x20 try_one(                             # 0\n\
+         \x20   rule:\"star(∅)→ε\",                  # 0_0\n\
+         \x20   any_child(lm, out,                 # 0_1\n\
+         \x20     rule:\"star(star(r))→star(r)\")))  # 0_1_0\n"
+    );
+    let a = atom(b'a', &mut f);
+    let t = concat(star(star(a, &mut f), &mut f), epsilon(&mut f), &mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(process, t, f);
+    let applications = executor.progress();
+    assert_eq!(applications[0].rule_chain[0].0.to_string(), "0_1_0");
+}
+
+// == errors ====================================================================
+
+#[test]
+fn unknown_names_are_located() {
+    assert_eq!(
+        parse("repeat(\n  rule:nope)").err(),
+        Some(err(
+            StrategyParsingErrorKind::UnknownRule("nope".to_string()),
+            2,
+            3
+        ))
+    );
+    assert_eq!(
+        parse("try_one(loop(rule:star_empty))").err(),
+        Some(err(
+            StrategyParsingErrorKind::UnknownCombinator("loop".to_string()),
+            1,
+            9
+        ))
+    );
+    assert_eq!(
+        parse("any_child(lm, deep, rule:star_empty)").err(),
+        Some(err(
+            StrategyParsingErrorKind::UnknownOption("deep".to_string()),
+            1,
+            15
+        ))
+    );
+}
+
+#[test]
+fn malformed_input_is_reported() {
+    assert_eq!(
+        parse("repeat(rule:star_empty, rule:star_empty)").err(),
+        Some(err(
+            StrategyParsingErrorKind::ArgumentCountMismatch {
+                combinator: "repeat".to_string(),
+                expected: 1,
+                found: 2,
+            },
+            1,
+            1
+        ))
+    );
+    assert_eq!(
+        parse("pipe(rule:star_empty").err(),
+        Some(err(StrategyParsingErrorKind::UnexpectedEndOfInput, 1, 21))
+    );
+    assert_eq!(
+        parse("rule:star_empty)").err(),
+        Some(err(
+            StrategyParsingErrorKind::UnexpectedCharacter(')'),
+            1,
+            16
+        ))
+    );
+}
+
+#[test]
+fn environment_errors_are_reported() {
+    assert_eq!(
+        parse("call:simplify").err(),
+        Some(err(
+            StrategyParsingErrorKind::Environment(StrategyEnvironmentError::UnboundName {
+                in_definition: None,
+                name: "simplify".to_string(),
+            }),
+            1,
+            1
+        ))
+    );
+    assert_eq!(
+        parse("let(a = repeat(call:a); call:a)").err(),
+        Some(err(
+            StrategyParsingErrorKind::Environment(StrategyEnvironmentError::UnguardedRecursion(
+                vec!["a".to_string()]
+            )),
+            1,
+            1
+        ))
+    );
+}
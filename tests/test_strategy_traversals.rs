@@ -0,0 +1,257 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for the generic traversals of [`RewriteProcess::Traverse`].
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::{PositionInLanguageTerm, PositionInRewriteProcess};
+use simple_term_rewriter::process::normalizer::Normalizer;
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::term::syntax::TermFactory;
+
+use common::regex::constructors::*;
+use common::regex::generation::generate_regex_terms;
+use common::regex::lang::RegexOp;
+use common::regex::rules::*;
+
+fn any_rule() -> RewriteProcess<RegexOp> {
+    RewriteProcess::TryOnePath(all_rules().into_iter().map(RewriteProcess::Rule).collect())
+}
+
+fn rule_double() -> RewriteProcess<RegexOp> {
+    rule_as_process(rule_double_star())
+}
+
+// == one pass ==================================================================
+
+#[test]
+fn topdown_and_bottomup_differ_on_nested_redexes() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // Star(Star(Star(a))): top-down rewrites the root once and finds no redex
+    // below; bottom-up rewrites the inner redex, then the root again.
+    let t = star(star(star(atom(b'a', &mut f), &mut f), &mut f), &mut f);
+    let top_down = rewrite(RewriteProcess::topdown(rule_double()), t.clone(), &mut f);
+    assert_eq!(
+        top_down,
+        vec![star(star(atom(b'a', &mut f), &mut f), &mut f)]
+    );
+    let bottom_up = rewrite(RewriteProcess::bottomup(rule_double()), t, &mut f);
+    assert_eq!(bottom_up, vec![star(atom(b'a', &mut f), &mut f)]);
+}
+
+#[test]
+fn topdown_and_bottomup_fail_without_redex() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(atom(b'a', &mut f), atom(b'b', &mut f), &mut f);
+    for strategy in [
+        RewriteProcess::topdown(rule_double()),
+        RewriteProcess::bottomup(rule_double()),
+    ] {
+        assert!(rewrite(strategy, t.clone(), &mut f).is_empty());
+    }
+}
+
+#[test]
+fn repeated_passes_reach_a_normal_form() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(star(atom(b'a', &mut f), &mut f), &mut f), &mut f);
+    for strategy in [
+        RewriteProcess::topdown(rule_double()),
+        RewriteProcess::bottomup(rule_double()),
+    ] {
+        let strategy = RewriteProcess::Repeat(Box::new(strategy));
+        assert_eq!(
+            rewrite(strategy, t.clone(), &mut f),
+            vec![star(atom(b'a', &mut f), &mut f)]
+        );
+    }
+}
+
+#[test]
+fn topdown_rewrites_disjoint_redexes_in_one_pass() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(
+        star(star(atom(b'a', &mut f), &mut f), &mut f),
+        star(star(atom(b'b', &mut f), &mut f), &mut f),
+        &mut f,
+    );
+    let result = rewrite(RewriteProcess::topdown(rule_double()), t, &mut f);
+    assert_eq!(
+        result,
+        vec![alt(
+            star(atom(b'a', &mut f), &mut f),
+            star(atom(b'b', &mut f), &mut f),
+            &mut f
+        )]
+    );
+}
+
+// == single steps ==============================================================
+
+#[test]
+fn steps_are_their_anychild_encodings() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let outermost_encoding = RewriteProcess::TryOnePath(vec![
+        any_rule(),
+        RewriteProcess::AnyChild(
+            SiblingOrder::Leftmost,
+            DepthOrder::Outermost,
+            Box::new(any_rule()),
+        ),
+    ]);
+    let innermost_encoding = RewriteProcess::TryOnePath(vec![
+        RewriteProcess::AnyChild(
+            SiblingOrder::Leftmost,
+            DepthOrder::Innermost,
+            Box::new(any_rule()),
+        ),
+        any_rule(),
+    ]);
+    let outermost_step = RewriteProcess::outermost_step(any_rule());
+    let innermost_step = RewriteProcess::innermost_step(any_rule());
+    for t in generate_regex_terms(200, 17, &mut f) {
+        let executor = RewriteProcessUntracedExecutor::rewrite;
+        assert_eq!(
+            executor(&outermost_step, &t, &mut f),
+            executor(&outermost_encoding, &t, &mut f)
+        );
+        assert_eq!(
+            executor(&innermost_step, &t, &mut f),
+            executor(&innermost_encoding, &t, &mut f)
+        );
+    }
+}
+
+#[test]
+fn parallel_step_rewrites_all_outermost_redexes() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    // The redexes at 0 and 1 are disjoint; the one at 1_0 is below the one at 1.
+    let t = concat(
+        star(star(atom(b'a', &mut f), &mut f), &mut f),
+        star(star(star(atom(b'b', &mut f), &mut f), &mut f), &mut f),
+        &mut f,
+    );
+    let result = rewrite(
+        RewriteProcess::parallel_outermost_step(rule_double()),
+        t,
+        &mut f,
+    );
+    assert_eq!(
+        result,
+        vec![concat(
+            star(atom(b'a', &mut f), &mut f),
+            star(star(atom(b'b', &mut f), &mut f), &mut f),
+            &mut f
+        )]
+    );
+}
+
+#[test]
+fn steps_fail_without_redex() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = concat(atom(b'a', &mut f), atom(b'b', &mut f), &mut f);
+    for strategy in [
+        RewriteProcess::outermost_step(rule_double()),
+        RewriteProcess::innermost_step(rule_double()),
+        RewriteProcess::parallel_outermost_step(rule_double()),
+    ] {
+        assert!(rewrite(strategy, t.clone(), &mut f).is_empty());
+    }
+}
+
+// == normalization =============================================================
+
+#[test]
+fn normalizations_agree_on_random_terms() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let mut normalizer = Normalizer::new(all_rules());
+    for t in generate_regex_terms(200, 23, &mut f) {
+        let expected = vec![normalizer.normalize(&t, &mut f)];
+        assert_eq!(
+            rewrite(RewriteProcess::innermost(any_rule()), t.clone(), &mut f),
+            expected
+        );
+        assert_eq!(
+            rewrite(RewriteProcess::outermost(any_rule()), t.clone(), &mut f),
+            rewrite(normalization_strategy(), t.clone(), &mut f)
+        );
+        assert_eq!(
+            rewrite(RewriteProcess::parallel_outermost(any_rule()), t, &mut f).len(),
+            1
+        );
+    }
+}
+
+// == traced execution ==========================================================
+
+#[test]
+fn traced_topdown_and_bottomup_complete() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(star(atom(b'a', &mut f), &mut f), &mut f), &mut f);
+    let normal_form = star(atom(b'a', &mut f), &mut f);
+    // Top-down takes a pass per redex; bottom-up removes both in one pass.
+    for (strategy, passes) in [
+        (RewriteProcess::topdown(rule_double()), 2),
+        (RewriteProcess::bottomup(rule_double()), 1),
+    ] {
+        let mut executor = RewriteProcessTracedExecutor::new(strategy, t.clone(), f);
+        for _ in 0..passes {
+            assert!(!executor.progress().is_empty());
+        }
+        assert!(executor.progress().is_empty());
+        assert!(executor.get_current_terms().is_empty());
+        assert_eq!(
+            executor.get_completed_terms().to_vec(),
+            vec![normal_form.clone()]
+        );
+        f = executor.into_factory();
+    }
+}
+
+#[test]
+fn traced_parallel_step_lists_every_redex() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(
+        star(star(atom(b'a', &mut f), &mut f), &mut f),
+        star(star(atom(b'b', &mut f), &mut f), &mut f),
+        &mut f,
+    );
+    let strategy = RewriteProcess::parallel_outermost(rule_double());
+    let mut executor = RewriteProcessTracedExecutor::new(strategy, t, f);
+    let applications = executor.progress();
+    assert_eq!(applications.len(), 1);
+    // Repeat → Traverse → Rule.
+    let rule_sp = PositionInRewriteProcess::from_absolute_coordinates(vec![0, 0]);
+    assert_eq!(
+        applications[0].rule_chain,
+        vec![
+            (
+                rule_sp.clone(),
+                PositionInLanguageTerm::from_absolute_coordinates(vec![0])
+            ),
+            (
+                rule_sp,
+                PositionInLanguageTerm::from_absolute_coordinates(vec![1])
+            ),
+        ]
+    );
+    assert!(executor.progress().is_empty());
+}
@@ -0,0 +1,185 @@
+/*
+Copyright 2024 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests for arity-checked construction ([`LanguageTermNode::try_build`]),
+//! [`validate_term`], and the opt-in signature checking of both executors.
+
+mod common;
+
+use hashconsing::HConsign;
+
+use simple_term_rewriter::position::PositionInLanguageTerm;
+use simple_term_rewriter::process::control::RewriteExecutionError;
+use simple_term_rewriter::process::strategy::{DepthOrder, RewriteProcess, SiblingOrder};
+use simple_term_rewriter::process::traced::RewriteProcessTracedExecutor;
+use simple_term_rewriter::process::untraced::RewriteProcessUntracedExecutor;
+use simple_term_rewriter::rule::ClosureRewriteRule;
+use simple_term_rewriter::term::syntax::{
+    validate_term, ArityMismatch, LanguageTermNode, TermFactory,
+};
+
+use common::regex::constructors::*;
+use common::regex::lang::RegexOp;
+
+fn pos(coords: &[usize]) -> PositionInLanguageTerm {
+    PositionInLanguageTerm::from_absolute_coordinates(coords.to_vec())
+}
+
+/// `Star(x) → Star(x, x)`: a buggy rule producing an ill-formed term.
+fn buggy_rule() -> RewriteProcess<RegexOp> {
+    RewriteProcess::Rule(Box::new(ClosureRewriteRule::new(
+        "buggy star",
+        |t, _, _, f| {
+            if t.operator != RegexOp::Star {
+                return None;
+            }
+            let x = t.sub_terms[0].clone();
+            Some(LanguageTermNode::build(
+                RegexOp::Star,
+                vec![x.clone(), x],
+                f,
+            ))
+        },
+    )))
+}
+
+fn buggy_anywhere() -> RewriteProcess<RegexOp> {
+    RewriteProcess::AnyChild(
+        SiblingOrder::Leftmost,
+        DepthOrder::Outermost,
+        Box::new(buggy_rule()),
+    )
+}
+
+// == try_build =================================================================
+
+#[test]
+fn try_build_accepts_well_formed_node() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let t = LanguageTermNode::try_build(RegexOp::Star, vec![a.clone()], &mut f);
+    assert_eq!(t, Ok(star(a, &mut f)));
+}
+
+#[test]
+fn try_build_rejects_wrong_arity() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let t = LanguageTermNode::try_build(RegexOp::Star, vec![a.clone(), a], &mut f);
+    assert_eq!(
+        t,
+        Err(ArityMismatch {
+            operator: RegexOp::Star,
+            expected: 1,
+            found: 2
+        })
+    );
+}
+
+// == validate_term =============================================================
+
+#[test]
+fn validate_well_formed_term() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(star(atom(b'a', &mut f), &mut f), epsilon(&mut f), &mut f);
+    assert_eq!(validate_term(&t), Ok(()));
+}
+
+#[test]
+fn validate_reports_every_ill_formed_position() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let a = atom(b'a', &mut f);
+    let bad_star = LanguageTermNode::build(RegexOp::Star, vec![a.clone(), a.clone()], &mut f);
+    let bad_alt = LanguageTermNode::build(RegexOp::Alt, vec![bad_star.clone()], &mut f);
+    let t = concat(bad_alt, bad_star, &mut f);
+    let ill_formed = validate_term(&t).unwrap_err();
+    let positions: Vec<_> = ill_formed.iter().map(|i| i.position.clone()).collect();
+    assert_eq!(positions, vec![pos(&[0]), pos(&[0, 0]), pos(&[1])]);
+    assert_eq!(ill_formed[0].mismatch.operator, RegexOp::Alt);
+    assert_eq!(ill_formed[0].mismatch.found, 1);
+}
+
+// == untraced executor =========================================================
+
+#[test]
+fn untraced_without_checking_lets_ill_formed_output_through() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(star(atom(b'a', &mut f), &mut f), epsilon(&mut f), &mut f);
+    let results = RewriteProcessUntracedExecutor::new()
+        .execute(&buggy_anywhere(), &t, &mut f)
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(validate_term(&results[0]).is_err());
+}
+
+#[test]
+fn untraced_checking_names_the_offending_rule() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let sa = star(atom(b'a', &mut f), &mut f);
+    let t = alt(sa.clone(), epsilon(&mut f), &mut f);
+    let err = RewriteProcessUntracedExecutor::new()
+        .with_signature_checking(true)
+        .execute(&buggy_anywhere(), &t, &mut f)
+        .unwrap_err();
+    let RewriteExecutionError::IllFormedRuleOutput(violation) = &err else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!(violation.rule_desc, "buggy star");
+    assert_eq!(violation.position, pos(&[0]));
+    assert_eq!(violation.redex, sa);
+    assert_eq!(violation.ill_formed_positions.len(), 1);
+    assert_eq!(violation.ill_formed_positions[0].position, pos(&[]));
+    assert!(format!("{err}").contains("rule 'buggy star' fired at position 0"));
+}
+
+#[test]
+fn untraced_checking_accepts_well_formed_outputs() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(star(atom(b'a', &mut f), &mut f), &mut f);
+    let strategy = common::regex::rules::normalization_strategy();
+    let results = RewriteProcessUntracedExecutor::new()
+        .with_signature_checking(true)
+        .execute(&strategy, &t, &mut f)
+        .unwrap();
+    assert_eq!(results, vec![star(atom(b'a', &mut f), &mut f)]);
+}
+
+// == traced executor ===========================================================
+
+#[test]
+fn traced_checking_reports_error_and_keeps_state() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = alt(star(atom(b'a', &mut f), &mut f), epsilon(&mut f), &mut f);
+    let mut executor = RewriteProcessTracedExecutor::new(buggy_anywhere(), t.clone(), f)
+        .with_signature_checking(true);
+    let err = executor.try_progress().unwrap_err();
+    let RewriteExecutionError::IllFormedRuleOutput(violation) = err else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!(violation.rule_desc, "buggy star");
+    assert_eq!(executor.get_current_terms(), &[t]);
+    assert!(executor.get_completed_terms().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "buggy star")]
+fn traced_progress_panics_on_checked_error() {
+    let mut f: TermFactory<RegexOp> = HConsign::empty();
+    let t = star(atom(b'a', &mut f), &mut f);
+    let mut executor =
+        RewriteProcessTracedExecutor::new(buggy_rule(), t, f).with_signature_checking(true);
+    executor.progress();
+}